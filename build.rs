@@ -0,0 +1,27 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "capi")]
+    generate_c_header();
+}
+
+/// C API（`src/capi.rs`）のヘッダーを`$OUT_DIR/pdf_rasterizer.h`に生成する
+///
+/// ソースツリーは書き換えない。リポジトリの`include/pdf_rasterizer.h`は
+/// C APIを変更したときに`cbindgen`コマンドで明示的に更新する（READMEを参照）。
+#[cfg(feature = "capi")]
+fn generate_c_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set");
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR is not set");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src/capi.rs");
+
+    let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml"))
+        .expect("failed to read cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("failed to generate the C header")
+        .write_to_file(format!("{out_dir}/pdf_rasterizer.h"));
+}
@@ -0,0 +1,328 @@
+/* pdf_rasterizer C API: cbindgenで生成したファイルのため直接編集しないこと */
+
+#ifndef PDF_RASTERIZER_H
+#define PDF_RASTERIZER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * 関数の実行結果
+ */
+typedef enum PdfRasterizerStatus {
+  /**
+   * 成功した
+   */
+  PDF_RASTERIZER_STATUS_OK = 0,
+  /**
+   * 引数がNULLまたは正しくない
+   */
+  PDF_RASTERIZER_STATUS_INVALID_ARGUMENT = 1,
+  /**
+   * 変換に失敗した
+   */
+  PDF_RASTERIZER_STATUS_FAILED = 2,
+  /**
+   * ライブラリ内部の不具合（パニック）で失敗した
+   */
+  PDF_RASTERIZER_STATUS_INTERNAL = 3,
+} PdfRasterizerStatus;
+
+/**
+ * 進捗の種類
+ */
+typedef enum PdfRasterizerProgressKind {
+  /**
+   * PDFのパースが完了した（`total`は変換するページ数）
+   */
+  PDF_RASTERIZER_PROGRESS_KIND_PARSED = 0,
+  /**
+   * ページの画像化が完了した（`index`は0始まり、`bytes`はエンコード後のサイズ）
+   */
+  PDF_RASTERIZER_PROGRESS_KIND_PAGE_RENDERED = 1,
+  /**
+   * 出力PDFへページを書き出した（`done`／`total`ページ）
+   */
+  PDF_RASTERIZER_PROGRESS_KIND_ASSEMBLING = 2,
+  /**
+   * 出力PDFの保存が完了した（`bytes`は出力のサイズ）
+   */
+  PDF_RASTERIZER_PROGRESS_KIND_SAVED = 3,
+} PdfRasterizerProgressKind;
+
+/**
+ * 出力PDFに埋め込む画像の色空間（`pdf_rasterizer_options_set_color_space`に渡す値）
+ */
+typedef enum PdfRasterizerColorSpace {
+  PDF_RASTERIZER_COLOR_SPACE_RGB = 0,
+  PDF_RASTERIZER_COLOR_SPACE_GRAY = 1,
+} PdfRasterizerColorSpace;
+
+/**
+ * 出力PDFに埋め込む画像の圧縮方式（`pdf_rasterizer_options_set_encoding`に渡す値）
+ */
+typedef enum PdfRasterizerEncoding {
+  PDF_RASTERIZER_ENCODING_JPEG = 0,
+  PDF_RASTERIZER_ENCODING_FLATE = 1,
+} PdfRasterizerEncoding;
+
+/**
+ * 出力PDFのページを揃える用紙サイズ（`pdf_rasterizer_options_set_paper`に渡す値）
+ */
+typedef enum PdfRasterizerPaper {
+  /**
+   * 元のページの大きさのまま
+   */
+  PDF_RASTERIZER_PAPER_NONE = 0,
+  PDF_RASTERIZER_PAPER_A4 = 1,
+  PDF_RASTERIZER_PAPER_LETTER = 2,
+} PdfRasterizerPaper;
+
+/**
+ * 用紙サイズに揃えるときの配置方法（`pdf_rasterizer_options_set_paper`に渡す値）
+ */
+typedef enum PdfRasterizerPaperFit {
+  /**
+   * ページ全体を用紙に収める
+   */
+  PDF_RASTERIZER_PAPER_FIT_FIT = 0,
+  /**
+   * 用紙全体を覆い、はみ出した部分は切り取る
+   */
+  PDF_RASTERIZER_PAPER_FIT_FILL = 1,
+} PdfRasterizerPaperFit;
+
+/**
+ * 変換のオプション（中身は公開しない）
+ */
+typedef struct PdfRasterizerOptions PdfRasterizerOptions;
+
+/**
+ * 変換の進捗（種類ごとに使わないフィールドは0）
+ */
+typedef struct PdfRasterizerProgress {
+  enum PdfRasterizerProgressKind kind;
+  size_t index;
+  size_t done;
+  size_t total;
+  uint64_t bytes;
+} PdfRasterizerProgress;
+
+/**
+ * 進捗を受け取る関数
+ *
+ * 並列処理時は複数のスレッドから同時に呼び出される。
+ */
+typedef void (*PdfRasterizerProgressFn)(const struct PdfRasterizerProgress *progress,
+                                        void *user_data);
+
+/**
+ * ライブラリが確保したバイト列（`pdf_rasterizer_buffer_free`で解放する）
+ */
+typedef struct PdfRasterizerBuffer {
+  uint8_t *data;
+  size_t len;
+} PdfRasterizerBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * デフォルト値のオプションを作成する（`pdf_rasterizer_options_free`で解放する）
+ */
+struct PdfRasterizerOptions *pdf_rasterizer_options_new(void);
+
+/**
+ * オプションを解放する
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返したポインタかNULLで、解放済みでないこと。
+ */
+void pdf_rasterizer_options_free(struct PdfRasterizerOptions *options);
+
+/**
+ * 解像度（DPI）を設定する
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_dpi(struct PdfRasterizerOptions *options,
+                                                        uint32_t dpi);
+
+/**
+ * ページごとにDPIを自動で決める場合の上限を設定する（`0`で自動決定をやめる）
+ *
+ * 自動で決める場合、`pdf_rasterizer_options_set_dpi`の値は画像を含まないページのDPIと、
+ * 画像から決めるDPIの下限に使う。
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_auto_dpi(struct PdfRasterizerOptions *options,
+                                                             uint32_t max_dpi);
+
+/**
+ * レンダリングする画像の最大幅・最大高さ（ピクセル）と最大画素数（メガピクセル）を設定する
+ *
+ * それぞれ`0`で制限しない。超えるページは縦横比を保ったまま解像度を下げる。
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_max_size(struct PdfRasterizerOptions *options,
+                                                             uint32_t max_width,
+                                                             uint32_t max_height,
+                                                             float max_megapixels);
+
+/**
+ * 出力PDFのページを揃える用紙サイズと配置方法を設定する
+ *
+ * `paper`は`PdfRasterizerPaper`、`fit`は`PdfRasterizerPaperFit`の値。
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_paper(struct PdfRasterizerOptions *options,
+                                                          uint32_t paper,
+                                                          uint32_t fit);
+
+/**
+ * JPEG品質（1〜100）を設定する
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_quality(struct PdfRasterizerOptions *options,
+                                                            uint8_t quality);
+
+/**
+ * 埋め込む画像の色空間（`PdfRasterizerColorSpace`の値）を設定する
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_color_space(struct PdfRasterizerOptions *options,
+                                                                uint32_t color_space);
+
+/**
+ * 埋め込む画像の圧縮方式（`PdfRasterizerEncoding`の値）を設定する
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_encoding(struct PdfRasterizerOptions *options,
+                                                             uint32_t encoding);
+
+/**
+ * レンダリングに使用するスレッド数を設定する（0はCPUコア数）
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_threads(struct PdfRasterizerOptions *options,
+                                                            size_t threads);
+
+/**
+ * 同時にレンダリングするページのメモリ上限（バイト）を設定する
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_memory_limit(struct PdfRasterizerOptions *options,
+                                                                 uint64_t bytes);
+
+/**
+ * 信頼できない入力PDFに対する上限を設定する
+ *
+ * 入力の最大サイズ（バイト）、最大ページ数、1ページの最大画素数、全ページの画素数の合計の上限、
+ * 1ページのレンダリングにかかった時間の上限（ミリ秒）を指定し、それぞれ`0`で制限しない。
+ * レンダリング時間はページのレンダリングが終わった後に確認するため、レンダリング中には止まらない。
+ * 上限を超える入力は、メモリを確保する前に`PDF_RASTERIZER_STATUS_FAILED`で失敗する。
+ * デフォルトでは1ページの最大画素数だけを2億ピクセルに制限している。
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_limits(struct PdfRasterizerOptions *options,
+                                                           uint64_t max_input_bytes,
+                                                           size_t max_pages,
+                                                           uint64_t max_page_pixels,
+                                                           uint64_t max_total_pixels,
+                                                           uint64_t max_page_render_ms);
+
+/**
+ * 変換するページを「1-3,5」のような1始まりの範囲で指定する（NULLはすべてのページ）
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタ、
+ * `ranges`はNULLか終端のあるUTF-8文字列であること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_pages(struct PdfRasterizerOptions *options,
+                                                          const char *ranges);
+
+/**
+ * 進捗を受け取る関数を設定する（NULLで解除）
+ *
+ * `user_data`はそのまま`progress`に渡される。並列処理時は複数のスレッドから
+ * 同時に呼び出されるため、`user_data`はスレッドセーフに扱うこと。
+ *
+ * # Safety
+ *
+ * `options`は`pdf_rasterizer_options_new`が返した有効なポインタで、
+ * `user_data`は変換が終わるまで有効であること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_options_set_progress(struct PdfRasterizerOptions *options,
+                                                             PdfRasterizerProgressFn progress,
+                                                             void *user_data);
+
+/**
+ * PDFを画像化し、変換後のPDFを`output`に書き込む
+ *
+ * 入力はライブラリ内にコピーしてから変換する。成功した場合、`output`は
+ * `pdf_rasterizer_buffer_free`で解放すること。失敗した場合は`output`を空にする。
+ *
+ * # Safety
+ *
+ * `data`は`len`バイトの読み込み可能な領域、`options`はNULLか
+ * `pdf_rasterizer_options_new`が返した有効なポインタ、`output`は書き込み可能なポインタであること。
+ */
+enum PdfRasterizerStatus pdf_rasterizer_rasterize(const uint8_t *data,
+                                                  size_t len,
+                                                  const struct PdfRasterizerOptions *options,
+                                                  struct PdfRasterizerBuffer *output);
+
+/**
+ * `pdf_rasterizer_rasterize`が返したバイト列を解放する
+ *
+ * # Safety
+ *
+ * `buffer`はこのライブラリが返したもので、解放済みでないこと（`data`がNULLなら何もしない）。
+ */
+void pdf_rasterizer_buffer_free(struct PdfRasterizerBuffer buffer);
+
+/**
+ * このスレッドで最後に失敗した呼び出しのエラーメッセージ（英語）を返す
+ *
+ * 失敗していなければNULLを返す。文字列は次にこのスレッドでライブラリを
+ * 呼び出すまで有効で、解放してはならない。
+ */
+const char *pdf_rasterizer_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PDF_RASTERIZER_H */
@@ -0,0 +1,18 @@
+#!/bin/sh
+# Trunkのビルド後（post_buildフック）に実行し、Service Workerにキャッシュするファイルの
+# 一覧とビルドの識別子を埋め込む。
+#
+# ファイル名にはTrunkがハッシュを付けるため、一覧はビルドのたびに作り直す。
+# 識別子はファイルの内容から計算するので、内容が変わらなければ更新を促さない。
+set -eu
+
+cd "${TRUNK_STAGING_DIR:?Trunkのフックから実行してください}"
+
+assets=$(find . -type f ! -name sw.js | sed 's|^\./||' | LC_ALL=C sort)
+precache=$(printf '%s\n' "$assets" | sed 's/.*/"&"/' | paste -sd, -)
+build=$(printf '%s\n' "$assets" | while read -r file; do cat "$file"; done | cksum | cut -d' ' -f1)
+
+sed -e "s|^const PRECACHE = \[\];|const PRECACHE = [$precache];|" \
+    -e "s|^const BUILD = \"dev\";|const BUILD = \"$build\";|" \
+    sw.js > sw.js.tmp
+mv sw.js.tmp sw.js
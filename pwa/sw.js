@@ -0,0 +1,59 @@
+// PDF RasterizerのService Worker
+//
+// ビルドしたファイルをすべてキャッシュし、ネットワークに接続していなくても
+// アプリを起動・利用できるようにする。PRECACHEとBUILDはTrunkのビルド後に
+// pwa/precache.shが埋め込む。
+
+const PRECACHE = [];
+const BUILD = "dev";
+const CACHE_NAME = `pdf_rasterizer-${BUILD}`;
+
+// 新しいService Workerを有効にするためにページから送られるメッセージ
+const SKIP_WAITING = "skipWaiting";
+
+self.addEventListener("install", (event) => {
+  // 有効化はページ側で利用者が更新を選んだときに行う（SKIP_WAITING）
+  event.waitUntil(
+    caches
+      .open(CACHE_NAME)
+      .then((cache) => cache.addAll(["./", ...PRECACHE])),
+  );
+});
+
+self.addEventListener("activate", (event) => {
+  // 以前のバージョンのキャッシュを削除する
+  event.waitUntil(
+    caches
+      .keys()
+      .then((names) =>
+        Promise.all(
+          names
+            .filter((name) => name.startsWith("pdf_rasterizer-") && name !== CACHE_NAME)
+            .map((name) => caches.delete(name)),
+        ),
+      )
+      .then(() => self.clients.claim()),
+  );
+});
+
+self.addEventListener("message", (event) => {
+  if (event.data === SKIP_WAITING) {
+    self.skipWaiting();
+  }
+});
+
+self.addEventListener("fetch", (event) => {
+  const request = event.request;
+  if (request.method !== "GET" || new URL(request.url).origin !== self.location.origin) {
+    return;
+  }
+
+  // ページの読み込みにはキャッシュしたindex.htmlを返し、更新は利用者の操作で反映する
+  const key = request.mode === "navigate" ? "./" : request;
+  event.respondWith(
+    caches
+      .open(CACHE_NAME)
+      .then((cache) => cache.match(key, { ignoreSearch: request.mode === "navigate" }))
+      .then((cached) => cached || fetch(request)),
+  );
+});
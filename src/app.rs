@@ -1,108 +1,896 @@
 use gloo_console::log;
 use gloo_file::callbacks::FileReader;
 use gloo_file::File;
+use gloo_timers::future::TimeoutFuture;
+use std::rc::Rc;
 use wasm_bindgen::JsCast;
-use web_sys::{Event, HtmlInputElement};
+use web_sys::{DragEvent, Event, HtmlInputElement, HtmlSelectElement, InputEvent};
 use yew::prelude::*;
 
+use crate::i18n::{self, Lang, Message};
+use crate::pwa::{self, PwaEvent};
+use crate::settings::{self, Preset};
+use crate::worker::{
+    hardware_concurrency, RasterizePool, RasterizeWorker, SizeEstimator, WorkerEvent,
+};
+use crate::{
+    format_page_ranges, is_pdf, parse_page_ranges, pdf_info, CancellationToken, Cancelled,
+    ColorSpace, Document, Error, ImageEncoding, PagePreview, PdfInfo, ProgressEvent,
+    RasterizeOptions, SizeEstimate, HEADER_SEARCH_LIMIT,
+};
+
 pub enum Msg {
     FileSelected(Vec<File>),
-    FileLoaded(Vec<u8>),
+    HeaderLoaded(usize, Vec<u8>),
+    FileLoaded(usize, Vec<u8>),
+    FileLoadFailed(usize, String),
     ProcessPdf(u32),
-    PdfProcessed(Result<Vec<u8>, String>),
+    PdfProcessed(Runner, Result<Vec<u8>, Message>),
+    Cancel,
+    PdfCancelled(Runner),
+    WorkerCrashed(Runner, String),
     SetDpi(u32),
-    UpdateProgress(String),
+    SetQuality(u8),
+    SetColorSpace(ColorSpace),
+    SetEncoding(ImageEncoding),
+    SetPageRanges(String),
+    SelectPreset(String),
+    ApplyPreset,
+    SetPresetName(String),
+    SavePreset,
+    DeletePreset,
+    ExportPreset,
+    ImportPreset(File),
+    PresetFileLoaded(String, Result<String, String>),
+    Estimated(usize, Result<SizeEstimate, Error>),
+    SetParallel(bool),
+    SetParallelFiles(bool),
+    UpdateProgress(Runner, ProgressEvent),
+    RemoveItem(usize),
+    ClearQueue,
+    DownloadAll,
+    SetDragOver(bool),
+    ShowPreview(usize),
+    ThumbnailRendered(usize, usize, Result<Vec<u8>, Message>),
+    TogglePage(usize, usize),
+    SelectAllPages(usize, bool),
+    Compare(usize, usize),
+    Compared(usize, usize, Result<PagePreview, Message>),
+    SetCompareMode(CompareMode),
+    SetCompareSlider(u32),
+    CloseComparison,
+    SetLang(Lang),
+    Pwa(PwaEvent),
+    ApplyUpdate,
+    DismissUpdate,
 }
 
-pub struct App {
-    file: Option<Vec<u8>>,
-    processing: bool,
-    result: Option<Result<Vec<u8>, String>>,
+/// サムネイルをレンダリングするDPI（A4で幅150px程度）
+const THUMBNAIL_DPI: u32 = 18;
+
+/// 受け付ける入力ファイルの最大サイズ
+const MAX_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// 変換を実行する場所
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runner {
+    /// 共有のWorker・ワーカープール・メインスレッド（1ファイルずつ変換する）
+    Shared,
+    /// ファイル並列変換用のWorker
+    File(usize),
+}
+
+/// 変換前後の比較の表示方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareMode {
+    /// 左右に並べて表示する
+    SideBySide,
+    /// 重ねて表示し、スライダーで境界を動かす
+    Slider,
+}
+
+/// 変換前後の比較の状態
+enum Comparison {
+    Loading {
+        item: usize,
+        index: usize,
+    },
+    Ready {
+        index: usize,
+        original: ObjectUrl,
+        encoded: ObjectUrl,
+        encoded_bytes: usize,
+    },
+    Failed(Message),
+}
+
+/// 出力サイズの見積もりの状態
+enum Estimate {
+    Pending,
+    Ready(SizeEstimate),
+    Failed(Message),
+}
+
+/// キュー内のファイルの状態
+enum ItemStatus {
+    Loading,
+    Ready,
+    Queued,
+    Processing(Runner),
+    Done(Vec<u8>),
+    Failed(Message),
+    Cancelled,
+}
+
+/// 変換キューのファイル
+struct QueueItem {
+    id: usize,
+    name: String,
+    /// 読み込み中のファイル（ヘッダーを確認してから全体を読み込む）
+    file: Option<File>,
+    data: Option<Vec<u8>>,
+    /// ページ数とPDFのバージョン（読み込み後に取得する）
+    info: Option<PdfInfo>,
+    status: ItemStatus,
+    progress_message: Option<Message>,
+    /// 書き出し済みのページ数と総ページ数
+    progress: Option<(usize, usize)>,
     file_reader: Option<FileReader>,
-    dpi: u32,
-    file_name: Option<String>,
-    progress_message: Option<String>,
+    /// 変換するページ（ページ番号順）
+    selected: Vec<bool>,
+    /// パース済みの文書（Workerを使えない場合にプレビュー時に作成する）
+    document: Option<Rc<Document>>,
+    /// ページのサムネイル（レンダリング済みのもののみ）
+    thumbnails: Vec<Option<ObjectUrl>>,
+    /// 次にレンダリングするサムネイルのページ番号
+    next_thumbnail: usize,
+    /// サムネイルのレンダリングを要求して結果を待っているかどうか
+    thumbnail_pending: bool,
+    /// サムネイルのレンダリングを中断するためのトークン
+    thumbnail_cancel: CancellationToken,
+}
+
+pub struct App {
+    /// 表示言語（切り替えるとlocalStorageへ保存する）
+    lang: Lang,
+    items: Vec<QueueItem>,
+    next_item_id: usize,
+    /// 変換設定（変更するたびにlocalStorageへ保存する）
+    settings: RasterizeOptions,
+    /// ページ範囲の入力欄の内容
+    page_ranges: String,
+    page_ranges_error: Option<Message>,
+    /// 組み込みのプリセットとユーザーが保存したプリセット
+    presets: Vec<Preset>,
+    /// 選択中のプリセットの名前
+    selected_preset: String,
+    /// 保存するプリセットの名前の入力欄の内容
+    preset_name: String,
+    preset_error: Option<Message>,
+    preset_reader: Option<FileReader>,
+    /// 出力サイズを見積もるWorker
+    estimator: Option<SizeEstimator>,
+    /// プレビュー中のファイルの出力サイズの見積もり
+    estimate: Option<Estimate>,
+    /// 見積もりを実行中かどうか
+    estimating: bool,
+    /// 見積もりの実行中に設定が変更されたかどうか
+    estimate_outdated: bool,
+    /// メインスレッドで変換している場合の中断用トークン
+    cancel: Option<CancellationToken>,
+    /// 変換を実行するWorker（起動できなかった場合はメインスレッドで処理する）
+    worker: Option<RasterizeWorker>,
+    /// ページを複数のWorkerで並列に画像化するかどうか
+    parallel: bool,
+    /// 並列処理用のワーカープール（初回の並列変換時に起動する）
+    pool: Option<RasterizePool>,
+    /// 複数のファイルを同時に変換するかどうか
+    parallel_files: bool,
+    /// ファイル並列変換用のWorker（初回のファイル並列変換時に起動する）
+    file_workers: Vec<RasterizeWorker>,
+    /// ファイルがドロップゾーン上にドラッグされているかどうか
+    drag_over: bool,
+    /// プレビューを表示しているファイル
+    preview: Option<usize>,
+    comparison: Option<Comparison>,
+    compare_mode: CompareMode,
+    /// スライダー表示で変換前の画像を表示する幅（%）
+    compare_slider: u32,
+    /// Service Workerがアプリをキャッシュし、オフラインで利用できるかどうか
+    offline_ready: bool,
+    /// 新しいバージョンが有効化を待っているかどうか
+    update_ready: bool,
 }
 
 impl Component for App {
     type Message = Msg;
     type Properties = ();
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        // 切り替えた言語がなければブラウザの言語設定に合わせる
+        let lang = settings::load_lang().unwrap_or_else(browser_lang);
+        set_document_lang(lang);
+
+        // 開発サーバーでは古いキャッシュが残らないようにService Workerを使わない
+        if !cfg!(debug_assertions) {
+            let link = ctx.link().clone();
+            pwa::register(move |event| link.send_message(Msg::Pwa(event)));
+        }
+
+        let worker = {
+            let link = ctx.link().clone();
+            RasterizeWorker::spawn(move |event| {
+                link.send_message(worker_message(Runner::Shared, event))
+            })
+        };
+        let worker = match worker {
+            Ok(worker) => Some(worker),
+            Err(e) => {
+                log!(i18n::tr(lang, "web.worker_unavailable"), e);
+                None
+            }
+        };
+
+        let estimator = {
+            let link = ctx.link().clone();
+            let preview_link = ctx.link().clone();
+            let thumbnail_link = ctx.link().clone();
+            SizeEstimator::spawn(
+                move |key, result| link.send_message(Msg::Estimated(key, result)),
+                move |key, index, result| {
+                    preview_link.send_message(Msg::Compared(
+                        key,
+                        index,
+                        result.map_err(|e| Message::from(&e)),
+                    ))
+                },
+                move |key, index, result| {
+                    thumbnail_link.send_message(Msg::ThumbnailRendered(
+                        key,
+                        index,
+                        result.map_err(|e| Message::from(&e)),
+                    ))
+                },
+            )
+        };
+        let estimator = match estimator {
+            Ok(estimator) => Some(estimator),
+            Err(e) => {
+                log!(i18n::tr(lang, "web.estimator_unavailable"), e);
+                None
+            }
+        };
+
+        // 前回の設定を復元する
+        let settings = settings::load_settings().unwrap_or_default();
+        let page_ranges = settings
+            .pages
+            .as_deref()
+            .map(format_page_ranges)
+            .unwrap_or_default();
+        let presets: Vec<Preset> = settings::builtin_presets()
+            .into_iter()
+            .chain(settings::load_presets())
+            .collect();
+        let selected_preset = presets[0].name.clone();
+
         Self {
-            file: None,
-            processing: false,
-            result: None,
-            file_reader: None,
-            dpi: 72,
-            file_name: None,
-            progress_message: None,
+            lang,
+            items: Vec::new(),
+            next_item_id: 0,
+            settings,
+            page_ranges,
+            page_ranges_error: None,
+            presets,
+            selected_preset,
+            preset_name: String::new(),
+            preset_error: None,
+            preset_reader: None,
+            estimator,
+            estimate: None,
+            estimating: false,
+            estimate_outdated: false,
+            cancel: None,
+            worker,
+            parallel: hardware_concurrency() > 1,
+            pool: None,
+            parallel_files: false,
+            file_workers: Vec::new(),
+            drag_over: false,
+            preview: None,
+            comparison: None,
+            compare_mode: CompareMode::SideBySide,
+            compare_slider: 50,
+            offline_ready: false,
+            update_ready: false,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::FileSelected(files) => {
-                if let Some(file) = files.first() {
-                    self.file_name = Some(file.name());
-                    let link = ctx.link().clone();
-                    let file_reader = gloo_file::callbacks::read_as_bytes(file, move |res| {
-                        if let Ok(data) = res {
-                            link.send_message(Msg::FileLoaded(data));
-                        }
+                self.drag_over = false;
+                for file in files {
+                    let id = self.next_item_id;
+                    self.next_item_id += 1;
+
+                    let mut item = QueueItem {
+                        id,
+                        name: file.name(),
+                        file: None,
+                        data: None,
+                        info: None,
+                        status: ItemStatus::Loading,
+                        progress_message: None,
+                        progress: None,
+                        file_reader: None,
+                        selected: Vec::new(),
+                        document: None,
+                        thumbnails: Vec::new(),
+                        next_thumbnail: 0,
+                        thumbnail_pending: false,
+                        thumbnail_cancel: CancellationToken::new(),
+                    };
+
+                    if file.size() == 0 {
+                        item.status = ItemStatus::Failed(Message::new("web.empty_file"));
+                    } else if file.size() > MAX_FILE_SIZE {
+                        item.status = ItemStatus::Failed(Message::with_args(
+                            "web.file_too_large",
+                            &[
+                                &format!("{:.1}", file.size() as f64 / 1024.0 / 1024.0),
+                                &(MAX_FILE_SIZE / 1024 / 1024),
+                            ],
+                        ));
+                    } else {
+                        // ファイル全体を読み込む前に先頭だけ読んでPDFかどうかを確認する
+                        let header = file.slice(0, HEADER_SEARCH_LIMIT as u64);
+                        let link = ctx.link().clone();
+                        item.file_reader =
+                            Some(gloo_file::callbacks::read_as_bytes(&header, move |res| {
+                                link.send_message(match res {
+                                    Ok(data) => Msg::HeaderLoaded(id, data),
+                                    Err(e) => Msg::FileLoadFailed(id, e.to_string()),
+                                });
+                            }));
+                        item.file = Some(file);
+                    }
+
+                    self.items.push(item);
+                }
+                true
+            }
+            Msg::HeaderLoaded(id, header) => {
+                let Some(item) = self.items.iter_mut().find(|item| item.id == id) else {
+                    return false;
+                };
+                let Some(file) = item.file.take() else {
+                    return false;
+                };
+
+                if !is_pdf(&header) {
+                    item.status = ItemStatus::Failed(Message::from(&Error::NotPdf));
+                    item.file_reader = None;
+                    return true;
+                }
+
+                let link = ctx.link().clone();
+                item.file_reader = Some(gloo_file::callbacks::read_as_bytes(&file, move |res| {
+                    link.send_message(match res {
+                        Ok(data) => Msg::FileLoaded(id, data),
+                        Err(e) => Msg::FileLoadFailed(id, e.to_string()),
                     });
-                    self.file_reader = Some(file_reader);
+                }));
+                false
+            }
+            Msg::FileLoaded(id, data) => {
+                let Some(item) = self.items.iter_mut().find(|item| item.id == id) else {
+                    return false;
+                };
+                item.file_reader = None;
+                match pdf_info(data.clone()) {
+                    Ok(info) => {
+                        log!(i18n::trf(
+                            self.lang,
+                            "web.file_loaded",
+                            &[&item.name, &info.pages, &info.version]
+                        ));
+                        item.selected = (0..info.pages)
+                            .map(|index| {
+                                self.settings
+                                    .pages
+                                    .as_ref()
+                                    .is_none_or(|pages| pages.contains(&index))
+                            })
+                            .collect();
+                        item.info = Some(info);
+                        item.data = Some(data);
+                        item.status = ItemStatus::Ready;
+                    }
+                    Err(e) => {
+                        item.status = ItemStatus::Failed(Message::from_error(&e));
+                        return true;
+                    }
+                }
+
+                // 最初に読み込んだファイルのプレビューを自動的に表示する
+                if self.preview.is_none() {
+                    self.show_preview(ctx, id);
                 }
                 true
             }
-            Msg::FileLoaded(data) => {
-                log!("ファイルを読み込みました");
-                self.file = Some(data);
-                self.result = None;
-                self.progress_message = None;
+            Msg::FileLoadFailed(id, message) => {
+                if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+                    item.status =
+                        ItemStatus::Failed(Message::with_args("web.read_error", &[&message]));
+                    item.file = None;
+                    item.file_reader = None;
+                }
                 true
             }
             Msg::ProcessPdf(dpi) => {
-                if let Some(data) = &self.file {
-                    self.processing = true;
-                    self.result = None;
-                    self.progress_message = Some("処理を開始しています...".to_string());
-                    log!(format!("PDFを処理中... (DPI: {})", dpi));
-
-                    let data = data.clone();
-                    let link = ctx.link().clone();
-
-                    // WASMで処理を実行
-                    wasm_bindgen_futures::spawn_local(async move {
-                        let result = crate::rasterize_pdf_with_progress(data, dpi, {
-                            let link = link.clone();
-                            move |msg| {
-                                link.send_message(Msg::UpdateProgress(msg));
-                            }
-                        })
-                        .await
-                        .map_err(|e| format!("エラー: {}", e));
-                        link.send_message(Msg::PdfProcessed(result));
-                    });
+                log!(self.trf("web.processing_log", &[&dpi]));
+                for item in &mut self.items {
+                    if item.data.is_some() && !item.is_active() {
+                        item.status = ItemStatus::Queued;
+                        item.progress = None;
+                        item.progress_message = None;
+                    }
+                }
+                self.schedule(ctx);
+                true
+            }
+            Msg::PdfProcessed(runner, result) => {
+                let lang = self.lang;
+                if let Some(item) = self.item_for(runner) {
+                    match &result {
+                        Ok(_) => log!(i18n::trf(lang, "web.done_log", &[&item.name])),
+                        Err(e) => log!(i18n::trf(lang, "web.error_log", &[&e.localize(lang)])),
+                    }
+                    item.status = match result {
+                        Ok(data) => ItemStatus::Done(data),
+                        Err(e) => ItemStatus::Failed(e),
+                    };
+                    item.progress = None;
+                    item.progress_message = None;
+                }
+                if runner == Runner::Shared {
+                    self.cancel = None;
+                }
+                self.schedule(ctx);
+                true
+            }
+            Msg::Cancel => {
+                log!(self.tr("web.cancel_log"));
+                for item in &mut self.items {
+                    match item.status {
+                        ItemStatus::Queued => item.status = ItemStatus::Cancelled,
+                        ItemStatus::Processing(_) => {
+                            item.progress_message = Some(Message::new(CANCELLING_MESSAGE))
+                        }
+                        _ => {}
+                    }
+                }
+
+                if let Some(cancel) = &self.cancel {
+                    cancel.cancel();
+                }
+                if let Some(worker) = &self.worker {
+                    worker.cancel();
+                }
+                if let Some(pool) = &self.pool {
+                    pool.cancel();
+                }
+                for worker in &self.file_workers {
+                    worker.cancel();
+                }
+                true
+            }
+            Msg::PdfCancelled(runner) => {
+                let lang = self.lang;
+                if let Some(item) = self.item_for(runner) {
+                    log!(i18n::trf(lang, "web.cancelled_log", &[&item.name]));
+                    item.status = ItemStatus::Cancelled;
+                    item.progress = None;
+                    item.progress_message = None;
+                }
+                if runner == Runner::Shared {
+                    self.cancel = None;
                 }
+                self.schedule(ctx);
                 true
             }
-            Msg::PdfProcessed(result) => {
-                self.processing = false;
-                self.progress_message = None;
-                match &result {
-                    Ok(_) => log!("PDF処理が完了しました"),
-                    Err(e) => log!(format!("エラー: {}", e)),
+            Msg::WorkerCrashed(runner, message) => {
+                log!(self.trf("web.worker_crashed_log", &[&message]));
+                // 止まったWorkerは使わず、ワーカープール→共有のWorker→メインスレッドの順に切り替える
+                let runners: Vec<Runner> = match runner {
+                    Runner::Shared => {
+                        if self.pool.is_some() && self.parallel {
+                            self.pool = None;
+                            self.parallel = false;
+                        } else {
+                            self.worker = None;
+                        }
+                        vec![Runner::Shared]
+                    }
+                    Runner::File(_) => {
+                        self.file_workers.clear();
+                        self.parallel_files = false;
+                        self.items
+                            .iter()
+                            .filter_map(|item| match item.status {
+                                ItemStatus::Processing(runner @ Runner::File(_)) => Some(runner),
+                                _ => None,
+                            })
+                            .collect()
+                    }
+                };
+                for runner in runners {
+                    if let Some(item) = self.item_for(runner) {
+                        let cancelling = item
+                            .progress_message
+                            .as_ref()
+                            .is_some_and(|message| message.key() == CANCELLING_MESSAGE);
+                        item.status = if cancelling {
+                            ItemStatus::Cancelled
+                        } else {
+                            ItemStatus::Queued
+                        };
+                        item.progress = None;
+                        item.progress_message = None;
+                    }
                 }
-                self.result = Some(result);
+                self.schedule(ctx);
                 true
             }
             Msg::SetDpi(dpi) => {
-                self.dpi = dpi;
+                self.settings.dpi = dpi;
+                self.settings_changed();
+                true
+            }
+            Msg::SetQuality(quality) => {
+                self.settings.quality = quality;
+                self.settings_changed();
+                true
+            }
+            Msg::SetColorSpace(color_space) => {
+                self.settings.color_space = color_space;
+                self.settings_changed();
+                true
+            }
+            Msg::SetEncoding(encoding) => {
+                self.settings.encoding = encoding;
+                self.settings_changed();
+                true
+            }
+            Msg::SetPageRanges(page_ranges) => {
+                let pages = if page_ranges.trim().is_empty() {
+                    Ok(None)
+                } else {
+                    parse_page_ranges(&page_ranges).map(Some)
+                };
+                self.page_ranges = page_ranges;
+
+                match pages {
+                    Ok(pages) => {
+                        self.page_ranges_error = None;
+                        self.set_pages(pages);
+                        self.settings_changed();
+                    }
+                    Err(e) => self.page_ranges_error = Some(Message::from_error(&e)),
+                }
+                true
+            }
+            Msg::SelectPreset(name) => {
+                self.selected_preset = name;
+                self.preset_error = None;
+                true
+            }
+            Msg::ApplyPreset => {
+                let Some(preset) = self.preset(&self.selected_preset) else {
+                    return false;
+                };
+                log!(self.trf("web.preset_applied", &[&preset.name]));
+                // 画面で変更できない項目（最大サイズ・用紙・上限など）も含めてプリセットの設定にする
+                let options = preset.options.clone();
+                self.settings = options.clone();
+                self.page_ranges = options
+                    .pages
+                    .as_deref()
+                    .map(format_page_ranges)
+                    .unwrap_or_default();
+                self.page_ranges_error = None;
+                self.set_pages(options.pages);
+                self.settings_changed();
+                true
+            }
+            Msg::SetPresetName(name) => {
+                self.preset_name = name;
+                false
+            }
+            Msg::SavePreset => {
+                let name = self.preset_name.trim().to_string();
+                if name.is_empty() {
+                    self.preset_error = Some(Message::new("web.preset_name_required"));
+                    return true;
+                }
+                let preset = Preset {
+                    name: name.clone(),
+                    options: self.settings.clone(),
+                    builtin: false,
+                };
+                if let Err(e) = self.add_preset(preset) {
+                    self.preset_error = Some(e);
+                    return true;
+                }
+                self.preset_name.clear();
+                true
+            }
+            Msg::DeletePreset => {
+                let name = self.selected_preset.clone();
+                self.presets
+                    .retain(|preset| preset.builtin || preset.name != name);
+                settings::save_presets(&self.presets);
+                self.selected_preset = self.presets[0].name.clone();
+                self.preset_error = None;
+                true
+            }
+            Msg::ExportPreset => {
+                if let Some(preset) = self.preset(&self.selected_preset) {
+                    download_file(
+                        settings::export_preset(preset).as_bytes(),
+                        &format!("{}.json", preset.name),
+                        "application/json",
+                    );
+                }
+                false
+            }
+            Msg::ImportPreset(file) => {
+                // ファイル名（拡張子を除く）をプリセット名にする
+                let file_name = file.name();
+                let name = file_name
+                    .strip_suffix(".json")
+                    .unwrap_or(&file_name)
+                    .to_string();
+                let link = ctx.link().clone();
+                self.preset_reader = Some(gloo_file::callbacks::read_as_text(&file, move |res| {
+                    link.send_message(Msg::PresetFileLoaded(name, res.map_err(|e| e.to_string())))
+                }));
+                false
+            }
+            Msg::PresetFileLoaded(name, result) => {
+                self.preset_reader = None;
+                let preset = result
+                    .map_err(|e| Message::with_args("web.read_error", &[&e]))
+                    .and_then(|json| {
+                        settings::import_preset(&name, &json)
+                            .map_err(|e| Message::with_args("web.preset_invalid", &[&e]))
+                    });
+                match preset.and_then(|preset| self.add_preset(preset)) {
+                    Ok(()) => log!(self.trf("web.preset_imported", &[&name])),
+                    Err(e) => self.preset_error = Some(e),
+                }
+                true
+            }
+            Msg::Estimated(key, result) => {
+                self.estimating = false;
+                // 実行中に設定が変わっていれば最新の設定で見積もり直す
+                if self.estimate_outdated {
+                    self.request_estimate();
+                    return false;
+                }
+                if self.preview != Some(key) {
+                    return false;
+                }
+                self.estimate = Some(match result {
+                    Ok(estimate) => Estimate::Ready(estimate),
+                    Err(e) => Estimate::Failed(Message::from(&e)),
+                });
+                true
+            }
+            Msg::SetParallel(parallel) => {
+                self.parallel = parallel;
+                true
+            }
+            Msg::SetParallelFiles(parallel_files) => {
+                self.parallel_files = parallel_files;
+                true
+            }
+            Msg::UpdateProgress(runner, event) => {
+                let Some(item) = self.item_for(runner) else {
+                    return false;
+                };
+                // キャンセル中は進捗表示を更新しない
+                if item
+                    .progress_message
+                    .as_ref()
+                    .is_some_and(|message| message.key() == CANCELLING_MESSAGE)
+                {
+                    return false;
+                }
+                let total = item.progress.map(|(_, total)| total).unwrap_or(0);
+                let message = match event {
+                    ProgressEvent::Parsed { pages } => {
+                        item.progress = Some((0, pages));
+                        Message::with_args("web.progress_parsed", &[&pages])
+                    }
+                    ProgressEvent::PageRendered { index, .. } => {
+                        Message::with_args("web.progress_rendered", &[&(index + 1), &total])
+                    }
+                    ProgressEvent::Assembling { done, total } => {
+                        item.progress = Some((done, total));
+                        Message::with_args("web.progress_assembling", &[&done, &total])
+                    }
+                    ProgressEvent::Saved { bytes } => Message::with_args(
+                        "web.progress_saved",
+                        &[&format!("{:.1}", bytes as f64 / 1024.0 / 1024.0)],
+                    ),
+                };
+                item.progress_message = Some(message);
+                true
+            }
+            Msg::RemoveItem(id) => {
+                self.items.retain(|item| item.id != id || item.is_active());
+                self.forget_removed_items();
+                true
+            }
+            Msg::ClearQueue => {
+                self.items.retain(|item| item.is_active());
+                self.forget_removed_items();
+                true
+            }
+            Msg::DownloadAll => {
+                let files: Vec<(String, &[u8])> = self
+                    .items
+                    .iter()
+                    .filter_map(|item| match &item.status {
+                        ItemStatus::Done(data) => Some((output_file_name(&item.name), &data[..])),
+                        _ => None,
+                    })
+                    .collect();
+
+                match crate::archive::create_zip(&files) {
+                    Ok(zip) => download_file(&zip, "rasterized.zip", "application/zip"),
+                    Err(e) => {
+                        log!(self.trf("web.error_log", &[&i18n::error_message(self.lang, &e)]))
+                    }
+                }
+                false
+            }
+            Msg::SetDragOver(drag_over) => {
+                let changed = self.drag_over != drag_over;
+                self.drag_over = drag_over;
+                changed
+            }
+            Msg::ShowPreview(id) => {
+                if self.preview != Some(id) {
+                    self.comparison = None;
+                }
+                self.show_preview(ctx, id);
+                true
+            }
+            Msg::ThumbnailRendered(id, index, result) => {
+                let Some(item) = self.items.iter_mut().find(|item| item.id == id) else {
+                    return false;
+                };
+                item.thumbnail_pending = false;
+                match result {
+                    Ok(png) => {
+                        if let Some(thumbnail) = item.thumbnails.get_mut(index) {
+                            *thumbnail = ObjectUrl::new(&png, "image/png");
+                        }
+                    }
+                    Err(e) => log!(self.trf("web.thumbnail_failed", &[&e.localize(self.lang)])),
+                }
+                self.request_thumbnail(ctx, id);
+                self.preview == Some(id)
+            }
+            Msg::TogglePage(id, index) => {
+                if let Some(selected) = self
+                    .items
+                    .iter_mut()
+                    .find(|item| item.id == id)
+                    .and_then(|item| item.selected.get_mut(index))
+                {
+                    *selected = !*selected;
+                }
+                self.request_estimate();
+                true
+            }
+            Msg::SelectAllPages(id, select) => {
+                if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+                    item.selected.fill(select);
+                }
+                self.request_estimate();
+                true
+            }
+            Msg::Compare(id, index) => {
+                let Some(item) = self.items.iter().find(|item| item.id == id) else {
+                    return false;
+                };
+                let options = self.options();
+
+                // 全解像度のレンダリングは時間がかかるため、見積もり用のWorkerで実行する
+                if let (Some(estimator), Some(data)) = (&self.estimator, &item.data) {
+                    estimator.preview(id, index, data, &options);
+                    self.comparison = Some(Comparison::Loading { item: id, index });
+                    return true;
+                }
+
+                // Workerを起動できなかった場合はメインスレッドで処理する
+                let Some(document) = item.document.clone() else {
+                    return false;
+                };
+                let link = ctx.link().clone();
+                self.comparison = Some(Comparison::Loading { item: id, index });
+
+                wasm_bindgen_futures::spawn_local(async move {
+                    // 読み込み中の表示を更新するために少し待機
+                    TimeoutFuture::new(10).await;
+                    let result = document
+                        .preview_page(index, &options)
+                        .map_err(|e| Message::from_error(&e));
+                    link.send_message(Msg::Compared(id, index, result));
+                });
+                true
+            }
+            Msg::Compared(id, index, result) => {
+                // 比較を閉じたか、別のページを選び直した場合は結果を捨てる
+                if !matches!(self.comparison, Some(Comparison::Loading { item, index: i }) if item == id && i == index)
+                {
+                    return false;
+                }
+                self.comparison = Some(match result {
+                    Ok(preview) => match (
+                        ObjectUrl::new(&preview.original_png, "image/png"),
+                        ObjectUrl::new(&preview.encoded_image, preview.encoded_mime_type),
+                    ) {
+                        (Some(original), Some(encoded)) => Comparison::Ready {
+                            index,
+                            original,
+                            encoded,
+                            encoded_bytes: preview.encoded_bytes,
+                        },
+                        _ => Comparison::Failed(Message::new("web.image_unavailable")),
+                    },
+                    Err(e) => Comparison::Failed(e),
+                });
+                true
+            }
+            Msg::SetCompareMode(mode) => {
+                self.compare_mode = mode;
+                true
+            }
+            Msg::SetCompareSlider(value) => {
+                self.compare_slider = value.min(100);
+                true
+            }
+            Msg::CloseComparison => {
+                self.comparison = None;
+                true
+            }
+            Msg::SetLang(lang) => {
+                self.lang = lang;
+                settings::save_lang(lang);
+                set_document_lang(lang);
                 true
             }
-            Msg::UpdateProgress(message) => {
-                self.progress_message = Some(message);
+            Msg::Pwa(PwaEvent::OfflineReady) => {
+                self.offline_ready = true;
+                true
+            }
+            Msg::Pwa(PwaEvent::UpdateReady) => {
+                self.offline_ready = true;
+                self.update_ready = true;
+                true
+            }
+            Msg::ApplyUpdate => {
+                // 再読み込みで変換が中断されないよう、変換中はボタンを無効にしている
+                pwa::apply_update();
+                false
+            }
+            Msg::DismissUpdate => {
+                self.update_ready = false;
                 true
             }
         }
@@ -114,24 +902,72 @@ impl Component for App {
             Callback::from(move |e: Event| {
                 let input: HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
                 if let Some(files) = input.files() {
-                    let file_list: Vec<File> = js_sys::try_iter(&files)
-                        .unwrap()
-                        .unwrap()
-                        .map(|v| File::from(web_sys::File::from(v.unwrap())))
-                        .collect();
-                    link.send_message(Msg::FileSelected(file_list));
+                    link.send_message(Msg::FileSelected(file_list(files)));
+                }
+                // 同じファイルを続けて追加できるように選択をリセットする
+                input.set_value("");
+            })
+        };
+
+        let on_drag_over = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: DragEvent| {
+                // preventDefaultしないとドロップを受け付けない
+                e.prevent_default();
+                link.send_message(Msg::SetDragOver(true));
+            })
+        };
+
+        let on_drag_leave = {
+            let link = ctx.link().clone();
+            Callback::from(move |_: DragEvent| {
+                link.send_message(Msg::SetDragOver(false));
+            })
+        };
+
+        let on_drop = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: DragEvent| {
+                // ブラウザがファイルを開いてしまわないようにする
+                e.prevent_default();
+                match e.data_transfer().and_then(|transfer| transfer.files()) {
+                    Some(files) => link.send_message(Msg::FileSelected(file_list(files))),
+                    None => link.send_message(Msg::SetDragOver(false)),
                 }
             })
         };
 
         let on_process = {
             let link = ctx.link().clone();
-            let dpi = self.dpi;
+            let dpi = self.settings.dpi;
             Callback::from(move |_| {
                 link.send_message(Msg::ProcessPdf(dpi));
             })
         };
 
+        let on_cancel = {
+            let link = ctx.link().clone();
+            Callback::from(move |_| {
+                link.send_message(Msg::Cancel);
+            })
+        };
+
+        let on_parallel_change = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: Event| {
+                let input: HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+                link.send_message(Msg::SetParallel(input.checked()));
+            })
+        };
+
+        let on_parallel_files_change = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: Event| {
+                let input: HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+                link.send_message(Msg::SetParallelFiles(input.checked()));
+            })
+        };
+
         let on_dpi_change = {
             let link = ctx.link().clone();
             Callback::from(move |e: Event| {
@@ -142,62 +978,97 @@ impl Component for App {
             })
         };
 
-        let download_button = if let Some(Ok(data)) = &self.result {
-            let data = data.clone();
-            let file_name = self
-                .file_name
-                .as_ref()
-                .and_then(|name| name.strip_suffix(".pdf"))
-                .map(|base| format!("{}_rasterized.pdf", base))
-                .unwrap_or_else(|| "output.pdf".to_string());
+        let on_page_ranges_change = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: Event| {
+                let input: HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+                link.send_message(Msg::SetPageRanges(input.value()));
+            })
+        };
 
-            html! {
-                <button
-                    class="download-button"
-                    onclick={Callback::from(move |_| {
-                        download_pdf(&data, &file_name);
-                    })}
-                >
-                    { "ダウンロード" }
-                </button>
-            }
-        } else {
-            html! {}
+        let on_quality_input = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: InputEvent| {
+                let input: HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+                if let Ok(value) = input.value().parse::<u8>() {
+                    link.send_message(Msg::SetQuality(value));
+                }
+            })
+        };
+
+        let on_color_space_change = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: Event| {
+                let select: HtmlSelectElement = e.target().unwrap().dyn_into().unwrap();
+                link.send_message(Msg::SetColorSpace(match select.value().as_str() {
+                    "gray" => ColorSpace::Gray,
+                    _ => ColorSpace::Rgb,
+                }));
+            })
+        };
+
+        let on_encoding_change = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: Event| {
+                let select: HtmlSelectElement = e.target().unwrap().dyn_into().unwrap();
+                link.send_message(Msg::SetEncoding(match select.value().as_str() {
+                    "flate" => ImageEncoding::Flate,
+                    _ => ImageEncoding::Jpeg,
+                }));
+            })
         };
 
+        let processing = self.is_processing();
+        let has_input = self.items.iter().any(|item| item.data.is_some());
+        let done_count = self
+            .items
+            .iter()
+            .filter(|item| matches!(item.status, ItemStatus::Done(_)))
+            .count();
+
         html! {
             <div class="container">
                 <header class="header">
                     <h1>{ "PDF Rasterizer" }</h1>
-                    <p class="subtitle">{ "PDFを画像化してから再度PDFに変換するツール" }</p>
+                    <p class="subtitle">{ self.tr("app.description") }</p>
+                    { self.view_lang_toggle(ctx) }
                 </header>
 
                 <main class="main">
-                    <div class="upload-section">
+                    { self.view_update_prompt(ctx) }
+
+                    <div
+                        class={classes!("upload-section", "drop-zone", self.drag_over.then_some("drag-over"))}
+                        ondragover={on_drag_over}
+                        ondragleave={on_drag_leave}
+                        ondrop={on_drop}
+                    >
                         <label class="file-label">
                             <input
                                 type="file"
                                 accept=".pdf"
+                                multiple=true
                                 onchange={on_file_change}
                                 class="file-input"
                             />
-                            <span class="file-button">{ "PDFを選択" }</span>
+                            <span class="file-button">{ self.tr("web.select_pdf") }</span>
                         </label>
-                        {
-                            if let Some(name) = &self.file_name {
-                                html! { <p class="file-name">{ format!("選択: {}", name) }</p> }
-                            } else {
-                                html! {}
-                            }
-                        }
+                        <p class="file-hint">
+                            { self.trf("web.drop_hint", &[&(MAX_FILE_SIZE / 1024 / 1024)]) }
+                        </p>
                     </div>
 
+                    { self.view_queue(ctx) }
+
+                    { self.view_preview(ctx) }
+
                     <div class="settings-section">
+                        { self.view_presets(ctx) }
                         <label class="dpi-label">
                             { "DPI: " }
                             <input
                                 type="number"
-                                value={self.dpi.to_string()}
+                                value={self.settings.dpi.to_string()}
                                 onchange={on_dpi_change}
                                 min="72"
                                 max="600"
@@ -205,82 +1076,1049 @@ impl Component for App {
                                 class="dpi-input"
                             />
                         </label>
-                        <p class="dpi-hint">{ "解像度を指定します（72-600）" }</p>
+                        <p class="dpi-hint">{ self.tr("web.dpi_hint") }</p>
+                        <label class="setting-label">
+                            { self.tr("web.page_ranges") }
+                            <input
+                                type="text"
+                                class="page-ranges-input"
+                                placeholder={self.tr("web.page_ranges_placeholder")}
+                                value={self.page_ranges.clone()}
+                                onchange={on_page_ranges_change}
+                            />
+                        </label>
+                        {
+                            if let Some(e) = &self.page_ranges_error {
+                                html! { <p class="setting-error">{ e.localize(self.lang) }</p> }
+                            } else {
+                                html! {}
+                            }
+                        }
+                        <label class="setting-label">
+                            { self.tr("web.encoding") }
+                            <select class="setting-select" onchange={on_encoding_change}>
+                                <option value="jpeg" selected={self.settings.encoding == ImageEncoding::Jpeg}>
+                                    { self.tr("web.encoding_jpeg") }
+                                </option>
+                                <option value="flate" selected={self.settings.encoding == ImageEncoding::Flate}>
+                                    { self.tr("web.encoding_flate") }
+                                </option>
+                            </select>
+                        </label>
+                        <label class="setting-label">
+                            { self.tr("web.quality") }
+                            <input
+                                type="range"
+                                class="quality-input"
+                                min="1"
+                                max="100"
+                                value={self.settings.quality.to_string()}
+                                oninput={on_quality_input}
+                                disabled={self.settings.encoding != ImageEncoding::Jpeg}
+                            />
+                            <span class="quality-value">{ self.settings.quality }</span>
+                        </label>
+                        <label class="setting-label">
+                            { self.tr("web.color") }
+                            <select class="setting-select" onchange={on_color_space_change}>
+                                <option value="rgb" selected={self.settings.color_space == ColorSpace::Rgb}>
+                                    { self.tr("web.color_rgb") }
+                                </option>
+                                <option value="gray" selected={self.settings.color_space == ColorSpace::Gray}>
+                                    { self.tr("web.color_gray") }
+                                </option>
+                            </select>
+                        </label>
+                        { self.view_estimate() }
+                        <label class="parallel-label">
+                            <input
+                                type="checkbox"
+                                checked={self.parallel}
+                                onchange={on_parallel_change}
+                                disabled={processing}
+                            />
+                            { self.trf("web.parallel", &[&hardware_concurrency()]) }
+                        </label>
+                        <label class="parallel-label">
+                            <input
+                                type="checkbox"
+                                checked={self.parallel_files}
+                                onchange={on_parallel_files_change}
+                                disabled={processing}
+                            />
+                            { self.tr("web.parallel_files") }
+                        </label>
                     </div>
 
                     <div class="action-section">
                         <button
                             class="process-button"
                             onclick={on_process}
-                            disabled={self.file.is_none() || self.processing}
+                            disabled={!has_input || processing}
                         >
                             {
-                                if self.processing {
-                                    "処理中..."
+                                if processing {
+                                    self.tr("web.processing")
                                 } else {
-                                    "変換"
+                                    self.tr("web.convert")
                                 }
                             }
                         </button>
-                    </div>
-
-                    {
-                        if let Some(progress) = &self.progress_message {
-                            html! {
-                                <div class="progress">
-                                    <div class="progress-spinner"></div>
-                                    <p>{ progress }</p>
-                                </div>
+                        {
+                            if processing {
+                                html! {
+                                    <button class="cancel-button" onclick={on_cancel}>
+                                        { self.tr("web.cancel") }
+                                    </button>
+                                }
+                            } else {
+                                html! {}
                             }
-                        } else {
-                            html! {}
                         }
-                    }
+                    </div>
 
                     {
-                        if let Some(Err(e)) = &self.result {
+                        if done_count > 0 && !processing {
                             html! {
-                                <div class="error">
-                                    <p>{ e }</p>
+                                <div class="success">
+                                    <p>{ self.trf("web.done_count", &[&done_count]) }</p>
+                                    <button
+                                        class="download-button"
+                                        onclick={ctx.link().callback(|_| Msg::DownloadAll)}
+                                    >
+                                        { self.tr("web.download_zip") }
+                                    </button>
                                 </div>
                             }
                         } else {
                             html! {}
                         }
                     }
+                </main>
 
+                <footer class="footer">
+                    <span>{ self.trf("web.version", &[&env!("CARGO_PKG_VERSION")]) }</span>
                     {
-                        if self.result.as_ref().map(|r| r.is_ok()).unwrap_or(false) {
-                            html! {
-                                <div class="success">
-                                    <p>{ "✓ 変換完了" }</p>
-                                    { download_button }
-                                </div>
-                            }
+                        if self.offline_ready {
+                            html! { <span class="offline-ready">{ self.tr("web.offline_ready") }</span> }
                         } else {
                             html! {}
                         }
                     }
-                </main>
+                </footer>
             </div>
         }
     }
 }
 
-fn download_pdf(data: &[u8], filename: &str) {
-    let window = web_sys::window().unwrap();
-    let document = window.document().unwrap();
+/// キャンセル要求後に表示するメッセージのキー
+const CANCELLING_MESSAGE: &str = "web.cancelling";
 
-    // Blobを作成
+impl App {
+    /// 表示言語の文言
+    fn tr(&self, key: &'static str) -> &'static str {
+        i18n::tr(self.lang, key)
+    }
+
+    /// 値を埋め込んだ表示言語の文言
+    fn trf(&self, key: &'static str, args: &[&dyn std::fmt::Display]) -> String {
+        i18n::trf(self.lang, key, args)
+    }
+
+    /// 画面の設定から変換オプションを作成する
+    ///
+    /// 変換するページはファイルごとに選択するため含めない。
+    fn options(&self) -> RasterizeOptions {
+        RasterizeOptions {
+            pages: None,
+            cancel: CancellationToken::new(),
+            ..self.settings.clone()
+        }
+    }
+
+    /// 設定を保存し、出力サイズを見積もり直す
+    fn settings_changed(&mut self) {
+        settings::save_settings(&self.settings);
+        self.request_estimate();
+    }
+
+    /// ページ範囲を設定し、変換待ちのファイルのページ選択に反映する
+    fn set_pages(&mut self, pages: Option<Vec<usize>>) {
+        for item in self.items.iter_mut().filter(|item| !item.is_active()) {
+            for (index, selected) in item.selected.iter_mut().enumerate() {
+                *selected = pages.as_ref().is_none_or(|pages| pages.contains(&index));
+            }
+        }
+        self.settings.pages = pages;
+    }
+
+    fn preset(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// プリセットを追加して保存する（同じ名前のプリセットは置き換える）
+    fn add_preset(&mut self, preset: Preset) -> Result<(), Message> {
+        match self.presets.iter().position(|p| p.name == preset.name) {
+            Some(index) if self.presets[index].builtin => {
+                return Err(Message::with_args("web.preset_builtin", &[&preset.name]));
+            }
+            Some(index) => self.presets[index] = preset.clone(),
+            None => self.presets.push(preset.clone()),
+        }
+        settings::save_presets(&self.presets);
+        self.selected_preset = preset.name;
+        self.preset_error = None;
+        Ok(())
+    }
+
+    /// プレビュー中のファイルの出力サイズをバックグラウンドで見積もる
+    ///
+    /// 見積もりの実行中に呼ばれた場合は、完了後に最新の設定で見積もり直す。
+    fn request_estimate(&mut self) {
+        let Some(estimator) = &self.estimator else {
+            return;
+        };
+        let Some(item) = self
+            .preview
+            .and_then(|id| self.items.iter().find(|item| item.id == id))
+        else {
+            self.estimate = None;
+            return;
+        };
+        let Some(data) = &item.data else {
+            return;
+        };
+
+        self.estimate = Some(Estimate::Pending);
+        if self.estimating {
+            self.estimate_outdated = true;
+            return;
+        }
+
+        let options = RasterizeOptions {
+            pages: item.page_selection(),
+            ..self.options()
+        };
+        estimator.estimate(item.id, data, &options);
+        self.estimating = true;
+        self.estimate_outdated = false;
+    }
+
+    /// ファイルのプレビューを表示し、まだならサムネイルのレンダリングを開始する
+    fn show_preview(&mut self, ctx: &Context<Self>, id: usize) {
+        if self.items.iter().all(|item| item.id != id) {
+            return;
+        }
+        if self.preview != Some(id) {
+            self.preview = Some(id);
+            self.request_estimate();
+        }
+
+        let Some(item) = self.items.iter_mut().find(|item| item.id == id) else {
+            return;
+        };
+        if item.thumbnails.is_empty() {
+            item.thumbnails = item.selected.iter().map(|_| None).collect();
+        }
+        self.request_thumbnail(ctx, id);
+    }
+
+    /// プレビュー中のファイルの次のサムネイルのレンダリングを要求する
+    ///
+    /// 見積もり用のWorkerで1ページずつレンダリングし、結果を受け取ってから次のページを要求する。
+    /// 別のファイルをプレビューしている間は止め、プレビューし直すと続きから再開する。
+    fn request_thumbnail(&mut self, ctx: &Context<Self>, id: usize) {
+        if self.preview != Some(id) {
+            return;
+        }
+        let lang = self.lang;
+        let Some(item) = self.items.iter_mut().find(|item| item.id == id) else {
+            return;
+        };
+        if item.thumbnail_pending || item.next_thumbnail >= item.thumbnails.len() {
+            return;
+        }
+        let Some(data) = &item.data else {
+            return;
+        };
+        let index = item.next_thumbnail;
+
+        if let Some(estimator) = &self.estimator {
+            estimator.thumbnail(id, index, THUMBNAIL_DPI, data);
+        } else {
+            // Workerを起動できなかった場合はメインスレッドで処理する
+            let document = match &item.document {
+                Some(document) => document.clone(),
+                None => match Document::load(data.clone()) {
+                    Ok(document) => {
+                        let document = Rc::new(document);
+                        item.document = Some(document.clone());
+                        document
+                    }
+                    Err(e) => {
+                        log!(i18n::trf(
+                            lang,
+                            "web.preview_failed",
+                            &[&i18n::error_message(lang, &e)]
+                        ));
+                        item.next_thumbnail = item.thumbnails.len();
+                        return;
+                    }
+                },
+            };
+            let cancel = item.thumbnail_cancel.clone();
+            let link = ctx.link().clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                // 前のページの表示を更新するためにブラウザに制御を戻す
+                TimeoutFuture::new(1).await;
+                if cancel.is_cancelled() {
+                    return;
+                }
+                let result = document
+                    .render_page_png(index, THUMBNAIL_DPI)
+                    .map_err(|e| Message::from_error(&e));
+                link.send_message(Msg::ThumbnailRendered(id, index, result));
+            });
+        }
+        item.next_thumbnail += 1;
+        item.thumbnail_pending = true;
+    }
+
+    /// 削除されたファイルのプレビューと比較を閉じる
+    fn forget_removed_items(&mut self) {
+        let exists = |id: usize| self.items.iter().any(|item| item.id == id);
+        if self.preview.is_some_and(|id| !exists(id)) {
+            self.preview = None;
+            self.comparison = None;
+            self.estimate = None;
+        }
+    }
+
+    /// 順番待ちまたは変換中のファイルがあるかどうか
+    fn is_processing(&self) -> bool {
+        self.items.iter().any(|item| item.is_active())
+    }
+
+    /// 指定した場所で変換中のファイル
+    fn item_for(&mut self, runner: Runner) -> Option<&mut QueueItem> {
+        self.items
+            .iter_mut()
+            .find(|item| matches!(item.status, ItemStatus::Processing(r) if r == runner))
+    }
+
+    /// 空いている場所に順番待ちのファイルを割り当てる
+    fn schedule(&mut self, ctx: &Context<Self>) {
+        if self.parallel_files {
+            self.ensure_file_workers(ctx);
+        }
+        let runners: Vec<Runner> = if self.parallel_files {
+            (0..self.file_workers.len()).map(Runner::File).collect()
+        } else {
+            vec![Runner::Shared]
+        };
+
+        for runner in runners {
+            if self.item_for(runner).is_some() {
+                continue;
+            }
+            let Some(index) = self
+                .items
+                .iter()
+                .position(|item| matches!(item.status, ItemStatus::Queued))
+            else {
+                break;
+            };
+            self.start(ctx, index, runner);
+        }
+    }
+
+    /// ファイルの変換を開始する
+    fn start(&mut self, ctx: &Context<Self>, index: usize, runner: Runner) {
+        if runner == Runner::Shared && self.parallel {
+            self.ensure_pool(ctx);
+        }
+
+        let options = RasterizeOptions {
+            pages: self.items[index].page_selection(),
+            ..self.options()
+        };
+
+        let lang = self.lang;
+        let item = &mut self.items[index];
+        let Some(data) = &item.data else {
+            return;
+        };
+        log!(i18n::trf(lang, "web.start_log", &[&item.name]));
+        item.status = ItemStatus::Processing(runner);
+        item.progress_message = Some(Message::new("web.starting"));
+        item.progress = None;
+
+        match runner {
+            Runner::File(slot) => {
+                self.file_workers[slot].rasterize(data, &options);
+            }
+            Runner::Shared => {
+                // 複数のWorkerでページを分担して処理
+                if let Some(pool) = self.pool.as_ref().filter(|_| self.parallel) {
+                    log!(i18n::trf(lang, "web.pool_log", &[&pool.size()]));
+                    pool.rasterize(data, &options);
+                    return;
+                }
+
+                // Workerで処理を実行（UIスレッドをブロックしない）
+                if let Some(worker) = &self.worker {
+                    worker.rasterize(data, &options);
+                    return;
+                }
+
+                self.cancel = Some(options.cancel.clone());
+                let data = data.clone();
+                let link = ctx.link().clone();
+
+                // WASMで処理を実行
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = crate::rasterize_pdf_with_progress(data, &options, {
+                        let link = link.clone();
+                        move |event| {
+                            link.send_message(Msg::UpdateProgress(Runner::Shared, event));
+                        }
+                    })
+                    .await;
+                    match result {
+                        Err(e) if e.is::<Cancelled>() => {
+                            link.send_message(Msg::PdfCancelled(Runner::Shared))
+                        }
+                        result => link.send_message(Msg::PdfProcessed(
+                            Runner::Shared,
+                            result.map_err(|e| Message::from_error(&e)),
+                        )),
+                    }
+                });
+            }
+        }
+    }
+
+    /// ワーカープールが未起動なら起動する
+    fn ensure_pool(&mut self, ctx: &Context<Self>) {
+        if self.pool.is_some() {
+            return;
+        }
+
+        let link = ctx.link().clone();
+        match RasterizePool::spawn(hardware_concurrency(), move |event| {
+            link.send_message(worker_message(Runner::Shared, event))
+        }) {
+            Ok(pool) => self.pool = Some(pool),
+            Err(e) => log!(self.tr("web.pool_unavailable"), e),
+        }
+    }
+
+    /// ファイル並列変換用のWorkerが未起動なら起動する
+    ///
+    /// 1つも起動できなかった場合は1ファイルずつの変換に切り替える。
+    fn ensure_file_workers(&mut self, ctx: &Context<Self>) {
+        if !self.file_workers.is_empty() {
+            return;
+        }
+
+        for slot in 0..hardware_concurrency() {
+            let link = ctx.link().clone();
+            match RasterizeWorker::spawn(move |event| {
+                link.send_message(worker_message(Runner::File(slot), event))
+            }) {
+                Ok(worker) => self.file_workers.push(worker),
+                Err(e) => {
+                    log!(self.tr("web.file_workers_unavailable"), e);
+                    break;
+                }
+            }
+        }
+
+        if self.file_workers.is_empty() {
+            self.parallel_files = false;
+        }
+    }
+
+    /// 新しいバージョンへの更新を促す表示
+    fn view_update_prompt(&self, ctx: &Context<Self>) -> Html {
+        if !self.update_ready {
+            return html! {};
+        }
+
+        html! {
+            <div class="update-prompt">
+                <span>{ self.tr("web.update_ready") }</span>
+                <button
+                    class="update-button"
+                    disabled={self.is_processing()}
+                    onclick={ctx.link().callback(|_| Msg::ApplyUpdate)}
+                >
+                    { self.tr("web.update_apply") }
+                </button>
+                <button class="update-dismiss" onclick={ctx.link().callback(|_| Msg::DismissUpdate)}>
+                    { self.tr("web.update_later") }
+                </button>
+            </div>
+        }
+    }
+
+    /// 表示言語の切り替えボタン
+    fn view_lang_toggle(&self, ctx: &Context<Self>) -> Html {
+        html! {
+            <div class="lang-toggle" title={self.tr("web.language")}>
+                {
+                    for Lang::ALL.into_iter().map(|lang| html! {
+                        <button
+                            class={classes!("lang-button", (lang == self.lang).then_some("active"))}
+                            onclick={ctx.link().callback(move |_| Msg::SetLang(lang))}
+                        >
+                            { lang.name() }
+                        </button>
+                    })
+                }
+            </div>
+        }
+    }
+
+    fn view_queue(&self, ctx: &Context<Self>) -> Html {
+        if self.items.is_empty() {
+            return html! {};
+        }
+
+        html! {
+            <div class="queue-section">
+                <ul class="queue">
+                    { for self.items.iter().map(|item| view_item(ctx, self.lang, item, self.preview == Some(item.id))) }
+                </ul>
+                <button
+                    class="queue-clear"
+                    onclick={ctx.link().callback(|_| Msg::ClearQueue)}
+                    disabled={self.is_processing()}
+                >
+                    { self.tr("web.clear_queue") }
+                </button>
+            </div>
+        }
+    }
+
+    fn view_presets(&self, ctx: &Context<Self>) -> Html {
+        let on_select = ctx.link().callback(|e: Event| {
+            let select: HtmlSelectElement = e.target().unwrap().dyn_into().unwrap();
+            Msg::SelectPreset(select.value())
+        });
+        let on_name_input = ctx.link().callback(|e: InputEvent| {
+            let input: HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+            Msg::SetPresetName(input.value())
+        });
+        let on_import = {
+            let link = ctx.link().clone();
+            Callback::from(move |e: Event| {
+                let input: HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+                if let Some(file) = input.files().and_then(|files| files.get(0)) {
+                    link.send_message(Msg::ImportPreset(File::from(file)));
+                }
+                input.set_value("");
+            })
+        };
+        let builtin = self
+            .preset(&self.selected_preset)
+            .is_none_or(|preset| preset.builtin);
+
+        html! {
+            <div class="presets">
+                <div class="preset-row">
+                    <span class="preset-label">{ self.tr("web.preset") }</span>
+                    <select class="setting-select" onchange={on_select}>
+                        {
+                            for self.presets.iter().map(|preset| html! {
+                                <option
+                                    value={preset.name.clone()}
+                                    selected={preset.name == self.selected_preset}
+                                >
+                                    { &preset.name }
+                                </option>
+                            })
+                        }
+                    </select>
+                    <button class="preset-button" onclick={ctx.link().callback(|_| Msg::ApplyPreset)}>
+                        { self.tr("web.preset_apply") }
+                    </button>
+                    <button class="preset-button" onclick={ctx.link().callback(|_| Msg::ExportPreset)}>
+                        { self.tr("web.preset_export") }
+                    </button>
+                    <button
+                        class="preset-button"
+                        disabled={builtin}
+                        onclick={ctx.link().callback(|_| Msg::DeletePreset)}
+                    >
+                        { self.tr("web.preset_delete") }
+                    </button>
+                </div>
+                <div class="preset-row">
+                    <input
+                        type="text"
+                        class="preset-name-input"
+                        placeholder={self.tr("web.preset_name")}
+                        value={self.preset_name.clone()}
+                        oninput={on_name_input}
+                    />
+                    <button class="preset-button" onclick={ctx.link().callback(|_| Msg::SavePreset)}>
+                        { self.tr("web.preset_save") }
+                    </button>
+                    <label class="preset-button">
+                        { self.tr("web.preset_import") }
+                        <input
+                            type="file"
+                            accept=".json,application/json"
+                            class="file-input"
+                            onchange={on_import}
+                        />
+                    </label>
+                </div>
+                {
+                    if let Some(e) = &self.preset_error {
+                        html! { <p class="setting-error">{ e.localize(self.lang) }</p> }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+
+    fn view_estimate(&self) -> Html {
+        let Some(estimate) = &self.estimate else {
+            return html! {};
+        };
+
+        let text = match estimate {
+            Estimate::Pending => self.tr("web.estimate_pending").to_string(),
+            Estimate::Ready(estimate) => self.trf(
+                "web.estimate_ready",
+                &[
+                    &format!("{:.1}", estimate.bytes as f64 / 1024.0 / 1024.0),
+                    &estimate.pages,
+                    &(estimate.sample_page + 1),
+                    &format!("{:.1}", estimate.sample_bytes as f64 / 1024.0),
+                ],
+            ),
+            Estimate::Failed(e) => self.trf("web.estimate_failed", &[&e.localize(self.lang)]),
+        };
+
+        html! { <p class="estimate">{ text }</p> }
+    }
+
+    fn view_preview(&self, ctx: &Context<Self>) -> Html {
+        let Some(item) = self
+            .preview
+            .and_then(|id| self.items.iter().find(|item| item.id == id))
+        else {
+            return html! {};
+        };
+
+        let id = item.id;
+        let done = matches!(item.status, ItemStatus::Done(_));
+        let selected_count = item.selected.iter().filter(|&&selected| selected).count();
+
+        let thumbnails = item.selected.iter().enumerate().map(|(index, &selected)| {
+            let image = match item.thumbnails.get(index).and_then(Option::as_ref) {
+                Some(url) => html! { <img src={url.as_str().to_string()} alt={self.trf("web.page", &[&(index + 1)])} /> },
+                None => html! { <div class="thumbnail-placeholder"></div> },
+            };
+            let on_compare = done.then(|| ctx.link().callback(move |_| Msg::Compare(id, index)));
+
+            html! {
+                <div class={classes!("thumbnail", selected.then_some("selected"), done.then_some("comparable"))}>
+                    <div class="thumbnail-image" onclick={on_compare}>{ image }</div>
+                    <label class="thumbnail-label">
+                        <input
+                            type="checkbox"
+                            checked={selected}
+                            disabled={item.is_active()}
+                            onchange={ctx.link().callback(move |_| Msg::TogglePage(id, index))}
+                        />
+                        { format!(" {}", index + 1) }
+                    </label>
+                </div>
+            }
+        });
+
+        html! {
+            <div class="preview-section">
+                <div class="preview-header">
+                    <span class="preview-title">{ &item.name }</span>
+                    <span class="preview-count">
+                        { self.trf("web.pages_selected", &[&selected_count, &item.selected.len()]) }
+                    </span>
+                    <button
+                        class="preview-select"
+                        disabled={item.is_active()}
+                        onclick={ctx.link().callback(move |_| Msg::SelectAllPages(id, true))}
+                    >
+                        { self.tr("web.select_all") }
+                    </button>
+                    <button
+                        class="preview-select"
+                        disabled={item.is_active()}
+                        onclick={ctx.link().callback(move |_| Msg::SelectAllPages(id, false))}
+                    >
+                        { self.tr("web.select_none") }
+                    </button>
+                </div>
+                <p class="preview-hint">
+                    {
+                        if done {
+                            self.tr("web.compare_hint")
+                        } else {
+                            self.tr("web.select_hint")
+                        }
+                    }
+                </p>
+                <div class="thumbnails">
+                    { for thumbnails }
+                </div>
+                { self.view_comparison(ctx) }
+            </div>
+        }
+    }
+
+    fn view_comparison(&self, ctx: &Context<Self>) -> Html {
+        let Some(comparison) = &self.comparison else {
+            return html! {};
+        };
+
+        let body = match comparison {
+            Comparison::Loading { index, .. } => html! {
+                <div class="progress">
+                    <div class="progress-spinner"></div>
+                    <p>{ self.trf("web.rendering_page", &[&(index + 1)]) }</p>
+                </div>
+            },
+            Comparison::Failed(e) => html! {
+                <div class="error">
+                    <p>{ e.localize(self.lang) }</p>
+                </div>
+            },
+            Comparison::Ready {
+                index,
+                original,
+                encoded,
+                encoded_bytes,
+                ..
+            } => {
+                let encoded_label = self.trf(
+                    "web.after_size",
+                    &[&format!("{:.1}", *encoded_bytes as f64 / 1024.0)],
+                );
+                let before = self.tr("web.before");
+                let after = self.tr("web.after");
+                let images = match self.compare_mode {
+                    CompareMode::SideBySide => html! {
+                        <div class="comparison-side">
+                            <figure>
+                                <img src={original.as_str().to_string()} alt={before} />
+                                <figcaption>{ before }</figcaption>
+                            </figure>
+                            <figure>
+                                <img src={encoded.as_str().to_string()} alt={after} />
+                                <figcaption>{ encoded_label }</figcaption>
+                            </figure>
+                        </div>
+                    },
+                    CompareMode::Slider => {
+                        let on_slide = ctx.link().callback(|e: InputEvent| {
+                            let input: HtmlInputElement = e.target().unwrap().dyn_into().unwrap();
+                            Msg::SetCompareSlider(input.value().parse().unwrap_or(50))
+                        });
+                        html! {
+                            <>
+                                <div class="comparison-slider">
+                                    <img src={encoded.as_str().to_string()} alt={after} />
+                                    <img
+                                        class="comparison-overlay"
+                                        src={original.as_str().to_string()}
+                                        alt={before}
+                                        style={format!("clip-path: inset(0 {}% 0 0)", 100 - self.compare_slider)}
+                                    />
+                                </div>
+                                <input
+                                    type="range"
+                                    class="comparison-range"
+                                    min="0"
+                                    max="100"
+                                    value={self.compare_slider.to_string()}
+                                    oninput={on_slide}
+                                />
+                                <div class="comparison-legend">
+                                    <span>{ format!("← {}", before) }</span>
+                                    <span>{ format!("{} →", encoded_label) }</span>
+                                </div>
+                            </>
+                        }
+                    }
+                };
+
+                html! {
+                    <>
+                        <p class="comparison-page">{ self.trf("web.compare_page", &[&(index + 1), &self.settings.dpi]) }</p>
+                        { images }
+                    </>
+                }
+            }
+        };
+
+        let mode_button = |mode: CompareMode, label: &str| {
+            html! {
+                <button
+                    class={classes!("comparison-mode", (self.compare_mode == mode).then_some("active"))}
+                    onclick={ctx.link().callback(move |_| Msg::SetCompareMode(mode))}
+                >
+                    { label }
+                </button>
+            }
+        };
+
+        html! {
+            <div class="comparison">
+                <div class="comparison-header">
+                    { mode_button(CompareMode::SideBySide, self.tr("web.side_by_side")) }
+                    { mode_button(CompareMode::Slider, self.tr("web.slider")) }
+                    <button
+                        class="queue-remove"
+                        title={self.tr("web.close_comparison")}
+                        onclick={ctx.link().callback(|_| Msg::CloseComparison)}
+                    >
+                        { "×" }
+                    </button>
+                </div>
+                { body }
+            </div>
+        }
+    }
+}
+
+impl QueueItem {
+    /// 変換するページの番号（すべてのページを変換する場合は`None`）
+    fn page_selection(&self) -> Option<Vec<usize>> {
+        if self.selected.iter().all(|&selected| selected) {
+            return None;
+        }
+        Some(
+            self.selected
+                .iter()
+                .enumerate()
+                .filter(|(_, &selected)| selected)
+                .map(|(index, _)| index)
+                .collect(),
+        )
+    }
+
+    /// 順番待ちまたは変換中かどうか
+    fn is_active(&self) -> bool {
+        matches!(self.status, ItemStatus::Queued | ItemStatus::Processing(_))
+    }
+}
+
+impl Drop for QueueItem {
+    fn drop(&mut self) {
+        self.thumbnail_cancel.cancel();
+    }
+}
+
+fn view_item(ctx: &Context<App>, lang: Lang, item: &QueueItem, previewing: bool) -> Html {
+    let tr = |key| i18n::tr(lang, key);
+    let (class, status) = match &item.status {
+        ItemStatus::Loading => ("loading", tr("web.status_loading").to_string()),
+        ItemStatus::Ready => ("ready", tr("web.status_ready").to_string()),
+        ItemStatus::Queued => ("queued", tr("web.status_queued").to_string()),
+        ItemStatus::Processing(_) => (
+            "processing",
+            item.progress_message
+                .as_ref()
+                .map(|message| message.localize(lang))
+                .unwrap_or_else(|| tr("web.processing").to_string()),
+        ),
+        ItemStatus::Done(data) => (
+            "done",
+            i18n::trf(
+                lang,
+                "web.status_done",
+                &[&format!("{:.1}", data.len() as f64 / 1024.0 / 1024.0)],
+            ),
+        ),
+        ItemStatus::Failed(e) => ("failed", e.localize(lang)),
+        ItemStatus::Cancelled => ("cancelled", tr("web.status_cancelled").to_string()),
+    };
+
+    let progress_bar = match (&item.status, item.progress) {
+        (ItemStatus::Processing(_), Some((done, total))) => {
+            let percent = done * 100 / total.max(1);
+            html! {
+                <div class="progress-bar queue-progress">
+                    <div
+                        class="progress-bar-fill"
+                        style={format!("width: {}%", percent)}
+                    ></div>
+                    <span class="progress-bar-label">{ format!("{}%", percent) }</span>
+                </div>
+            }
+        }
+        _ => html! {},
+    };
+
+    let id = item.id;
+    let action = match &item.status {
+        ItemStatus::Done(data) => {
+            let data = data.clone();
+            let file_name = output_file_name(&item.name);
+            html! {
+                <button
+                    class="queue-download"
+                    onclick={Callback::from(move |_| {
+                        download_file(&data, &file_name, "application/pdf");
+                    })}
+                >
+                    { tr("web.download") }
+                </button>
+            }
+        }
+        _ if item.is_active() => html! {},
+        _ => html! {
+            <button
+                class="queue-remove"
+                title={tr("web.remove")}
+                onclick={ctx.link().callback(move |_| Msg::RemoveItem(id))}
+            >
+                { "×" }
+            </button>
+        },
+    };
+
+    html! {
+        <li class={classes!("queue-item", class, previewing.then_some("previewing"))}>
+            <div class="queue-info">
+                {
+                    if item.data.is_some() {
+                        html! {
+                            <button
+                                class="queue-name"
+                                title={tr("web.show_preview")}
+                                onclick={ctx.link().callback(move |_| Msg::ShowPreview(id))}
+                            >
+                                { &item.name }
+                            </button>
+                        }
+                    } else {
+                        html! { <span class="queue-name">{ &item.name }</span> }
+                    }
+                }
+                {
+                    if let Some(info) = &item.info {
+                        let selected = item.selected.iter().filter(|&&selected| selected).count();
+                        let pages = if selected < info.pages {
+                            i18n::trf(lang, "web.page_count_selected", &[&selected, &info.pages])
+                        } else {
+                            i18n::trf(lang, "web.page_count", &[&info.pages])
+                        };
+                        html! {
+                            <span class="queue-meta">
+                                { format!("{} · PDF {}", pages, info.version) }
+                            </span>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                <span class="queue-status">{ status }</span>
+                { progress_bar }
+            </div>
+            { action }
+        </li>
+    }
+}
+
+/// ブラウザの言語設定（`navigator.language`）に合わせた表示言語
+fn browser_lang() -> Lang {
+    web_sys::window()
+        .and_then(|window| window.navigator().language())
+        .and_then(|language| Lang::from_locale(&language))
+        .unwrap_or_default()
+}
+
+/// 文書の`lang`属性を表示言語に合わせる
+fn set_document_lang(lang: Lang) {
+    if let Some(root) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.document_element())
+    {
+        let _ = root.set_attribute("lang", lang.code());
+    }
+}
+
+/// ファイル選択・ドロップで渡されたファイルの一覧
+fn file_list(files: web_sys::FileList) -> Vec<File> {
+    gloo_file::FileList::from(files).to_vec()
+}
+
+/// Workerからの通知をコンポーネントのメッセージに変換する
+fn worker_message(runner: Runner, event: WorkerEvent) -> Msg {
+    match event {
+        WorkerEvent::Progress(event) => Msg::UpdateProgress(runner, event),
+        WorkerEvent::Done(data) => Msg::PdfProcessed(runner, Ok(data)),
+        WorkerEvent::Failed(error) => Msg::PdfProcessed(runner, Err(Message::from(&error))),
+        WorkerEvent::Cancelled => Msg::PdfCancelled(runner),
+        WorkerEvent::Crashed(message) => Msg::WorkerCrashed(runner, message),
+    }
+}
+
+/// 変換後のファイル名
+fn output_file_name(name: &str) -> String {
+    let base = name
+        .strip_suffix(".pdf")
+        .or_else(|| name.strip_suffix(".PDF"))
+        .unwrap_or(name);
+    format!("{}_rasterized.pdf", base)
+}
+
+/// データから作成したBlob URL（ドロップ時に解放する）
+struct ObjectUrl(String);
+
+impl ObjectUrl {
+    fn new(data: &[u8], mime_type: &str) -> Option<Self> {
+        let blob = create_blob(data, mime_type)?;
+        web_sys::Url::create_object_url_with_blob(&blob)
+            .ok()
+            .map(Self)
+    }
+
+    fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for ObjectUrl {
+    fn drop(&mut self) {
+        let _ = web_sys::Url::revoke_object_url(&self.0);
+    }
+}
+
+fn create_blob(data: &[u8], mime_type: &str) -> Option<web_sys::Blob> {
     let array = js_sys::Uint8Array::new(&unsafe { js_sys::Uint8Array::view(data) }.into());
     let blob_parts = js_sys::Array::new();
     blob_parts.push(&array.buffer());
 
     let blob_property = web_sys::BlobPropertyBag::new();
-    blob_property.set_type("application/pdf");
+    blob_property.set_type(mime_type);
+
+    web_sys::Blob::new_with_u8_array_sequence_and_options(&blob_parts, &blob_property).ok()
+}
 
-    let blob =
-        web_sys::Blob::new_with_u8_array_sequence_and_options(&blob_parts, &blob_property).unwrap();
+fn download_file(data: &[u8], filename: &str, mime_type: &str) {
+    let window = web_sys::window().unwrap();
+    let document = window.document().unwrap();
+
+    // Blobを作成
+    let blob = create_blob(data, mime_type).unwrap();
 
     // URLを作成
     let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap();
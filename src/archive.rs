@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::io::{Cursor, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::Error;
+
+/// 複数のファイルを1つのZIPアーカイブにまとめる
+///
+/// 画像化したPDFはほとんど圧縮できないため無圧縮で格納する。
+/// 同じ名前のファイルには連番を付けて区別する。
+pub fn create_zip<N: AsRef<str>>(files: &[(N, &[u8])]) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut used_names = HashSet::new();
+
+    for (name, data) in files {
+        let name = unique_name(name.as_ref(), &mut used_names);
+        zip.start_file(name.as_str(), options)
+            .with_context(|| Error::AddToZip { name: name.clone() })?;
+        zip.write_all(data)
+            .with_context(|| Error::AddToZip { name: name.clone() })?;
+    }
+
+    let cursor = zip.finish().context(Error::CreateZip)?;
+    Ok(cursor.into_inner())
+}
+
+/// 既に使われている名前なら「名前 (2).pdf」のように連番を付ける
+fn unique_name(name: &str, used_names: &mut HashSet<String>) -> String {
+    let (stem, extension) = match name.rfind('.') {
+        Some(dot) if dot > 0 => (&name[..dot], &name[dot..]),
+        _ => (name, ""),
+    };
+
+    let mut candidate = name.to_string();
+    let mut counter = 2;
+    while !used_names.insert(candidate.clone()) {
+        candidate = format!("{} ({}){}", stem, counter, extension);
+        counter += 1;
+    }
+    candidate
+}
@@ -0,0 +1,473 @@
+//! `batch`サブコマンド: ディレクトリやglobパターンに含まれるPDFをまとめて変換する
+//!
+//! 入力の基準ディレクトリからの相対パスを出力ディレクトリの下に再現し、
+//! ファイル単位で並列に変換する。
+
+use anyhow::{Context, Result};
+use glob::{glob_with, MatchOptions, Pattern};
+use pdf_rasterizer::i18n::{tr, trf, Lang};
+use pdf_rasterizer::RasterizeOptions;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::cli_report::{FileReport, FileStatus};
+use crate::PartialOutputs;
+
+/// 変換する1ファイル
+pub struct Job {
+    pub input: PathBuf,
+    pub output: PathBuf,
+}
+
+/// バッチ変換の集計
+#[derive(Default)]
+pub struct Summary {
+    pub converted: usize,
+    pub skipped: usize,
+    /// 失敗したファイルとエラーメッセージ（入力の順）
+    pub failed: Vec<(PathBuf, String)>,
+    pub cancelled: bool,
+    /// すべてのファイルの結果（`collect_jobs`の順）
+    pub files: Vec<FileReport>,
+}
+
+/// 入力の指定（ディレクトリ・ファイル・globパターン）から変換するファイルを集める
+///
+/// ディレクトリはサブディレクトリまで含めて`.pdf`ファイルを探す（大文字小文字は区別しない）。
+/// 出力先は`out_dir`の下に、ディレクトリやパターンの固定部分からの相対パスで配置する。
+/// 出力ディレクトリの中にあるファイルは、前回の出力を入力にしないよう除外する。
+/// 異なるファイルが同じ出力先になる場合は、後のファイルの出力に`-1`などの番号を付ける。
+pub fn collect_jobs(lang: Lang, inputs: &[String], out_dir: &Path) -> Result<Vec<Job>> {
+    let out_dir_absolute = absolute(out_dir);
+    let mut seen = HashSet::new();
+    let mut outputs = HashSet::new();
+    let mut jobs = Vec::new();
+
+    for input in inputs {
+        let (base, files) = expand_input(lang, input)?;
+        if files.is_empty() {
+            eprintln!("{}", trf(lang, "cli.batch.no_match", &[input]));
+            continue;
+        }
+
+        for input in files {
+            if absolute(&input).starts_with(&out_dir_absolute) {
+                continue;
+            }
+            // 複数の指定に同じファイルが含まれていても1回だけ変換する
+            if !seen.insert(canonical(&input)) {
+                continue;
+            }
+            // 別のファイルが同じ出力先になる場合は、後のファイルの名前に番号を付ける
+            let mut output = output_path(out_dir, &base, &input);
+            if outputs.contains(&output) {
+                output = numbered_path(&output, &outputs);
+                eprintln!(
+                    "{}",
+                    trf(
+                        lang,
+                        "cli.batch.renamed",
+                        &[&input.display(), &output.display()]
+                    )
+                );
+            }
+            outputs.insert(output.clone());
+            jobs.push(Job { input, output });
+        }
+    }
+
+    Ok(jobs)
+}
+
+/// 変換を実行して結果を集計する
+///
+/// 出力ファイルが既にあるものは`force`でなければ変換しない。
+/// `threads`を指定した場合は専用のスレッドプールで、ファイルとページの両方を並列に処理する。
+pub fn run(
+    lang: Lang,
+    jobs: &[Job],
+    options: &RasterizeOptions,
+    threads: Option<usize>,
+    force: bool,
+    partial_outputs: &PartialOutputs,
+) -> Result<Summary> {
+    let convert_job: Vec<bool> = jobs
+        .iter()
+        .map(|job| force || !job.output.exists())
+        .collect();
+    let pending: Vec<&Job> = jobs
+        .iter()
+        .zip(&convert_job)
+        .filter_map(|(job, &convert)| convert.then_some(job))
+        .collect();
+    let skipped = jobs.len() - pending.len();
+    if skipped > 0 {
+        status!("{}", trf(lang, "cli.batch.skipping", &[&skipped]));
+    }
+
+    let total = pending.len();
+    let finished = AtomicUsize::new(0);
+    let convert_all = || -> Vec<FileStatus> {
+        pending
+            .par_iter()
+            .map(|job| {
+                let outcome = convert(lang, job, options, partial_outputs);
+                let done = finished.fetch_add(1, Ordering::SeqCst) + 1;
+                match &outcome {
+                    FileStatus::Converted { .. } => status!(
+                        "{}",
+                        trf(
+                            lang,
+                            "cli.batch.converted",
+                            &[&done, &total, &job.input.display(), &job.output.display()]
+                        )
+                    ),
+                    FileStatus::Failed { message, .. } => eprintln!(
+                        "{}",
+                        trf(
+                            lang,
+                            "cli.batch.failed",
+                            &[&done, &total, &job.input.display(), message]
+                        )
+                    ),
+                    FileStatus::Skipped | FileStatus::Cancelled => {}
+                }
+                outcome
+            })
+            .collect()
+    };
+
+    let outcomes = match threads {
+        Some(n) if n > 0 => rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .thread_name(|i| format!("pdf_rasterizer-batch-{}", i))
+            .build()
+            .context(pdf_rasterizer::Error::ThreadPool)?
+            .install(convert_all),
+        _ => convert_all(),
+    };
+
+    let mut summary = Summary {
+        skipped,
+        ..Default::default()
+    };
+    let mut outcomes = outcomes.into_iter();
+    for (job, convert) in jobs.iter().zip(convert_job) {
+        let outcome = if convert {
+            outcomes.next().unwrap_or(FileStatus::Cancelled)
+        } else {
+            FileStatus::Skipped
+        };
+        match &outcome {
+            FileStatus::Converted { .. } => summary.converted += 1,
+            FileStatus::Failed { message, .. } => {
+                summary.failed.push((job.input.clone(), message.clone()))
+            }
+            FileStatus::Cancelled => summary.cancelled = true,
+            FileStatus::Skipped => {}
+        }
+        summary
+            .files
+            .push(FileReport::new(&job.input, &job.output, outcome));
+    }
+    Ok(summary)
+}
+
+/// 集計を表示する
+pub fn print_summary(lang: Lang, summary: &Summary) {
+    status!(
+        "{}",
+        trf(
+            lang,
+            "cli.batch.summary",
+            &[&summary.converted, &summary.skipped, &summary.failed.len()]
+        )
+    );
+    if !summary.failed.is_empty() {
+        eprintln!("{}", tr(lang, "cli.batch.failures"));
+        for (input, message) in &summary.failed {
+            eprintln!("  {}: {}", input.display(), message);
+        }
+    }
+}
+
+/// 1ファイルを変換する（出力先のディレクトリは必要に応じて作成する）
+pub fn convert(
+    lang: Lang,
+    job: &Job,
+    options: &RasterizeOptions,
+    partial_outputs: &PartialOutputs,
+) -> FileStatus {
+    if options.cancel.is_cancelled() {
+        return FileStatus::Cancelled;
+    }
+
+    partial_outputs
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(job.output.clone());
+    let result = job
+        .output
+        .parent()
+        .map_or(Ok(()), |dir| {
+            std::fs::create_dir_all(dir)
+                .with_context(|| trf(lang, "cli.batch.create_dir", &[&dir.display()]))
+        })
+        .and_then(|()| pdf_rasterizer::rasterize_file(&job.input, &job.output, options));
+    if let Ok(report) = &result {
+        crate::print_warnings(lang, &job.input, report);
+    }
+    partial_outputs
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|output| output != &job.output);
+
+    FileStatus::from_result(lang, &result)
+}
+
+/// 入力の指定を基準ディレクトリとファイルの一覧にする
+fn expand_input(lang: Lang, input: &str) -> Result<(PathBuf, Vec<PathBuf>)> {
+    let path = Path::new(input);
+    if path.is_dir() {
+        let pattern = Path::new(&Pattern::escape(input)).join("**").join("*.pdf");
+        let files = glob_files(lang, &pattern.to_string_lossy())?;
+        return Ok((path.to_path_buf(), files));
+    }
+    if path.is_file() {
+        let base = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        return Ok((base, vec![path.to_path_buf()]));
+    }
+    Ok((glob_base(input), glob_files(lang, input)?))
+}
+
+/// パターンに一致するファイルを名前順に返す（読み込めないディレクトリは警告して飛ばす）
+fn glob_files(lang: Lang, pattern: &str) -> Result<Vec<PathBuf>> {
+    let options = MatchOptions {
+        case_sensitive: false,
+        ..MatchOptions::new()
+    };
+    let entries = glob_with(pattern, options)
+        .with_context(|| trf(lang, "cli.batch.invalid_pattern", &[&pattern]))?;
+
+    let mut files = Vec::new();
+    for entry in entries {
+        match entry {
+            Ok(path) if path.is_file() => files.push(path),
+            Ok(_) => {}
+            Err(e) => eprintln!(
+                "{}",
+                trf(lang, "cli.batch.unreadable", &[&e.path().display()])
+            ),
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// 入力ファイルの出力先（`base`からの相対パスを`out_dir`の下に配置する）
+///
+/// `base`の下にないファイルは、ファイル名だけを`out_dir`の下に配置する。
+fn output_path(out_dir: &Path, base: &Path, input: &Path) -> PathBuf {
+    let relative = match without_cur_dir(input).strip_prefix(without_cur_dir(base)) {
+        Ok(relative) if relative.file_name().is_some() => relative.to_path_buf(),
+        _ => PathBuf::from(input.file_name().unwrap_or_default()),
+    };
+    out_dir.join(relative)
+}
+
+/// `path`の名前に番号を付けた、`taken`に含まれないパス
+fn numbered_path(path: &Path, taken: &HashSet<PathBuf>) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    (1..)
+        .map(|n| path.with_file_name(format!("{}-{}.{}", stem, n, extension)))
+        .find(|path| !taken.contains(path))
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// globパターンのうちワイルドカードを含まない先頭部分（出力の相対パスの基準）
+fn glob_base(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|component| {
+            !component
+                .as_os_str()
+                .to_string_lossy()
+                .contains(['*', '?', '['])
+        })
+        .collect()
+}
+
+/// パスから`.`を取り除く（globは一致したパスの先頭の`./`を付けないため、基準と揃える）
+fn without_cur_dir(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// 同じファイルかどうかを比べるためのパス（シンボリックリンクや`..`を解決する）
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| absolute(path))
+}
+
+/// 比較用の絶対パス（存在しないパスでも作れるようにファイルシステムは参照しない）
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// テストごとの一時ディレクトリ（終了時に削除する）
+    pub(crate) struct TempDir(pub PathBuf);
+
+    impl TempDir {
+        pub fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "pdf_rasterizer-{}-{}",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn create(&self, relative: &str) -> PathBuf {
+            let path = self.0.join(relative);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, b"%PDF-1.7\n").unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn glob_base_stops_at_the_first_wildcard() {
+        assert_eq!(glob_base("scans/2024/*.pdf"), Path::new("scans/2024"));
+        assert_eq!(glob_base("scans/**/*.pdf"), Path::new("scans"));
+        assert_eq!(glob_base("scans/day?/a.pdf"), Path::new("scans"));
+        assert_eq!(glob_base("scans/[ab]/x.pdf"), Path::new("scans"));
+        assert_eq!(glob_base("*.pdf"), Path::new(""));
+        assert_eq!(glob_base("/data/in/*.pdf"), Path::new("/data/in"));
+    }
+
+    #[test]
+    fn output_path_keeps_the_path_below_the_base() {
+        let out = Path::new("out");
+        assert_eq!(
+            output_path(out, Path::new("scans"), Path::new("scans/2024/a.pdf")),
+            Path::new("out/2024/a.pdf")
+        );
+        // globは一致したパスの先頭に`./`を付けないため、基準の`./`は無視する
+        assert_eq!(
+            output_path(out, Path::new("./scans"), Path::new("scans/a.pdf")),
+            Path::new("out/a.pdf")
+        );
+        assert_eq!(
+            output_path(out, Path::new(""), Path::new("a.pdf")),
+            Path::new("out/a.pdf")
+        );
+    }
+
+    #[test]
+    fn output_path_falls_back_to_the_file_name() {
+        let out = Path::new("out");
+        assert_eq!(
+            output_path(out, Path::new("other"), Path::new("scans/a.pdf")),
+            Path::new("out/a.pdf")
+        );
+        // 基準そのものに一致した場合も、ファイル名で配置する
+        assert_eq!(
+            output_path(out, Path::new("scans/a.pdf"), Path::new("scans/a.pdf")),
+            Path::new("out/a.pdf")
+        );
+    }
+
+    #[test]
+    fn directories_are_mirrored_into_the_output_directory() {
+        let dir = TempDir::new("batch-mirror");
+        dir.create("in/a.pdf");
+        dir.create("in/sub/B.PDF");
+        dir.create("in/notes.txt");
+        let out_dir = dir.0.join("out");
+
+        let input = dir.0.join("in").to_string_lossy().into_owned();
+        let jobs = collect_jobs(Lang::En, &[input.clone(), input], &out_dir).unwrap();
+        let outputs: Vec<&Path> = jobs.iter().map(|job| job.output.as_path()).collect();
+        // 同じ入力を2回指定しても1回だけ変換する
+        assert_eq!(
+            outputs,
+            vec![out_dir.join("a.pdf"), out_dir.join("sub/B.PDF")]
+        );
+    }
+
+    #[test]
+    fn files_in_the_output_directory_are_not_inputs() {
+        let dir = TempDir::new("batch-exclude");
+        dir.create("a.pdf");
+        dir.create("out/a.pdf");
+        let out_dir = dir.0.join("out");
+
+        let jobs =
+            collect_jobs(Lang::En, &[dir.0.to_string_lossy().into_owned()], &out_dir).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].input, dir.0.join("a.pdf"));
+        assert_eq!(jobs[0].output, out_dir.join("a.pdf"));
+    }
+
+    #[test]
+    fn glob_patterns_map_relative_to_their_fixed_prefix() {
+        let dir = TempDir::new("batch-glob");
+        dir.create("in/2024/a.pdf");
+        dir.create("in/2025/b.pdf");
+        let out_dir = dir.0.join("out");
+
+        let pattern = dir.0.join("in/*/*.pdf").to_string_lossy().into_owned();
+        let jobs = collect_jobs(Lang::En, &[pattern], &out_dir).unwrap();
+        let outputs: Vec<&Path> = jobs.iter().map(|job| job.output.as_path()).collect();
+        assert_eq!(
+            outputs,
+            vec![out_dir.join("2024/a.pdf"), out_dir.join("2025/b.pdf")]
+        );
+    }
+
+    #[test]
+    fn inputs_with_the_same_name_get_distinct_outputs() {
+        let dir = TempDir::new("batch-same-name");
+        let a = dir.create("a/x.pdf");
+        let b = dir.create("b/x.pdf");
+        let c = dir.create("c/x.pdf");
+        let out_dir = dir.0.join("out");
+
+        // ファイルの指定、ディレクトリの指定のどちらでも、後のファイルに番号を付ける
+        let inputs = [
+            a.to_string_lossy().into_owned(),
+            b.to_string_lossy().into_owned(),
+            dir.0.join("c").to_string_lossy().into_owned(),
+            dir.0.join("b/../a/x.pdf").to_string_lossy().into_owned(),
+        ];
+        let jobs = collect_jobs(Lang::En, &inputs, &out_dir).unwrap();
+        let mapping: Vec<(&Path, &Path)> = jobs
+            .iter()
+            .map(|job| (job.input.as_path(), job.output.as_path()))
+            .collect();
+        // 別の書き方で同じファイルを指定しても1回だけ変換する
+        assert_eq!(
+            mapping,
+            vec![
+                (a.as_path(), out_dir.join("x.pdf").as_path()),
+                (b.as_path(), out_dir.join("x-1.pdf").as_path()),
+                (c.as_path(), out_dir.join("x-2.pdf").as_path()),
+            ]
+        );
+    }
+}
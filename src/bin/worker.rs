@@ -0,0 +1,3 @@
+fn main() {
+    pdf_rasterizer::worker::run_worker();
+}
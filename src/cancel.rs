@@ -0,0 +1,53 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 変換処理を中断するためのトークン
+///
+/// クローンしたトークンは同じ状態を共有するため、
+/// 別スレッドやUIのイベントハンドラから`cancel`を呼び出せる。
+/// 中断の要求はページの処理の合間に確認される。
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// 新しいトークンを作成する
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 変換の中断を要求する
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// 中断が要求されているかどうか
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 中断が要求されていれば`Cancelled`エラーを返す
+    pub fn check(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() {
+            Err(Cancelled.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 変換が中断されたことを表すエラー
+///
+/// `anyhow::Error::is::<Cancelled>()`で他のエラーと区別できる。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(crate::i18n::tr(crate::i18n::Lang::En, "error.cancelled"))
+    }
+}
+
+impl std::error::Error for Cancelled {}
@@ -0,0 +1,835 @@
+//! C言語から呼び出すためのAPI
+//!
+//! `cargo build --release --features capi`でビルドした共有ライブラリと
+//! `include/pdf_rasterizer.h`（ビルド時にcbindgenで生成）を使う。
+//! 失敗した関数は`PdfRasterizerStatus`でエラーを返し、
+//! 詳細は同じスレッドで`pdf_rasterizer_last_error`から取得できる。
+//!
+//! 列挙型の引数は範囲外の値が渡されても未定義動作にならないよう`uint32_t`で受け取り、
+//! 定義されていない値は`PDF_RASTERIZER_STATUS_INVALID_ARGUMENT`で失敗する。
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use anyhow::Result;
+
+use crate::{
+    parse_page_ranges, ColorSpace, Error, ImageEncoding, PaperFit, PaperSize, ProgressCallback,
+    ProgressEvent, RasterizeOptions, SafetyLimits,
+};
+
+thread_local! {
+    /// 最後に失敗した呼び出しのエラーメッセージ（スレッドごと）
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// 関数の実行結果
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfRasterizerStatus {
+    /// 成功した
+    Ok = 0,
+    /// 引数がNULLまたは正しくない
+    InvalidArgument = 1,
+    /// 変換に失敗した
+    Failed = 2,
+    /// ライブラリ内部の不具合（パニック）で失敗した
+    Internal = 3,
+}
+
+/// 出力PDFに埋め込む画像の色空間（`pdf_rasterizer_options_set_color_space`に渡す値）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfRasterizerColorSpace {
+    Rgb = 0,
+    Gray = 1,
+}
+
+/// 出力PDFに埋め込む画像の圧縮方式（`pdf_rasterizer_options_set_encoding`に渡す値）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfRasterizerEncoding {
+    Jpeg = 0,
+    Flate = 1,
+}
+
+/// 出力PDFのページを揃える用紙サイズ（`pdf_rasterizer_options_set_paper`に渡す値）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfRasterizerPaper {
+    /// 元のページの大きさのまま
+    None = 0,
+    A4 = 1,
+    Letter = 2,
+}
+
+/// 用紙サイズに揃えるときの配置方法（`pdf_rasterizer_options_set_paper`に渡す値）
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfRasterizerPaperFit {
+    /// ページ全体を用紙に収める
+    Fit = 0,
+    /// 用紙全体を覆い、はみ出した部分は切り取る
+    Fill = 1,
+}
+
+/// 進捗の種類
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfRasterizerProgressKind {
+    /// PDFのパースが完了した（`total`は変換するページ数）
+    Parsed = 0,
+    /// ページの画像化が完了した（`index`は0始まり、`bytes`はエンコード後のサイズ）
+    PageRendered = 1,
+    /// 出力PDFへページを書き出した（`done`／`total`ページ）
+    Assembling = 2,
+    /// 出力PDFの保存が完了した（`bytes`は出力のサイズ）
+    Saved = 3,
+}
+
+/// 変換の進捗（種類ごとに使わないフィールドは0）
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PdfRasterizerProgress {
+    pub kind: PdfRasterizerProgressKind,
+    pub index: usize,
+    pub done: usize,
+    pub total: usize,
+    pub bytes: u64,
+}
+
+/// 進捗を受け取る関数
+///
+/// 並列処理時は複数のスレッドから同時に呼び出される。
+pub type PdfRasterizerProgressFn =
+    Option<unsafe extern "C" fn(progress: *const PdfRasterizerProgress, user_data: *mut c_void)>;
+
+/// ライブラリが確保したバイト列（`pdf_rasterizer_buffer_free`で解放する）
+#[repr(C)]
+#[derive(Debug)]
+pub struct PdfRasterizerBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// 変換のオプション（中身は公開しない）
+pub struct PdfRasterizerOptions(RasterizeOptions);
+
+/// 進捗の関数に渡す利用者のデータ
+///
+/// スレッドをまたいで使えるようにするのは呼び出し元の責任とする。
+#[derive(Clone, Copy)]
+struct UserData(*mut c_void);
+
+// SAFETY: `pdf_rasterizer_options_set_progress`の説明のとおり、
+// 複数のスレッドから使えるデータを渡すのは呼び出し元の責任とする
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+/// デフォルト値のオプションを作成する（`pdf_rasterizer_options_free`で解放する）
+#[no_mangle]
+pub extern "C" fn pdf_rasterizer_options_new() -> *mut PdfRasterizerOptions {
+    Box::into_raw(Box::new(PdfRasterizerOptions(RasterizeOptions::default())))
+}
+
+/// オプションを解放する
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返したポインタかNULLで、解放済みでないこと。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_free(options: *mut PdfRasterizerOptions) {
+    if !options.is_null() {
+        drop(Box::from_raw(options));
+    }
+}
+
+/// 解像度（DPI）を設定する
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_dpi(
+    options: *mut PdfRasterizerOptions,
+    dpi: u32,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        if dpi == 0 {
+            return Err(invalid_argument("dpi"));
+        }
+        options.dpi = dpi;
+        Ok(())
+    })
+}
+
+/// ページごとにDPIを自動で決める場合の上限を設定する（`0`で自動決定をやめる）
+///
+/// 自動で決める場合、`pdf_rasterizer_options_set_dpi`の値は画像を含まないページのDPIと、
+/// 画像から決めるDPIの下限に使う。
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_auto_dpi(
+    options: *mut PdfRasterizerOptions,
+    max_dpi: u32,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        options.auto_dpi = (max_dpi > 0).then_some(max_dpi);
+        Ok(())
+    })
+}
+
+/// レンダリングする画像の最大幅・最大高さ（ピクセル）と最大画素数（メガピクセル）を設定する
+///
+/// それぞれ`0`で制限しない。超えるページは縦横比を保ったまま解像度を下げる。
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_max_size(
+    options: *mut PdfRasterizerOptions,
+    max_width: u32,
+    max_height: u32,
+    max_megapixels: f32,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        if max_megapixels.is_nan() || max_megapixels < 0.0 {
+            return Err(invalid_argument("max_megapixels"));
+        }
+        options.max_width = (max_width > 0).then_some(max_width);
+        options.max_height = (max_height > 0).then_some(max_height);
+        options.max_megapixels = (max_megapixels > 0.0).then_some(max_megapixels);
+        Ok(())
+    })
+}
+
+/// 出力PDFのページを揃える用紙サイズと配置方法を設定する
+///
+/// `paper`は`PdfRasterizerPaper`、`fit`は`PdfRasterizerPaperFit`の値。
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_paper(
+    options: *mut PdfRasterizerOptions,
+    paper: u32,
+    fit: u32,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        let paper = match paper {
+            p if p == PdfRasterizerPaper::None as u32 => None,
+            p if p == PdfRasterizerPaper::A4 as u32 => Some(PaperSize::A4),
+            p if p == PdfRasterizerPaper::Letter as u32 => Some(PaperSize::Letter),
+            _ => return Err(invalid_argument("paper")),
+        };
+        let paper_fit = match fit {
+            f if f == PdfRasterizerPaperFit::Fit as u32 => PaperFit::Fit,
+            f if f == PdfRasterizerPaperFit::Fill as u32 => PaperFit::Fill,
+            _ => return Err(invalid_argument("fit")),
+        };
+        options.paper = paper;
+        options.paper_fit = paper_fit;
+        Ok(())
+    })
+}
+
+/// JPEG品質（1〜100）を設定する
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_quality(
+    options: *mut PdfRasterizerOptions,
+    quality: u8,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        if !(1..=100).contains(&quality) {
+            return Err(invalid_argument("quality"));
+        }
+        options.quality = quality;
+        Ok(())
+    })
+}
+
+/// 埋め込む画像の色空間（`PdfRasterizerColorSpace`の値）を設定する
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_color_space(
+    options: *mut PdfRasterizerOptions,
+    color_space: u32,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        options.color_space = match color_space {
+            c if c == PdfRasterizerColorSpace::Rgb as u32 => ColorSpace::Rgb,
+            c if c == PdfRasterizerColorSpace::Gray as u32 => ColorSpace::Gray,
+            _ => return Err(invalid_argument("color_space")),
+        };
+        Ok(())
+    })
+}
+
+/// 埋め込む画像の圧縮方式（`PdfRasterizerEncoding`の値）を設定する
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_encoding(
+    options: *mut PdfRasterizerOptions,
+    encoding: u32,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        options.encoding = match encoding {
+            e if e == PdfRasterizerEncoding::Jpeg as u32 => ImageEncoding::Jpeg,
+            e if e == PdfRasterizerEncoding::Flate as u32 => ImageEncoding::Flate,
+            _ => return Err(invalid_argument("encoding")),
+        };
+        Ok(())
+    })
+}
+
+/// レンダリングに使用するスレッド数を設定する（0はCPUコア数）
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_threads(
+    options: *mut PdfRasterizerOptions,
+    threads: usize,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        options.threads = Some(threads);
+        Ok(())
+    })
+}
+
+/// 同時にレンダリングするページのメモリ上限（バイト）を設定する
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_memory_limit(
+    options: *mut PdfRasterizerOptions,
+    bytes: u64,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        options.memory_limit = bytes;
+        Ok(())
+    })
+}
+
+/// 信頼できない入力PDFに対する上限を設定する
+///
+/// 入力の最大サイズ（バイト）、最大ページ数、1ページの最大画素数、全ページの画素数の合計の上限、
+/// 1ページのレンダリングにかかった時間の上限（ミリ秒）を指定し、それぞれ`0`で制限しない。
+/// レンダリング時間はページのレンダリングが終わった後に確認するため、レンダリング中には止まらない。
+/// 上限を超える入力は、メモリを確保する前に`PDF_RASTERIZER_STATUS_FAILED`で失敗する。
+/// デフォルトでは1ページの最大画素数だけを2億ピクセルに制限している。
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_limits(
+    options: *mut PdfRasterizerOptions,
+    max_input_bytes: u64,
+    max_pages: usize,
+    max_page_pixels: u64,
+    max_total_pixels: u64,
+    max_page_render_ms: u64,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        options.limits = SafetyLimits {
+            max_input_bytes: (max_input_bytes > 0).then_some(max_input_bytes),
+            max_pages: (max_pages > 0).then_some(max_pages),
+            max_page_pixels: (max_page_pixels > 0).then_some(max_page_pixels),
+            max_total_pixels: (max_total_pixels > 0).then_some(max_total_pixels),
+            max_page_render_ms: (max_page_render_ms > 0).then_some(max_page_render_ms),
+        };
+        Ok(())
+    })
+}
+
+/// 変換するページを「1-3,5」のような1始まりの範囲で指定する（NULLはすべてのページ）
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタ、
+/// `ranges`はNULLか終端のあるUTF-8文字列であること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_pages(
+    options: *mut PdfRasterizerOptions,
+    ranges: *const c_char,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        if ranges.is_null() {
+            options.pages = None;
+            return Ok(());
+        }
+        let ranges = CStr::from_ptr(ranges)
+            .to_str()
+            .map_err(|_| invalid_argument("ranges"))?;
+        options.pages = Some(parse_page_ranges(ranges)?);
+        Ok(())
+    })
+}
+
+/// 進捗を受け取る関数を設定する（NULLで解除）
+///
+/// `user_data`はそのまま`progress`に渡される。並列処理時は複数のスレッドから
+/// 同時に呼び出されるため、`user_data`はスレッドセーフに扱うこと。
+///
+/// # Safety
+///
+/// `options`は`pdf_rasterizer_options_new`が返した有効なポインタで、
+/// `user_data`は変換が終わるまで有効であること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_options_set_progress(
+    options: *mut PdfRasterizerOptions,
+    progress: PdfRasterizerProgressFn,
+    user_data: *mut c_void,
+) -> PdfRasterizerStatus {
+    with_options(options, |options| {
+        let user_data = UserData(user_data);
+        options.progress = progress.map(|progress| {
+            ProgressCallback::new(move |event| {
+                // フィールドだけをキャプチャするとSendにならないため、構造体ごと移す
+                let user_data = user_data;
+                let event = progress_event(event);
+                // SAFETY: 関数ポインタとデータの有効性は呼び出し元が保証する
+                unsafe { progress(&event, user_data.0) };
+            })
+        });
+        Ok(())
+    })
+}
+
+/// PDFを画像化し、変換後のPDFを`output`に書き込む
+///
+/// 入力はライブラリ内にコピーしてから変換する。成功した場合、`output`は
+/// `pdf_rasterizer_buffer_free`で解放すること。失敗した場合は`output`を空にする。
+///
+/// # Safety
+///
+/// `data`は`len`バイトの読み込み可能な領域、`options`はNULLか
+/// `pdf_rasterizer_options_new`が返した有効なポインタ、`output`は書き込み可能なポインタであること。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_rasterize(
+    data: *const u8,
+    len: usize,
+    options: *const PdfRasterizerOptions,
+    output: *mut PdfRasterizerBuffer,
+) -> PdfRasterizerStatus {
+    if output.is_null() {
+        return fail(
+            PdfRasterizerStatus::InvalidArgument,
+            &invalid_argument("output"),
+        );
+    }
+    *output = PdfRasterizerBuffer {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+    if data.is_null() {
+        return fail(
+            PdfRasterizerStatus::InvalidArgument,
+            &invalid_argument("data"),
+        );
+    }
+
+    let pdf_data = std::slice::from_raw_parts(data, len).to_vec();
+    let default_options;
+    let options = match options.as_ref() {
+        Some(options) => &options.0,
+        None => {
+            default_options = RasterizeOptions::default();
+            &default_options
+        }
+    };
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        crate::rasterize_pdf_with_options(pdf_data, options)
+    }));
+    match result {
+        Ok(Ok(pdf)) => {
+            let pdf = pdf.into_boxed_slice();
+            let len = pdf.len();
+            *output = PdfRasterizerBuffer {
+                data: Box::into_raw(pdf).cast::<u8>(),
+                len,
+            };
+            clear_last_error();
+            PdfRasterizerStatus::Ok
+        }
+        Ok(Err(e)) => fail(PdfRasterizerStatus::Failed, &e),
+        Err(_) => panicked(),
+    }
+}
+
+/// `pdf_rasterizer_rasterize`が返したバイト列を解放する
+///
+/// # Safety
+///
+/// `buffer`はこのライブラリが返したもので、解放済みでないこと（`data`がNULLなら何もしない）。
+#[no_mangle]
+pub unsafe extern "C" fn pdf_rasterizer_buffer_free(buffer: PdfRasterizerBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+/// このスレッドで最後に失敗した呼び出しのエラーメッセージ（英語）を返す
+///
+/// 失敗していなければNULLを返す。文字列は次にこのスレッドでライブラリを
+/// 呼び出すまで有効で、解放してはならない。
+#[no_mangle]
+pub extern "C" fn pdf_rasterizer_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// オプションを変更し、失敗した場合はエラーを記録する
+///
+/// パニックはCの呼び出し元に伝えず、`Internal`として返す。
+unsafe fn with_options<F>(options: *mut PdfRasterizerOptions, f: F) -> PdfRasterizerStatus
+where
+    F: FnOnce(&mut RasterizeOptions) -> Result<()>,
+{
+    let Some(options) = options.as_mut() else {
+        return fail(
+            PdfRasterizerStatus::InvalidArgument,
+            &invalid_argument("options"),
+        );
+    };
+    match catch_unwind(AssertUnwindSafe(|| f(&mut options.0))) {
+        Ok(Ok(())) => {
+            clear_last_error();
+            PdfRasterizerStatus::Ok
+        }
+        Ok(Err(e)) => fail(PdfRasterizerStatus::InvalidArgument, &e),
+        Err(_) => panicked(),
+    }
+}
+
+fn invalid_argument(name: &str) -> anyhow::Error {
+    Error::InvalidArgument {
+        name: name.to_string(),
+    }
+    .into()
+}
+
+/// エラーを記録して`status`を返す
+fn fail(status: PdfRasterizerStatus, error: &anyhow::Error) -> PdfRasterizerStatus {
+    let message = Error::from_anyhow(error).to_string();
+    // NUL文字は含まれないはずだが、含まれていれば取り除く
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+    status
+}
+
+/// パニックを捕まえたことを記録して`Internal`を返す
+fn panicked() -> PdfRasterizerStatus {
+    fail(
+        PdfRasterizerStatus::Internal,
+        &anyhow::anyhow!("rasterizer panicked"),
+    )
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = None);
+}
+
+fn progress_event(event: ProgressEvent) -> PdfRasterizerProgress {
+    let mut progress = PdfRasterizerProgress {
+        kind: PdfRasterizerProgressKind::Parsed,
+        index: 0,
+        done: 0,
+        total: 0,
+        bytes: 0,
+    };
+    match event {
+        ProgressEvent::Parsed { pages } => progress.total = pages,
+        ProgressEvent::PageRendered { index, bytes } => {
+            progress.kind = PdfRasterizerProgressKind::PageRendered;
+            progress.index = index;
+            progress.bytes = bytes as u64;
+        }
+        ProgressEvent::Assembling { done, total } => {
+            progress.kind = PdfRasterizerProgressKind::Assembling;
+            progress.done = done;
+            progress.total = total;
+        }
+        ProgressEvent::Saved { bytes } => {
+            progress.kind = PdfRasterizerProgressKind::Saved;
+            progress.bytes = bytes;
+        }
+    }
+    progress
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> Option<String> {
+        let message = pdf_rasterizer_last_error();
+        // SAFETY: `pdf_rasterizer_last_error`はNULLか終端のある文字列を返す
+        (!message.is_null()).then(|| {
+            unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    /// オプションを作成して`f`に渡し、終わったら解放する
+    fn with_new_options(f: impl FnOnce(*mut PdfRasterizerOptions)) {
+        let options = pdf_rasterizer_options_new();
+        assert!(!options.is_null());
+        f(options);
+        // SAFETY: `pdf_rasterizer_options_new`が返したポインタを1回だけ解放する
+        unsafe { pdf_rasterizer_options_free(options) };
+    }
+
+    fn assert_invalid(status: PdfRasterizerStatus, name: &str) {
+        assert_eq!(status, PdfRasterizerStatus::InvalidArgument);
+        assert_eq!(last_error(), Some(format!("invalid argument: {}", name)));
+    }
+
+    #[test]
+    fn null_options_are_rejected() {
+        // SAFETY: NULLを渡した場合は何も読み書きしない
+        unsafe {
+            assert_invalid(
+                pdf_rasterizer_options_set_dpi(ptr::null_mut(), 150),
+                "options",
+            );
+            assert_invalid(
+                pdf_rasterizer_options_set_limits(ptr::null_mut(), 0, 0, 0, 0, 0),
+                "options",
+            );
+            pdf_rasterizer_options_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn invalid_values_are_rejected_and_recorded() {
+        with_new_options(|options| unsafe {
+            assert_invalid(pdf_rasterizer_options_set_dpi(options, 0), "dpi");
+            assert_invalid(pdf_rasterizer_options_set_quality(options, 0), "quality");
+            assert_invalid(pdf_rasterizer_options_set_quality(options, 101), "quality");
+            assert_invalid(
+                pdf_rasterizer_options_set_max_size(options, 0, 0, f32::NAN),
+                "max_megapixels",
+            );
+            assert_invalid(
+                pdf_rasterizer_options_set_max_size(options, 0, 0, -1.0),
+                "max_megapixels",
+            );
+
+            let options = &(*options).0;
+            assert_eq!(options.dpi, RasterizeOptions::default().dpi);
+            assert_eq!(options.quality, RasterizeOptions::default().quality);
+        });
+    }
+
+    #[test]
+    fn undefined_enum_values_are_rejected() {
+        with_new_options(|options| unsafe {
+            assert_invalid(
+                pdf_rasterizer_options_set_paper(options, 3, PdfRasterizerPaperFit::Fit as u32),
+                "paper",
+            );
+            assert_invalid(
+                pdf_rasterizer_options_set_paper(options, PdfRasterizerPaper::A4 as u32, 2),
+                "fit",
+            );
+            assert_invalid(
+                pdf_rasterizer_options_set_color_space(options, 2),
+                "color_space",
+            );
+            assert_invalid(
+                pdf_rasterizer_options_set_encoding(options, u32::MAX),
+                "encoding",
+            );
+            assert_eq!((*options).0.paper, None);
+        });
+    }
+
+    #[test]
+    fn valid_values_are_applied_and_clear_the_last_error() {
+        with_new_options(|options| unsafe {
+            assert_invalid(pdf_rasterizer_options_set_dpi(options, 0), "dpi");
+
+            assert_eq!(
+                pdf_rasterizer_options_set_paper(
+                    options,
+                    PdfRasterizerPaper::Letter as u32,
+                    PdfRasterizerPaperFit::Fill as u32,
+                ),
+                PdfRasterizerStatus::Ok
+            );
+            assert_eq!(last_error(), None);
+            assert_eq!(
+                pdf_rasterizer_options_set_encoding(options, PdfRasterizerEncoding::Flate as u32),
+                PdfRasterizerStatus::Ok
+            );
+            assert_eq!(
+                pdf_rasterizer_options_set_limits(options, 0, 10, 0, 0, 0),
+                PdfRasterizerStatus::Ok
+            );
+
+            let options = &(*options).0;
+            assert_eq!(options.paper, Some(PaperSize::Letter));
+            assert_eq!(options.paper_fit, PaperFit::Fill);
+            assert_eq!(options.encoding, ImageEncoding::Flate);
+            assert_eq!(
+                options.limits,
+                SafetyLimits {
+                    max_input_bytes: None,
+                    max_pages: Some(10),
+                    max_page_pixels: None,
+                    max_total_pixels: None,
+                    max_page_render_ms: None,
+                }
+            );
+        });
+    }
+
+    #[test]
+    fn page_ranges_are_parsed() {
+        with_new_options(|options| unsafe {
+            assert_eq!(
+                pdf_rasterizer_options_set_pages(options, c"1-3,5".as_ptr()),
+                PdfRasterizerStatus::Ok
+            );
+            assert_eq!((*options).0.pages, Some(vec![0, 1, 2, 4]));
+
+            assert_eq!(
+                pdf_rasterizer_options_set_pages(options, c"3-1".as_ptr()),
+                PdfRasterizerStatus::InvalidArgument
+            );
+            assert_eq!(
+                last_error().as_deref(),
+                Some("page range starts after it ends: 3-1")
+            );
+            assert_invalid(
+                pdf_rasterizer_options_set_pages(options, c"\xFF".as_ptr()),
+                "ranges",
+            );
+            // 失敗した場合は前の指定のまま
+            assert_eq!((*options).0.pages, Some(vec![0, 1, 2, 4]));
+
+            assert_eq!(
+                pdf_rasterizer_options_set_pages(options, ptr::null()),
+                PdfRasterizerStatus::Ok
+            );
+            assert_eq!((*options).0.pages, None);
+        });
+    }
+
+    #[test]
+    fn oversized_page_ranges_are_rejected() {
+        with_new_options(|options| unsafe {
+            assert_eq!(
+                pdf_rasterizer_options_set_pages(options, c"1-99999999999".as_ptr()),
+                PdfRasterizerStatus::InvalidArgument
+            );
+            assert_eq!(
+                last_error().as_deref(),
+                Some("page number 99999999999 exceeds the maximum of 1000000")
+            );
+            assert_eq!((*options).0.pages, None);
+        });
+    }
+
+    #[test]
+    fn panics_while_changing_options_are_caught() {
+        with_new_options(|options| unsafe {
+            let status = with_options(options, |_| panic!("setter panicked"));
+            assert_eq!(status, PdfRasterizerStatus::Internal);
+            assert_eq!(last_error().as_deref(), Some("rasterizer panicked"));
+
+            // パニックの後もオプションは使える
+            assert_eq!(
+                pdf_rasterizer_options_set_dpi(options, 150),
+                PdfRasterizerStatus::Ok
+            );
+            assert_eq!((*options).0.dpi, 150);
+        });
+    }
+
+    #[test]
+    fn rasterize_rejects_null_pointers() {
+        let data = b"%PDF-1.7";
+        let mut output = PdfRasterizerBuffer {
+            data: ptr::NonNull::dangling().as_ptr(),
+            len: 1,
+        };
+        // SAFETY: NULLのポインタは読み書きせず、`output`は書き込み可能
+        unsafe {
+            assert_invalid(
+                pdf_rasterizer_rasterize(data.as_ptr(), data.len(), ptr::null(), ptr::null_mut()),
+                "output",
+            );
+            assert_invalid(
+                pdf_rasterizer_rasterize(ptr::null(), 0, ptr::null(), &mut output),
+                "data",
+            );
+        }
+        // 失敗した場合も`output`は空にする
+        assert!(output.data.is_null());
+        assert_eq!(output.len, 0);
+    }
+
+    #[test]
+    fn rasterize_reports_conversion_failures() {
+        let data = b"%PDF-1.7\n";
+        with_new_options(|options| unsafe {
+            pdf_rasterizer_options_set_limits(options, 4, 0, 0, 0, 0);
+            let mut output = PdfRasterizerBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(
+                pdf_rasterizer_rasterize(data.as_ptr(), data.len(), options, &mut output),
+                PdfRasterizerStatus::Failed
+            );
+            assert!(output.data.is_null());
+            assert_eq!(
+                last_error().as_deref(),
+                Some("input PDF is 9 bytes, which exceeds the limit of 4 bytes")
+            );
+            // NULLのバッファの解放は何もしない
+            pdf_rasterizer_buffer_free(output);
+        });
+    }
+
+    #[test]
+    fn last_error_is_per_thread() {
+        with_new_options(|options| unsafe {
+            assert_invalid(pdf_rasterizer_options_set_dpi(options, 0), "dpi");
+        });
+        assert_eq!(std::thread::spawn(last_error).join().unwrap(), None);
+        assert!(last_error().is_some());
+    }
+}
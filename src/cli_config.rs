@@ -0,0 +1,339 @@
+//! `pdf_rasterizer.toml`: チームで共有する変換の設定を読み込む
+//!
+//! 設定ファイルの項目はライブラリの`RasterizeOptions`と同じ名前で、`[profiles.<名前>]`に
+//! 名前付きのプロファイルを定義できる。トップレベルの項目、`--profile`で選んだプロファイル、
+//! コマンドラインのフラグの順に上書きする。
+
+use anyhow::{anyhow, bail, Result};
+use pdf_rasterizer::i18n::{tr, trf, Lang};
+use pdf_rasterizer::RasterizeOptions;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// 設定ファイルの名前
+pub const CONFIG_FILE_NAME: &str = "pdf_rasterizer.toml";
+
+/// プロファイルを定義するテーブルの名前
+const PROFILES_KEY: &str = "profiles";
+
+/// 設定ファイルから読み込んだ設定
+pub struct Config {
+    /// 読み込んだ設定ファイル（見つからなければ`None`）
+    pub path: Option<PathBuf>,
+    /// 適用したプロファイル
+    pub profile: Option<String>,
+    pub options: RasterizeOptions,
+}
+
+/// 設定ファイルを探す
+///
+/// カレントディレクトリから親ディレクトリへ順に探し、見つからなければ
+/// `$XDG_CONFIG_HOME`（未設定なら`~/.config`）を探す。
+pub fn find() -> Option<PathBuf> {
+    let project = std::env::current_dir().ok().and_then(|dir| {
+        dir.ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+    });
+    project.or_else(|| {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_home.join(CONFIG_FILE_NAME)).filter(|path| path.is_file())
+    })
+}
+
+/// 設定ファイルを読み込み、`profile`を適用したオプションを返す
+///
+/// 設定ファイルがなければデフォルトのオプションを返す（`profile`を指定した場合はエラー）。
+pub fn load(lang: Lang, profile: Option<&str>) -> Result<Config> {
+    let Some(path) = find() else {
+        if let Some(profile) = profile {
+            bail!(trf(
+                lang,
+                "cli.config.not_found",
+                &[&profile, &CONFIG_FILE_NAME]
+            ));
+        }
+        return Ok(Config {
+            path: None,
+            profile: None,
+            options: RasterizeOptions::default(),
+        });
+    };
+
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow!(trf(lang, "cli.config.read", &[&path.display(), &e])))?;
+    let options = parse(lang, &path, &text, profile)?;
+    Ok(Config {
+        path: Some(path),
+        profile: profile.map(str::to_string),
+        options,
+    })
+}
+
+/// 設定ファイルの内容からオプションを作成する
+fn parse(lang: Lang, path: &Path, text: &str, profile: Option<&str>) -> Result<RasterizeOptions> {
+    let invalid =
+        |e: &dyn std::fmt::Display| anyhow!(trf(lang, "cli.config.parse", &[&path.display(), e]));
+
+    let mut table: Table = text.parse().map_err(|e| invalid(&e))?;
+    let profiles = match table.remove(PROFILES_KEY) {
+        Some(Value::Table(profiles)) => profiles,
+        Some(_) => return Err(invalid(&tr(lang, "cli.config.profiles_not_table"))),
+        None => Table::new(),
+    };
+
+    if let Some(name) = profile {
+        match profiles.get(name) {
+            Some(Value::Table(overrides)) => merge(&mut table, overrides.clone()),
+            Some(_) => {
+                return Err(invalid(&trf(
+                    lang,
+                    "cli.config.profile_not_table",
+                    &[&name],
+                )))
+            }
+            None => {
+                let available: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                bail!(trf(
+                    lang,
+                    "cli.config.unknown_profile",
+                    &[&name, &path.display(), &available.join(", ")]
+                ));
+            }
+        }
+    }
+
+    // 項目名の誤りを見逃さないよう、`RasterizeOptions`にない項目はエラーにする
+    let known = serde_json::to_value(RasterizeOptions::default())?;
+    check_keys(lang, &table, &known, "").map_err(|e| invalid(&e))?;
+
+    let options: RasterizeOptions = table.try_into().map_err(|e| invalid(&e))?;
+    validate(lang, &options).map_err(|e| invalid(&e))?;
+    Ok(options)
+}
+
+/// コマンドラインのフラグと同じ規則で値を確認する
+///
+/// フラグでは`0`で制限しない上限も、設定ファイルでは項目を省略して指定するため`0`はエラーにする。
+fn validate(lang: Lang, options: &RasterizeOptions) -> Result<()> {
+    if options.dpi == 0 {
+        bail!(trf(
+            lang,
+            "cli.config.not_positive",
+            &[&"dpi", &options.dpi]
+        ));
+    }
+    if let Some(max_dpi) = options.auto_dpi.filter(|&max_dpi| max_dpi == 0) {
+        bail!(trf(
+            lang,
+            "cli.config.not_positive",
+            &[&"auto_dpi", &max_dpi]
+        ));
+    }
+    if !(1..=100).contains(&options.quality) {
+        bail!(trf(lang, "cli.config.invalid_quality", &[&options.quality]));
+    }
+
+    let limits = &options.limits;
+    let maximums = [
+        ("max_width", options.max_width.map(f64::from)),
+        ("max_height", options.max_height.map(f64::from)),
+        ("max_megapixels", options.max_megapixels.map(f64::from)),
+        (
+            "limits.max_input_bytes",
+            limits.max_input_bytes.map(|v| v as f64),
+        ),
+        ("limits.max_pages", limits.max_pages.map(|v| v as f64)),
+        (
+            "limits.max_page_pixels",
+            limits.max_page_pixels.map(|v| v as f64),
+        ),
+        (
+            "limits.max_total_pixels",
+            limits.max_total_pixels.map(|v| v as f64),
+        ),
+        (
+            "limits.max_page_render_ms",
+            limits.max_page_render_ms.map(|v| v as f64),
+        ),
+    ];
+    for (name, value) in maximums {
+        if let Some(value) = value.filter(|value| !(value.is_finite() && *value > 0.0)) {
+            bail!(trf(lang, "cli.config.invalid_maximum", &[&name, &value]));
+        }
+    }
+    Ok(())
+}
+
+/// `overrides`の項目で`base`を上書きする（テーブルは項目ごとに上書きする）
+fn merge(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overrides)) => merge(base, overrides),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// `table`の項目がすべて`known`（デフォルトのオプションをJSONにしたもの）にあるか確認する
+fn check_keys(lang: Lang, table: &Table, known: &serde_json::Value, prefix: &str) -> Result<()> {
+    for (key, value) in table {
+        let name = format!("{}{}", prefix, key);
+        let Some(known) = known.get(key) else {
+            bail!(trf(lang, "cli.config.unknown_key", &[&name]));
+        };
+        if let (Value::Table(table), serde_json::Value::Object(_)) = (value, known) {
+            check_keys(lang, table, known, &format!("{}.", name))?;
+        }
+    }
+    Ok(())
+}
+
+/// 有効な設定をTOMLで表示する（読み込んだ設定ファイルとプロファイルはコメントにする）
+pub fn show(lang: Lang, config: &Config) -> Result<()> {
+    match &config.path {
+        Some(path) => println!("# {}", trf(lang, "cli.config.source", &[&path.display()])),
+        None => println!(
+            "# {}",
+            trf(lang, "cli.config.no_source", &[&CONFIG_FILE_NAME])
+        ),
+    }
+    if let Some(profile) = &config.profile {
+        println!("# {}", trf(lang, "cli.config.profile", &[profile]));
+    }
+    print!("{}", toml::to_string(&config.options)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pdf_rasterizer::{ColorSpace, ImageEncoding};
+
+    const CONFIG: &str = r#"
+dpi = 150
+quality = 80
+
+[limits]
+max_pages = 500
+max_input_bytes = 1000000
+
+[profiles.archive]
+dpi = 300
+encoding = "flate"
+
+[profiles.archive.limits]
+max_pages = 50
+
+[profiles.broken]
+dpi = 0
+"#;
+
+    fn parse_config(text: &str, profile: Option<&str>) -> Result<RasterizeOptions> {
+        parse(Lang::En, Path::new("pdf_rasterizer.toml"), text, profile)
+    }
+
+    fn parse_error(text: &str, profile: Option<&str>) -> String {
+        parse_config(text, profile).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn empty_config_uses_the_defaults() {
+        let options = parse_config("", None).unwrap();
+        let defaults = RasterizeOptions::default();
+        assert_eq!(options.dpi, defaults.dpi);
+        assert_eq!(options.quality, defaults.quality);
+        assert_eq!(options.limits, defaults.limits);
+    }
+
+    #[test]
+    fn top_level_settings_apply_without_a_profile() {
+        let options = parse_config(CONFIG, None).unwrap();
+        assert_eq!(options.dpi, 150);
+        assert_eq!(options.quality, 80);
+        assert_eq!(options.encoding, ImageEncoding::default());
+        assert_eq!(options.limits.max_pages, Some(500));
+    }
+
+    #[test]
+    fn profile_overrides_top_level_settings_per_key() {
+        let options = parse_config(CONFIG, Some("archive")).unwrap();
+        assert_eq!(options.dpi, 300);
+        assert_eq!(options.encoding, ImageEncoding::Flate);
+        // プロファイルにない項目はトップレベルの値のまま
+        assert_eq!(options.quality, 80);
+        assert_eq!(options.color_space, ColorSpace::default());
+        // テーブルは項目ごとに上書きする
+        assert_eq!(options.limits.max_pages, Some(50));
+        assert_eq!(options.limits.max_input_bytes, Some(1_000_000));
+    }
+
+    #[test]
+    fn unknown_profile_lists_the_defined_ones() {
+        let message = parse_error(CONFIG, Some("web"));
+        assert!(message.contains("web"), "{}", message);
+        assert!(message.contains("archive, broken"), "{}", message);
+    }
+
+    #[test]
+    fn non_table_profiles_are_rejected() {
+        assert!(parse_error("profiles = 1", None).contains("profiles must be a table"));
+        assert!(parse_error("[profiles]\nweb = 1", Some("web")).contains("profile web"));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected_with_their_full_name() {
+        let message = parse_error("dpi = 150\nqualty = 80", None);
+        assert!(message.contains("unknown setting: qualty"), "{}", message);
+
+        let message = parse_error("[limits]\nmax_page = 10", None);
+        assert!(
+            message.contains("unknown setting: limits.max_page"),
+            "{}",
+            message
+        );
+
+        let message = parse_error("[profiles.web]\ndpi = 96\nsize = 1", Some("web"));
+        assert!(message.contains("unknown setting: size"), "{}", message);
+    }
+
+    #[test]
+    fn unselected_profiles_are_not_checked() {
+        // 選ばなかったプロファイルの誤りは、そのプロファイルを使うまでエラーにしない
+        parse_config(CONFIG, None).unwrap();
+        parse_config(CONFIG, Some("archive")).unwrap();
+    }
+
+    #[test]
+    fn values_are_validated_like_the_flags() {
+        let message = parse_error(CONFIG, Some("broken"));
+        assert!(message.contains("pdf_rasterizer.toml"), "{}", message);
+        assert!(
+            message.contains("dpi must be greater than 0"),
+            "{}",
+            message
+        );
+
+        assert!(parse_error("quality = 0", None).contains("quality must be between 1 and 100"));
+        assert!(parse_error("quality = 101", None).contains("quality must be between 1 and 100"));
+        assert!(parse_error("auto_dpi = 0", None).contains("auto_dpi"));
+        assert!(parse_error("max_width = 0", None).contains("max_width"));
+        assert!(parse_error("max_megapixels = -1.0", None).contains("max_megapixels"));
+        assert!(parse_error("[limits]\nmax_pages = 0", None).contains("limits.max_pages"));
+    }
+
+    #[test]
+    fn invalid_toml_reports_the_config_path() {
+        let message = parse_error("dpi = ", None);
+        assert!(
+            message.starts_with("Invalid config file pdf_rasterizer.toml"),
+            "{}",
+            message
+        );
+    }
+}
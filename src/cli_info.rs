@@ -0,0 +1,151 @@
+//! `info`サブコマンド: 変換前にPDFのページ・フォント・画像などを表示する
+
+use anyhow::{Context, Result};
+use pdf_rasterizer::i18n::{tr, trf, Lang};
+use pdf_rasterizer::{format_page_ranges, inspect_pdf, ImageDetails, PageDetails, PdfDetails};
+use std::path::Path;
+
+/// 1インチあたりのミリメートル
+const MM_PER_INCH: f32 = 25.4;
+
+/// PDFを読み込んで詳細を表示する（`json`ならJSONで出力する）
+pub fn run(lang: Lang, input: &Path, json: bool) -> Result<()> {
+    let data = if crate::is_stdio(input) {
+        crate::read_stdin(lang, None)?
+    } else {
+        std::fs::read(input).with_context(|| pdf_rasterizer::Error::ReadInput {
+            path: input.to_path_buf(),
+        })?
+    };
+    let details = inspect_pdf(&data)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&details)?);
+    } else {
+        print_details(
+            lang,
+            &crate::display_path(lang, input, "cli.stdin"),
+            &details,
+        );
+    }
+    Ok(())
+}
+
+fn print_details(lang: Lang, input: &str, details: &PdfDetails) {
+    let yes_no = |value: bool| tr(lang, if value { "cli.info.yes" } else { "cli.info.no" });
+
+    println!("{}", trf(lang, "cli.info.file", &[&input]));
+    println!("{}", trf(lang, "cli.info.version", &[&details.version]));
+    println!(
+        "{}",
+        trf(lang, "cli.info.encrypted", &[&yes_no(details.encrypted)])
+    );
+    println!("{}", trf(lang, "cli.info.pages", &[&details.pages.len()]));
+    println!(
+        "{}",
+        trf(
+            lang,
+            "cli.info.transparency",
+            &[&yes_no(details.transparency)]
+        )
+    );
+    println!(
+        "{}",
+        trf(lang, "cli.info.javascript", &[&yes_no(details.javascript)])
+    );
+
+    for page in &details.pages {
+        println!();
+        print_page(lang, page);
+    }
+
+    println!();
+    println!("{}", trf(lang, "cli.info.fonts", &[&details.fonts.len()]));
+    for font in &details.fonts {
+        let embedded = if font.embedded {
+            tr(lang, "cli.info.embedded")
+        } else {
+            tr(lang, "cli.info.not_embedded")
+        };
+        let kind = match &font.subtype {
+            Some(subtype) => format!("{}, {}", subtype, embedded),
+            None => embedded.to_string(),
+        };
+        println!(
+            "{}",
+            trf(
+                lang,
+                "cli.info.font",
+                &[&font.name, &kind, &format_page_ranges(&font.pages)]
+            )
+        );
+    }
+}
+
+fn print_page(lang: Lang, page: &PageDetails) {
+    let (width, height) = page.size();
+    println!(
+        "{}",
+        trf(
+            lang,
+            "cli.info.page",
+            &[
+                &(page.index + 1),
+                &format!("{:.1}", width),
+                &format!("{:.1}", height),
+                &format!("{:.1}", width / 72.0 * MM_PER_INCH),
+                &format!("{:.1}", height / 72.0 * MM_PER_INCH),
+                &page.rotation,
+            ]
+        )
+    );
+
+    let boxes = [
+        ("MediaBox", Some(page.media_box)),
+        ("CropBox", page.crop_box),
+        ("BleedBox", page.bleed_box),
+        ("TrimBox", page.trim_box),
+        ("ArtBox", page.art_box),
+    ];
+    for (name, page_box) in boxes {
+        if let Some(page_box) = page_box {
+            println!(
+                "  {}: [{} {} {} {}]",
+                name, page_box.left, page_box.bottom, page_box.right, page_box.top
+            );
+        }
+    }
+
+    if page.images.is_empty() {
+        return;
+    }
+    println!("{}", trf(lang, "cli.info.images", &[&page.images.len()]));
+    for image in &page.images {
+        println!("    {}", image_line(lang, image));
+    }
+    if let Some(dpi) = page.max_image_dpi() {
+        println!("{}", trf(lang, "cli.info.max_dpi", &[&dpi.round()]));
+    }
+}
+
+/// 画像の大きさ・形式・解像度を1行にまとめる
+fn image_line(lang: Lang, image: &ImageDetails) -> String {
+    let mut attributes = Vec::new();
+    attributes.extend(image.color_space.clone());
+    attributes.extend(
+        image
+            .bits_per_component
+            .map(|bits| trf(lang, "cli.info.bits", &[&bits])),
+    );
+    attributes.extend(image.filters.iter().cloned());
+
+    let dpi = match (image.dpi_x, image.dpi_y) {
+        (Some(x), Some(y)) => trf(lang, "cli.info.image_dpi", &[&x.round(), &y.round()]),
+        _ => tr(lang, "cli.info.image_dpi_unknown").to_string(),
+    };
+    trf(
+        lang,
+        "cli.info.image",
+        &[&image.width, &image.height, &attributes.join(", "), &dpi],
+    )
+}
@@ -0,0 +1,105 @@
+//! `--report`: 変換結果の詳細をJSONで出力する
+//!
+//! ライブラリが返す`RasterizeReport`に、ファイルごとの結果（成功・スキップ・失敗）を加えて出力する。
+
+use anyhow::{Context, Result};
+use pdf_rasterizer::i18n::{error_message, tr, trf, Lang};
+use pdf_rasterizer::{Cancelled, Error, PageFailed, RasterizeReport};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 詳細の出力先
+pub struct ReportTarget {
+    /// 書き出すファイル（`None`なら標準出力）
+    pub path: Option<PathBuf>,
+}
+
+/// 1ファイルの変換結果
+#[derive(Debug, Serialize)]
+pub struct FileReport {
+    pub input: String,
+    pub output: String,
+    #[serde(flatten)]
+    pub status: FileStatus,
+}
+
+/// ファイルの変換結果の種類
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum FileStatus {
+    Converted {
+        report: RasterizeReport,
+    },
+    /// 出力ファイルが既にあるため変換しなかった
+    Skipped,
+    Failed {
+        error: Error,
+        message: String,
+        /// 特定のページの処理で失敗した場合はそのページ（入力PDFでの1始まりの番号）
+        page: Option<usize>,
+    },
+    Cancelled,
+}
+
+/// `batch`の詳細
+#[derive(Debug, Serialize)]
+pub struct BatchReport<'a> {
+    pub files: &'a [FileReport],
+    pub converted: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// `--report`と`--report-file`から出力先を決める（どちらもなければ`None`）
+///
+/// `--report-file`だけを指定した場合もJSONで出力する。
+pub fn target(lang: Lang, c: &seahorse::Context) -> Result<Option<ReportTarget>, String> {
+    let path = c.string_flag("report-file").ok().map(PathBuf::from);
+    match c.string_flag("report") {
+        Ok(format) if format != "json" => Err(trf(lang, "cli.report.unknown_format", &[&format])),
+        Ok(_) => Ok(Some(ReportTarget { path })),
+        Err(_) => Ok(path.map(|path| ReportTarget { path: Some(path) })),
+    }
+}
+
+impl FileReport {
+    pub fn new(input: &Path, output: &Path, status: FileStatus) -> Self {
+        Self {
+            input: input.display().to_string(),
+            output: output.display().to_string(),
+            status,
+        }
+    }
+}
+
+impl FileStatus {
+    /// 変換の結果から作成する
+    pub fn from_result(lang: Lang, result: &Result<RasterizeReport>) -> Self {
+        match result {
+            Ok(report) => Self::Converted {
+                report: report.clone(),
+            },
+            Err(e) if e.is::<Cancelled>() => Self::Cancelled,
+            Err(e) => Self::Failed {
+                error: Error::from_anyhow(e),
+                message: error_message(lang, e),
+                page: e.downcast_ref::<PageFailed>().map(|failed| failed.page),
+            },
+        }
+    }
+}
+
+/// 詳細をJSONで書き出す
+pub fn write<T: Serialize>(lang: Lang, target: &ReportTarget, report: &T) -> Result<()> {
+    let json = serde_json::to_string_pretty(report)?;
+    let written = match &target.path {
+        Some(path) => std::fs::write(path, format!("{}\n", json)),
+        None => writeln!(std::io::stdout().lock(), "{}", json),
+    };
+    let destination = match &target.path {
+        Some(path) => path.display().to_string(),
+        None => tr(lang, "cli.stdout").to_string(),
+    };
+    written.with_context(|| trf(lang, "cli.report.write", &[&destination]))
+}
@@ -0,0 +1,165 @@
+use anyhow::Result;
+use hayro::{InterpreterSettings, Pdf};
+use hayro_syntax::page::Page;
+use serde::{Deserialize, Serialize};
+
+use crate::encoding::PageImage;
+use crate::{Error, ImageEncoding, RasterizeOptions, SafetyLimits};
+
+/// 1ページあたりのPDFオブジェクト（Page・画像XObject・コンテンツストリーム・相互参照）のおおよそのサイズ
+const PAGE_OVERHEAD: u64 = 400;
+/// ヘッダー・Catalog・Pages・Trailerのおおよそのサイズ
+const FILE_OVERHEAD: u64 = 300;
+
+/// パース済みのPDF文書
+///
+/// サムネイルやプレビューのように同じPDFのページを繰り返し画像化する場合に、
+/// 毎回パースし直さずに済む。
+pub struct Document {
+    pdf: Pdf,
+}
+
+/// 変換前後を比較するためのページ画像
+#[derive(Debug, Clone)]
+pub struct PagePreview {
+    /// 元のページをレンダリングした画像（PNG、劣化なし）
+    pub original_png: Vec<u8>,
+    /// 出力PDFに埋め込まれる画像を表示用にした画像
+    ///
+    /// JPEGの場合は埋め込まれるデータそのもの、Deflateの場合はPNG。
+    pub encoded_image: Vec<u8>,
+    /// `encoded_image`のMIMEタイプ
+    pub encoded_mime_type: &'static str,
+    /// 出力PDFに埋め込まれる画像のサイズ（バイト）
+    pub encoded_bytes: usize,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 出力PDFのサイズの見積もり
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeEstimate {
+    /// 見積もった出力PDFのサイズ（バイト）
+    pub bytes: u64,
+    /// 変換するページ数
+    pub pages: usize,
+    /// 見本としてエンコードしたページの番号（0始まり）
+    pub sample_page: usize,
+    /// 見本のページのエンコード後のサイズ（バイト）
+    pub sample_bytes: usize,
+}
+
+impl Document {
+    /// PDFデータをパースする
+    pub fn load<D>(pdf_data: D) -> Result<Self>
+    where
+        D: AsRef<[u8]> + Send + Sync + 'static,
+    {
+        Ok(Self {
+            pdf: crate::parse_pdf(pdf_data)?,
+        })
+    }
+
+    /// ページ数
+    pub fn page_count(&self) -> usize {
+        self.pdf.pages().len()
+    }
+
+    /// ページを指定したDPIでレンダリングしてPNGにエンコードする
+    pub fn render_page_png(&self, index: usize, dpi: u32) -> Result<Vec<u8>> {
+        let page = self.page(index)?;
+        SafetyLimits::default().check_page(page, index, dpi as f32 / 72.0)?;
+        let image_buffer = crate::render_page_rgb(
+            page,
+            &InterpreterSettings::default(),
+            &crate::render_settings(dpi),
+        )?;
+        PageImage::Rgb(image_buffer).encode_png()
+    }
+
+    /// ページを変換前と変換後の両方の画像にする
+    pub fn preview_page(&self, index: usize, options: &RasterizeOptions) -> Result<PagePreview> {
+        let page = self.page(index)?;
+        options
+            .limits
+            .check_page(page, index, options.dpi as f32 / 72.0)?;
+        let image_buffer = crate::render_page_rgb(
+            page,
+            &InterpreterSettings::default(),
+            &crate::render_settings(options.dpi),
+        )?;
+        let original_png = PageImage::Rgb(image_buffer.clone()).encode_png()?;
+
+        let image = PageImage::new(image_buffer, options.color_space);
+        let encoded = image.encode(options.encoding, options.quality)?;
+        let encoded_bytes = encoded.len();
+        let encoded_image = match options.encoding {
+            ImageEncoding::Jpeg => encoded,
+            // FlateDecodeの画像はブラウザで表示できないため、同じ画素のPNGにする
+            ImageEncoding::Flate => image.encode_png()?,
+        };
+
+        Ok(PagePreview {
+            original_png,
+            encoded_image,
+            encoded_mime_type: preview_mime_type(options.encoding),
+            encoded_bytes,
+            width: image.width(),
+            height: image.height(),
+        })
+    }
+
+    /// 見本のページを1枚だけエンコードして出力PDFのサイズを見積もる
+    ///
+    /// 変換するページの中央のページを見本とし、他のページは見本との面積比で概算する。
+    pub fn estimate_output_size(&self, options: &RasterizeOptions) -> Result<SizeEstimate> {
+        let pages = self.pdf.pages();
+        let selected = options.selected_pages(pages.len())?;
+        let sample_page = selected[selected.len() / 2];
+        options
+            .limits
+            .check_page(&pages[sample_page], sample_page, options.dpi as f32 / 72.0)?;
+
+        let sample = crate::process_page(
+            &pages[sample_page],
+            &InterpreterSettings::default(),
+            &crate::render_settings(options.dpi),
+            options,
+        )?;
+        let sample_bytes = sample.data.len();
+        let sample_area = page_area(&pages[sample_page]).max(1.0);
+
+        let images: f64 = selected
+            .iter()
+            .map(|&index| sample_bytes as f64 * page_area(&pages[index]) / sample_area)
+            .sum();
+
+        Ok(SizeEstimate {
+            bytes: images as u64 + PAGE_OVERHEAD * selected.len() as u64 + FILE_OVERHEAD,
+            pages: selected.len(),
+            sample_page,
+            sample_bytes,
+        })
+    }
+
+    fn page(&self, index: usize) -> Result<&Page<'_>> {
+        self.pdf
+            .pages()
+            .get(index)
+            .ok_or_else(|| Error::PageNotFound { page: index + 1 }.into())
+    }
+}
+
+/// `PagePreview::encoded_image`のMIMEタイプ
+pub(crate) fn preview_mime_type(encoding: ImageEncoding) -> &'static str {
+    match encoding {
+        ImageEncoding::Jpeg => "image/jpeg",
+        ImageEncoding::Flate => "image/png",
+    }
+}
+
+/// ページの面積（ポイント単位）
+fn page_area(page: &Page) -> f64 {
+    let (width, height) = page.render_dimensions();
+    width as f64 * height as f64
+}
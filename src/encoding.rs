@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+use crate::Error;
+
+/// デフォルトのJPEG品質
+pub const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+/// 出力PDFに埋め込む画像の色空間
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+    /// カラー（DeviceRGB）
+    #[default]
+    Rgb,
+    /// グレースケール（DeviceGray）
+    Gray,
+}
+
+/// 出力PDFに埋め込む画像の圧縮方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageEncoding {
+    /// JPEG（DCTDecode、非可逆）
+    #[default]
+    Jpeg,
+    /// Deflate（FlateDecode、可逆）
+    Flate,
+}
+
+impl ColorSpace {
+    /// PDFの色空間名
+    pub(crate) fn pdf_name(self) -> &'static str {
+        match self {
+            Self::Rgb => "DeviceRGB",
+            Self::Gray => "DeviceGray",
+        }
+    }
+}
+
+impl ImageEncoding {
+    /// PDFのフィルター名
+    pub(crate) fn pdf_filter(self) -> &'static str {
+        match self {
+            Self::Jpeg => "DCTDecode",
+            Self::Flate => "FlateDecode",
+        }
+    }
+}
+
+/// 色空間を変換したページ画像
+pub(crate) enum PageImage {
+    Rgb(image::RgbImage),
+    Gray(image::GrayImage),
+}
+
+impl PageImage {
+    /// レンダリングしたRGB画像を指定した色空間に変換する
+    pub fn new(image_buffer: image::RgbImage, color_space: ColorSpace) -> Self {
+        match color_space {
+            ColorSpace::Rgb => Self::Rgb(image_buffer),
+            ColorSpace::Gray => Self::Gray(image::imageops::grayscale(&image_buffer)),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        match self {
+            Self::Rgb(image) => image.width(),
+            Self::Gray(image) => image.width(),
+        }
+    }
+
+    pub fn height(&self) -> u32 {
+        match self {
+            Self::Rgb(image) => image.height(),
+            Self::Gray(image) => image.height(),
+        }
+    }
+
+    fn raw(&self) -> &[u8] {
+        match self {
+            Self::Rgb(image) => image.as_raw(),
+            Self::Gray(image) => image.as_raw(),
+        }
+    }
+
+    fn color_type(&self) -> image::ColorType {
+        match self {
+            Self::Rgb(_) => image::ColorType::Rgb8,
+            Self::Gray(_) => image::ColorType::L8,
+        }
+    }
+
+    /// 出力PDFに埋め込む形式にエンコードする
+    pub fn encode(&self, encoding: ImageEncoding, quality: u8) -> Result<Vec<u8>> {
+        match encoding {
+            ImageEncoding::Jpeg => self.encode_jpeg(quality),
+            ImageEncoding::Flate => self.encode_flate(),
+        }
+    }
+
+    fn encode_jpeg(&self, quality: u8) -> Result<Vec<u8>> {
+        let mut jpeg_data = Vec::new();
+        let mut jpeg_encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+            &mut jpeg_data,
+            quality.clamp(1, 100),
+        );
+        jpeg_encoder
+            .encode(
+                self.raw(),
+                self.width(),
+                self.height(),
+                self.color_type().into(),
+            )
+            .context(Error::EncodeJpeg)?;
+
+        Ok(jpeg_data)
+    }
+
+    fn encode_flate(&self) -> Result<Vec<u8>> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(self.raw())
+            .and_then(|_| encoder.finish())
+            .context(Error::CompressImage)
+    }
+
+    /// ブラウザなどで表示できるPNGにエンコードする
+    pub fn encode_png(&self) -> Result<Vec<u8>> {
+        use image::ImageEncoder;
+
+        let mut png_data = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut png_data)
+            .write_image(
+                self.raw(),
+                self.width(),
+                self.height(),
+                self.color_type().into(),
+            )
+            .context(Error::EncodePng)?;
+
+        Ok(png_data)
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::i18n::{Lang, Message};
+use crate::PageFailed;
+
+/// ライブラリが返すエラー
+///
+/// `Display`は英語のメッセージを返す。利用者の言語で表示する場合は
+/// `i18n::error_message`で翻訳する。Web Workerから送れるようにシリアライズできる。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Error {
+    /// `%PDF-`ヘッダーが見つからない
+    NotPdf,
+    /// PDFのパースに失敗した（`reason`はパーサーが返した内容）
+    ParsePdf { reason: String },
+    /// 入力ファイルを読み込めない
+    ReadInput { path: PathBuf },
+    /// 出力ファイルを作成できない
+    CreateOutput { path: PathBuf },
+    /// 入力と出力に同じファイルが指定された
+    SameInputOutput { path: PathBuf },
+    /// 出力PDFの書き出しに失敗した
+    SaveOutput,
+    /// PDFヘッダーの書き出しに失敗した
+    WriteHeader,
+    /// 相互参照表に書き出されていないオブジェクトが残っている
+    UnwrittenObject,
+    /// 変換用のスレッドプールを作成できない
+    ThreadPool,
+    /// レンダリング結果から画像バッファを作成できない
+    ImageBuffer,
+    /// JPEGのエンコードに失敗した
+    EncodeJpeg,
+    /// PNGのエンコードに失敗した
+    EncodePng,
+    /// Deflateでの圧縮に失敗した
+    CompressImage,
+    /// ページが存在しない（`page`は1始まり）
+    PageNotFound { page: usize },
+    /// 選択したページが文書のページ数を超えている（`page`は1始まり）
+    PageOutOfRange { page: usize, total: usize },
+    /// 変換するページが1つも選択されていない
+    NoPagesSelected,
+    /// ページ範囲の指定が空
+    EmptyPageRange,
+    /// ページ番号を数値として解釈できない
+    InvalidPageNumber { value: String },
+    /// ページ番号に0が指定された
+    PageNumberZero,
+    /// ページ番号が`MAX_PAGE_NUMBER`を超えている
+    PageNumberTooLarge { page: usize, max: usize },
+    /// ページ範囲の開始が終了より後になっている
+    ReversedPageRange { range: String },
+    /// 入力PDFが`SafetyLimits::max_input_bytes`を超えている
+    InputTooLarge { bytes: u64, limit: u64 },
+    /// 入力PDFのページ数が`SafetyLimits::max_pages`を超えている
+    TooManyPages { pages: usize, limit: usize },
+    /// ページの画素数が`SafetyLimits::max_page_pixels`を超えている（`page`は1始まり）
+    PageTooLarge {
+        page: usize,
+        width: u64,
+        height: u64,
+        limit: u64,
+    },
+    /// ページの幅または高さがレンダラーで扱える大きさを超えている（`page`は1始まり）
+    PageDimensionTooLarge {
+        page: usize,
+        width: u64,
+        height: u64,
+        max: u64,
+    },
+    /// 変換するページの画素数の合計が`SafetyLimits::max_total_pixels`を超えている
+    TotalPixelsExceeded { pixels: u64, limit: u64 },
+    /// ページのレンダリングにかかった時間が`SafetyLimits::max_page_render_ms`を超えた（`page`は1始まり）
+    PageRenderTooSlow {
+        page: usize,
+        elapsed_ms: u64,
+        limit_ms: u64,
+    },
+    /// ZIPアーカイブの作成に失敗した
+    CreateZip,
+    /// ZIPアーカイブへファイルを追加できない
+    AddToZip { name: String },
+    /// Workerとの間で解釈できないメッセージを受け取った
+    InvalidMessage,
+    /// サイズの見積もりに使うPDFがWorkerに読み込まれていない
+    EstimateNotLoaded,
+    /// Workerでエラーが発生し、応答が返らなくなった（`message`はブラウザのエラーメッセージ）
+    WorkerCrashed { message: String },
+    /// JavaScriptから渡されたオプションを解釈できない
+    InvalidOptions { reason: String },
+    /// C APIの引数がNULLまたは正しくない（`name`は引数名）
+    InvalidArgument { name: String },
+    /// ライブラリの外で発生したエラー（メッセージは翻訳せずそのまま表示する）
+    Other { message: String },
+}
+
+impl Error {
+    /// `anyhow::Error`を`Error`に変換する
+    ///
+    /// ライブラリのエラーでなければメッセージだけを`Other`として保持する。
+    /// 失敗したページ（`PageFailed`）は別に取り出せるため、メッセージには含めない。
+    pub fn from_anyhow(error: &anyhow::Error) -> Self {
+        error.downcast_ref::<Self>().cloned().unwrap_or_else(|| {
+            // 文脈として付けた`PageFailed`は型で区別できないため、メッセージで読み飛ばす
+            let page = error.downcast_ref::<PageFailed>().map(ToString::to_string);
+            let message = error
+                .chain()
+                .map(|cause| cause.to_string())
+                .find(|message| Some(message) != page.as_ref())
+                .unwrap_or_else(|| error.to_string());
+            Self::Other { message }
+        })
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&Message::from(self).localize(Lang::En))
+    }
+}
+
+impl std::error::Error for Error {}
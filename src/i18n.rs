@@ -0,0 +1,1205 @@
+//! 表示メッセージの翻訳
+//!
+//! CLIとWebアプリに表示する文言は、キーごとに英語と日本語を持つカタログから引く。
+//! ライブラリのエラーは言語に依存しない`Error`として返し、表示する側で
+//! `error_message`や`Message`を使って利用者の言語に翻訳する。
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{Cancelled, Error};
+
+/// 表示言語
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    #[default]
+    En,
+    Ja,
+}
+
+impl Lang {
+    /// 対応しているすべての言語
+    pub const ALL: [Lang; 2] = [Lang::En, Lang::Ja];
+
+    /// 言語コード（`--lang`やHTMLの`lang`属性に使う）
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Ja => "ja",
+        }
+    }
+
+    /// その言語での言語名（切り替えボタンに表示する）
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::En => "English",
+            Self::Ja => "日本語",
+        }
+    }
+
+    /// 「ja_JP.UTF-8」や「en-US」のようなロケール名から言語を判定する
+    ///
+    /// 対応していない言語の場合は`None`を返す。
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let language = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        Self::ALL.into_iter().find(|lang| lang.code() == language)
+    }
+
+    /// 環境変数`LC_ALL`・`LC_MESSAGES`・`LANG`の順に言語を判定する
+    ///
+    /// いずれも設定されていないか対応していない言語の場合は英語にする。
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|locale| Self::from_locale(&locale))
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Lang {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// キーに対応する文言を返す（カタログにないキーはそのまま返す）
+pub fn tr(lang: Lang, key: &'static str) -> &'static str {
+    MESSAGES
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .map(|&(_, en, ja)| match lang {
+            Lang::En => en,
+            Lang::Ja => ja,
+        })
+        .unwrap_or(key)
+}
+
+/// 文言中の`{}`を先頭から順に`args`で置き換える
+pub fn trf(lang: Lang, key: &'static str, args: &[&dyn fmt::Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = tr(lang, key).split("{}");
+    let mut text = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            text.push_str(&arg.to_string());
+        }
+        text.push_str(part);
+    }
+    text
+}
+
+/// エラーを表示言語のメッセージにする
+///
+/// ライブラリのエラーと中断は翻訳し、それ以外はそのまま文字列にする。
+pub fn error_message(lang: Lang, error: &anyhow::Error) -> String {
+    Message::from_error(error).localize(lang)
+}
+
+/// 表示するときに翻訳するメッセージ
+///
+/// 画面に残るメッセージを保持しておき、表示言語を切り替えたときに
+/// 翻訳し直せるようにする。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    key: &'static str,
+    args: Vec<String>,
+}
+
+impl Message {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            args: Vec::new(),
+        }
+    }
+
+    /// 文言中の`{}`に埋め込む値を付けて作成する
+    pub fn with_args(key: &'static str, args: &[&dyn fmt::Display]) -> Self {
+        Self {
+            key,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// `anyhow::Error`からメッセージを作成する
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        if error.is::<Cancelled>() {
+            Self::new("error.cancelled")
+        } else {
+            Self::from(&Error::from_anyhow(error))
+        }
+    }
+
+    pub fn key(&self) -> &'static str {
+        self.key
+    }
+
+    /// 表示言語に翻訳する
+    pub fn localize(&self, lang: Lang) -> String {
+        let args: Vec<&dyn fmt::Display> = self.args.iter().map(|arg| arg as _).collect();
+        trf(lang, self.key, &args)
+    }
+}
+
+impl From<&Error> for Message {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::NotPdf => Self::new("error.not_pdf"),
+            Error::ParsePdf { reason } => Self::with_args("error.parse_pdf", &[reason]),
+            Error::ReadInput { path } => Self::with_args("error.read_input", &[&path.display()]),
+            Error::CreateOutput { path } => {
+                Self::with_args("error.create_output", &[&path.display()])
+            }
+            Error::SameInputOutput { path } => {
+                Self::with_args("error.same_input_output", &[&path.display()])
+            }
+            Error::SaveOutput => Self::new("error.save_output"),
+            Error::WriteHeader => Self::new("error.write_header"),
+            Error::UnwrittenObject => Self::new("error.unwritten_object"),
+            Error::ThreadPool => Self::new("error.thread_pool"),
+            Error::ImageBuffer => Self::new("error.image_buffer"),
+            Error::EncodeJpeg => Self::new("error.encode_jpeg"),
+            Error::EncodePng => Self::new("error.encode_png"),
+            Error::CompressImage => Self::new("error.compress_image"),
+            Error::PageNotFound { page } => Self::with_args("error.page_not_found", &[page]),
+            Error::PageOutOfRange { page, total } => {
+                Self::with_args("error.page_out_of_range", &[page, total])
+            }
+            Error::NoPagesSelected => Self::new("error.no_pages_selected"),
+            Error::EmptyPageRange => Self::new("error.empty_page_range"),
+            Error::InvalidPageNumber { value } => {
+                Self::with_args("error.invalid_page_number", &[value])
+            }
+            Error::PageNumberZero => Self::new("error.page_number_zero"),
+            Error::PageNumberTooLarge { page, max } => {
+                Self::with_args("error.page_number_too_large", &[page, max])
+            }
+            Error::ReversedPageRange { range } => {
+                Self::with_args("error.reversed_page_range", &[range])
+            }
+            Error::InputTooLarge { bytes, limit } => {
+                Self::with_args("error.input_too_large", &[bytes, limit])
+            }
+            Error::TooManyPages { pages, limit } => {
+                Self::with_args("error.too_many_pages", &[pages, limit])
+            }
+            Error::PageTooLarge {
+                page,
+                width,
+                height,
+                limit,
+            } => Self::with_args("error.page_too_large", &[page, width, height, limit]),
+            Error::PageDimensionTooLarge {
+                page,
+                width,
+                height,
+                max,
+            } => Self::with_args(
+                "error.page_dimension_too_large",
+                &[page, width, height, max],
+            ),
+            Error::TotalPixelsExceeded { pixels, limit } => {
+                Self::with_args("error.total_pixels_exceeded", &[pixels, limit])
+            }
+            Error::PageRenderTooSlow {
+                page,
+                elapsed_ms,
+                limit_ms,
+            } => Self::with_args("error.page_render_too_slow", &[page, elapsed_ms, limit_ms]),
+            Error::CreateZip => Self::new("error.create_zip"),
+            Error::AddToZip { name } => Self::with_args("error.add_to_zip", &[name]),
+            Error::InvalidMessage => Self::new("error.invalid_message"),
+            Error::EstimateNotLoaded => Self::new("error.estimate_not_loaded"),
+            Error::WorkerCrashed { message } => Self::with_args("error.worker_crashed", &[message]),
+            Error::InvalidOptions { reason } => Self::with_args("error.invalid_options", &[reason]),
+            Error::InvalidArgument { name } => Self::with_args("error.invalid_argument", &[name]),
+            Error::Other { message } => Self::with_args("error.other", &[message]),
+        }
+    }
+}
+
+/// メッセージカタログ（キー、英語、日本語）
+const MESSAGES: &[(&str, &str, &str)] = &[
+    // 共通
+    (
+        "app.description",
+        "Rasterize PDF files and convert them back into PDF",
+        "PDFファイルを画像化してから再度PDFに変換するツール",
+    ),
+    // ライブラリのエラー（英語はエラーの`Display`にも使う）
+    (
+        "error.not_pdf",
+        "not a PDF file (no %PDF- header found)",
+        "PDFファイルではありません（%PDF-ヘッダーが見つかりません）",
+    ),
+    (
+        "error.parse_pdf",
+        "failed to parse PDF: {}",
+        "PDFのパースに失敗しました: {}",
+    ),
+    (
+        "error.read_input",
+        "failed to read PDF file: {}",
+        "PDFファイルの読み込みに失敗しました: {}",
+    ),
+    (
+        "error.create_output",
+        "failed to create PDF file: {}",
+        "PDFファイルの作成に失敗しました: {}",
+    ),
+    (
+        "error.same_input_output",
+        "input and output are the same file: {}",
+        "入力と出力が同じファイルです: {}",
+    ),
+    (
+        "error.save_output",
+        "failed to save PDF",
+        "PDFの保存に失敗しました",
+    ),
+    (
+        "error.write_header",
+        "failed to write PDF header",
+        "PDFヘッダーの書き込みに失敗しました",
+    ),
+    (
+        "error.unwritten_object",
+        "a PDF object was never written",
+        "書き込まれていないPDFオブジェクトがあります",
+    ),
+    (
+        "error.thread_pool",
+        "failed to create thread pool",
+        "スレッドプールの作成に失敗しました",
+    ),
+    (
+        "error.image_buffer",
+        "failed to create RGB image buffer",
+        "RGB画像バッファの作成に失敗しました",
+    ),
+    (
+        "error.encode_jpeg",
+        "failed to encode JPEG image",
+        "JPEG画像のエンコードに失敗しました",
+    ),
+    (
+        "error.encode_png",
+        "failed to encode PNG image",
+        "PNG画像のエンコードに失敗しました",
+    ),
+    (
+        "error.compress_image",
+        "failed to compress image",
+        "画像の圧縮に失敗しました",
+    ),
+    (
+        "error.page_not_found",
+        "page {} not found",
+        "ページ{}が見つかりません",
+    ),
+    (
+        "error.page_out_of_range",
+        "page {} does not exist ({} pages in total)",
+        "ページ{}は存在しません（全{}ページ）",
+    ),
+    (
+        "error.no_pages_selected",
+        "no pages selected for conversion",
+        "変換するページが選択されていません",
+    ),
+    (
+        "error.empty_page_range",
+        "no page range specified",
+        "ページ範囲が指定されていません",
+    ),
+    (
+        "error.invalid_page_number",
+        "invalid page number: {}",
+        "ページ番号が正しくありません: {}",
+    ),
+    (
+        "error.page_number_zero",
+        "page numbers start at 1",
+        "ページ番号は1から指定してください",
+    ),
+    (
+        "error.page_number_too_large",
+        "page number {} exceeds the maximum of {}",
+        "ページ番号{}が上限（{}）を超えています",
+    ),
+    (
+        "error.reversed_page_range",
+        "page range starts after it ends: {}",
+        "ページ範囲の開始が終了より後になっています: {}",
+    ),
+    (
+        "error.input_too_large",
+        "input PDF is {} bytes, which exceeds the limit of {} bytes",
+        "入力PDFのサイズ（{}バイト）が上限（{}バイト）を超えています",
+    ),
+    (
+        "error.too_many_pages",
+        "input PDF has {} pages, which exceeds the limit of {} pages",
+        "入力PDFのページ数（{}ページ）が上限（{}ページ）を超えています",
+    ),
+    (
+        "error.page_too_large",
+        "page {} would render at {}x{} pixels, which exceeds the limit of {} pixels per page",
+        "ページ{}のレンダリングサイズ（{}x{}ピクセル）が1ページの上限（{}ピクセル）を超えています",
+    ),
+    (
+        "error.page_dimension_too_large",
+        "page {} would render at {}x{} pixels, but the width and height must be at most {} pixels",
+        "ページ{}のレンダリングサイズ（{}x{}ピクセル）が扱える幅・高さ（{}ピクセル）を超えています",
+    ),
+    (
+        "error.total_pixels_exceeded",
+        "pages would render at {} pixels in total, which exceeds the limit of {} pixels",
+        "ページの画素数の合計（{}ピクセル）が上限（{}ピクセル）を超えています",
+    ),
+    (
+        "error.page_render_too_slow",
+        "page {} took {} ms to render, which exceeds the limit of {} ms",
+        "ページ{}のレンダリングに{}ミリ秒かかり、上限（{}ミリ秒）を超えました",
+    ),
+    (
+        "error.create_zip",
+        "failed to create ZIP archive",
+        "ZIPの作成に失敗しました",
+    ),
+    (
+        "error.add_to_zip",
+        "failed to add file to ZIP: {}",
+        "ZIPへの追加に失敗しました: {}",
+    ),
+    (
+        "error.invalid_message",
+        "received an invalid worker message",
+        "Workerから不正なメッセージを受信しました",
+    ),
+    (
+        "error.estimate_not_loaded",
+        "no PDF loaded for size estimation",
+        "見積もり対象のPDFが読み込まれていません",
+    ),
+    (
+        "error.worker_crashed",
+        "the worker stopped with an error: {}",
+        "Workerがエラーで停止しました: {}",
+    ),
+    (
+        "error.invalid_options",
+        "invalid options: {}",
+        "オプションが正しくありません: {}",
+    ),
+    (
+        "error.invalid_argument",
+        "invalid argument: {}",
+        "引数が正しくありません: {}",
+    ),
+    (
+        "error.cancelled",
+        "conversion was cancelled",
+        "変換がキャンセルされました",
+    ),
+    ("error.other", "{}", "{}"),
+    // CLI
+    (
+        "cli.flag.dpi",
+        "DPI (resolution) used for rasterizing, or auto to follow each page's embedded images",
+        "ラスタライズ時のDPI（解像度）。autoを指定するとページごとに埋め込み画像の解像度に合わせる",
+    ),
+    (
+        "cli.flag.max_dpi",
+        "Maximum DPI chosen by --dpi auto (default: 300)",
+        "--dpi autoで選ぶDPIの上限（デフォルト: 300）",
+    ),
+    (
+        "cli.flag.max_width",
+        "Maximum width of each rendered page in pixels (lowers the resolution of larger pages)",
+        "レンダリングするページ画像の最大幅（ピクセル）。超えるページは解像度を下げます",
+    ),
+    (
+        "cli.flag.max_height",
+        "Maximum height of each rendered page in pixels",
+        "レンダリングするページ画像の最大高さ（ピクセル）",
+    ),
+    (
+        "cli.flag.max_megapixels",
+        "Maximum number of pixels of each rendered page in megapixels",
+        "レンダリングするページ画像の最大画素数（メガピクセル）",
+    ),
+    (
+        "cli.flag.paper",
+        "Normalize output pages to a paper size (a4, letter)",
+        "出力PDFのページを用紙サイズ（a4、letter）に揃える",
+    ),
+    (
+        "cli.flag.paper_fit",
+        "How pages are placed on the paper: fit (keep margins) or fill (crop) (default: fit)",
+        "用紙への配置方法。fit（全体を収める）またはfill（用紙を覆い、はみ出しは切り取る）（デフォルト: fit）",
+    ),
+    (
+        "cli.invalid_paper",
+        "Unsupported paper size: {} (available: a4, letter)",
+        "対応していない用紙サイズです: {}（a4、letterから指定してください）",
+    ),
+    (
+        "cli.invalid_paper_fit",
+        "Invalid paper fit: {} (available: fit, fill)",
+        "用紙への配置方法の指定が正しくありません: {}（fit、fillから指定してください）",
+    ),
+    (
+        "cli.invalid_dpi",
+        "Invalid DPI: {} (specify a positive number or auto)",
+        "DPIの指定が正しくありません: {}（正の数かautoを指定してください）",
+    ),
+    (
+        "cli.invalid_megabytes",
+        "Invalid size for --{}: {} (specify a positive number of MB)",
+        "--{}のサイズの指定が正しくありません: {}（正の数をMB単位で指定してください）",
+    ),
+    (
+        "cli.flag.memory_limit",
+        "Memory limit for pages rendered at the same time (MB)",
+        "同時にレンダリングするページのメモリ上限（MB）",
+    ),
+    (
+        "cli.flag.limit_input_size",
+        "Reject input PDFs larger than this size (MB)",
+        "このサイズ（MB）を超える入力PDFは変換しない",
+    ),
+    (
+        "cli.flag.limit_pages",
+        "Reject input PDFs with more pages than this",
+        "このページ数を超える入力PDFは変換しない",
+    ),
+    (
+        "cli.flag.limit_page_megapixels",
+        "Fail when a page would render larger than this many megapixels (default: 200, 0 for no limit)",
+        "ページのレンダリング結果がこの画素数（メガピクセル）を超える場合は失敗する（デフォルト: 200、0で無制限）",
+    ),
+    (
+        "cli.flag.limit_total_megapixels",
+        "Fail when all pages together would render larger than this many megapixels",
+        "全ページのレンダリング結果の画素数の合計（メガピクセル）がこれを超える場合は失敗する",
+    ),
+    (
+        "cli.flag.limit_page_render_time",
+        "Fail after a page that took longer than this to render (seconds, checked once the page is rendered)",
+        "1ページのレンダリングにこの時間（秒）より長くかかった場合は、そのページのレンダリング後に失敗する",
+    ),
+    (
+        "cli.flag.profile",
+        "Profile from pdf_rasterizer.toml to use",
+        "pdf_rasterizer.tomlから使用するプロファイル",
+    ),
+    (
+        "cli.flag.jobs",
+        "Number of rendering threads (1 disables parallel processing)",
+        "レンダリングに使用するスレッド数（1で並列処理を無効化）",
+    ),
+    (
+        "cli.flag.lang",
+        "Message language (en, ja; defaults to LANG)",
+        "メッセージの言語（en、ja。省略時はLANGから判定）",
+    ),
+    (
+        "cli.missing_input",
+        "Specify the input PDF file path",
+        "入力PDFファイルのパスを指定してください",
+    ),
+    (
+        "cli.missing_output",
+        "Specify the output PDF file path",
+        "出力PDFファイルのパスを指定してください",
+    ),
+    (
+        "cli.optimizing",
+        "Optimizing PDF...",
+        "PDFを最適化しています...",
+    ),
+    ("cli.input", "Input: {}", "入力: {}"),
+    ("cli.output", "Output: {}", "出力: {}"),
+    ("cli.dpi", "DPI: {}", "DPI: {}"),
+    (
+        "cli.dpi_auto",
+        "DPI: auto ({} to {}, {} for pages without images)",
+        "DPI: 自動（{}〜{}、画像のないページは {}）",
+    ),
+    ("cli.memory_limit", "Memory limit: {}MB", "メモリ上限: {}MB"),
+    ("cli.max_size", "Maximum image size: {}", "画像の最大サイズ: {}"),
+    ("cli.max_width", "width {}px", "幅 {}px"),
+    ("cli.max_height", "height {}px", "高さ {}px"),
+    ("cli.max_megapixels", "{} megapixels", "{}メガピクセル"),
+    ("cli.paper", "Paper: {} ({})", "用紙: {}（{}）"),
+    (
+        "cli.watch.description",
+        "Watch a folder and convert PDFs as they appear",
+        "フォルダーを監視し、置かれたPDFを変換する",
+    ),
+    (
+        "cli.flag.watch_out_dir",
+        "Output folder for converted PDFs",
+        "変換したPDFの出力フォルダー",
+    ),
+    (
+        "cli.flag.error_dir",
+        "Folder that receives PDFs that failed to convert and their logs (default: <out-dir>/errors)",
+        "変換に失敗したPDFとログを移すフォルダー（デフォルト: <out-dir>/errors）",
+    ),
+    (
+        "cli.flag.interval",
+        "Seconds between checks of the folder (default: 2, minimum: 0.1)",
+        "フォルダーを確認する間隔（秒、デフォルト: 2、最小: 0.1）",
+    ),
+    (
+        "cli.flag.settle",
+        "Seconds a file must stay unchanged before it is converted (default: 3)",
+        "変換する前にファイルが変化しないことを確認する時間（秒、デフォルト: 3）",
+    ),
+    (
+        "cli.watch.missing_input",
+        "Specify the folder to watch",
+        "監視するフォルダーを指定してください",
+    ),
+    (
+        "cli.watch.not_dir",
+        "Not a folder: {}",
+        "フォルダーではありません: {}",
+    ),
+    (
+        "cli.watch.same_dir",
+        "{} must be different from the watched folder",
+        "{}には監視するフォルダーと別のフォルダーを指定してください",
+    ),
+    (
+        "cli.watch.invalid_seconds",
+        "Invalid --{}: {} (specify a number of seconds of 0 or more)",
+        "--{}の指定が正しくありません: {}（0以上の秒数を指定してください）",
+    ),
+    (
+        "cli.watch.interval_too_short",
+        "--interval {} is too short (specify {} seconds or more)",
+        "--intervalの{}秒は短すぎます（{}秒以上を指定してください）",
+    ),
+    (
+        "cli.watch.watching",
+        "Watching {} (checking every {}s, converting files unchanged for {}s)",
+        "{}を監視しています（{}秒ごとに確認し、{}秒間変化しないファイルを変換します）",
+    ),
+    ("cli.watch.error_dir", "Errors: {}", "エラー: {}"),
+    (
+        "cli.watch.stop_hint",
+        "Press Ctrl-C to stop",
+        "Ctrl-Cで終了します",
+    ),
+    ("cli.watch.converted", "Converted {} -> {}", "変換しました: {} -> {}"),
+    (
+        "cli.watch.failed",
+        "Failed to convert {}: {} (moved to {})",
+        "{}の変換に失敗しました: {}（{}へ移しました）",
+    ),
+    (
+        "cli.watch.move_failed",
+        "Failed to convert {}: {} (could not move it to the error folder: {})",
+        "{}の変換に失敗しました: {}（エラーフォルダーへ移せませんでした: {}）",
+    ),
+    (
+        "cli.watch.read_dir",
+        "Failed to read folder {}: {}",
+        "フォルダー{}を読み込めません: {}",
+    ),
+    ("cli.watch.move", "Failed to move file to {}", "{}へ移せませんでした"),
+    (
+        "cli.watch.write_log",
+        "Failed to write log {}",
+        "ログ{}を書き出せませんでした",
+    ),
+    ("cli.watch.log_input", "Input: {}", "入力: {}"),
+    ("cli.watch.log_error", "Error: {}", "エラー: {}"),
+    ("cli.watch.stopped", "Stopped watching", "監視を終了しました"),
+    (
+        "cli.config.description",
+        "Show the effective configuration (config show)",
+        "有効な設定を表示する（config show）",
+    ),
+    (
+        "cli.config.missing_action",
+        "Specify an action (available: show)",
+        "操作を指定してください（showを指定できます）",
+    ),
+    (
+        "cli.config.unknown_action",
+        "Unknown config action: {} (available: show)",
+        "configの操作が正しくありません: {}（showを指定できます）",
+    ),
+    (
+        "cli.config.not_found",
+        "Profile {} was specified, but no {} was found",
+        "プロファイル{}が指定されましたが、{}が見つかりません",
+    ),
+    (
+        "cli.config.read",
+        "Failed to read config file {}: {}",
+        "設定ファイル{}を読み込めません: {}",
+    ),
+    (
+        "cli.config.parse",
+        "Invalid config file {}: {}",
+        "設定ファイル{}が正しくありません: {}",
+    ),
+    (
+        "cli.config.profiles_not_table",
+        "profiles must be a table",
+        "profilesはテーブルで指定してください",
+    ),
+    (
+        "cli.config.profile_not_table",
+        "profile {} must be a table",
+        "プロファイル{}はテーブルで指定してください",
+    ),
+    (
+        "cli.config.unknown_profile",
+        "Profile {} is not defined in {} (available: {})",
+        "プロファイル{}は{}に定義されていません（定義済み: {}）",
+    ),
+    (
+        "cli.config.unknown_key",
+        "unknown setting: {}",
+        "不明な設定項目です: {}",
+    ),
+    (
+        "cli.config.not_positive",
+        "{} must be greater than 0 (got {})",
+        "{}は0より大きい値にしてください（{}が指定されています）",
+    ),
+    (
+        "cli.config.invalid_maximum",
+        "{} must be greater than 0 (got {}); omit the setting for no limit",
+        "{}は0より大きい値にしてください（{}が指定されています）。制限しない場合は項目を省略してください",
+    ),
+    (
+        "cli.config.invalid_quality",
+        "quality must be between 1 and 100 (got {})",
+        "qualityは1から100の値にしてください（{}が指定されています）",
+    ),
+    ("cli.config.source", "Config file: {}", "設定ファイル: {}"),
+    (
+        "cli.config.no_source",
+        "No {} found (built-in defaults)",
+        "{}が見つかりません（組み込みのデフォルト値）",
+    ),
+    ("cli.config.profile", "Profile: {}", "プロファイル: {}"),
+    ("cli.limits", "Safety limits: {}", "入力の上限: {}"),
+    ("cli.limit.input_size", "input {}MB", "入力 {}MB"),
+    ("cli.limit.pages", "{} pages", "{}ページ"),
+    (
+        "cli.limit.page_megapixels",
+        "{} megapixels per page",
+        "1ページ {}メガピクセル",
+    ),
+    (
+        "cli.limit.total_megapixels",
+        "{} megapixels in total",
+        "合計 {}メガピクセル",
+    ),
+    ("cli.limit.page_render_time", "{}s of rendering per page", "1ページのレンダリング {}秒"),
+    ("cli.paper_fit.fit", "fit", "全体を収める"),
+    ("cli.paper_fit.fill", "fill", "用紙を覆う"),
+    ("cli.threads", "Threads: {}", "スレッド数: {}"),
+    ("cli.threads_auto", "Threads: auto", "スレッド数: 自動"),
+    (
+        "cli.rasterizing",
+        "  Rasterizing PDF with hayro...",
+        "  hayroを使用してPDFを画像化します...",
+    ),
+    (
+        "cli.progress",
+        "rendered {}/{}  written {}/{}",
+        "画像化 {}/{}  書き出し {}/{}",
+    ),
+    ("cli.saved", "  Saved ({}MB)", "  保存しました（{}MB）"),
+    (
+        "cli.done",
+        "✓ Optimization complete",
+        "✓ 最適化が完了しました",
+    ),
+    (
+        "cli.cancelled",
+        "Conversion cancelled",
+        "変換をキャンセルしました",
+    ),
+    (
+        "cli.interrupting",
+        "Interrupting... (press Ctrl-C again to quit immediately)",
+        "中断しています...（もう一度Ctrl-Cを押すと強制終了します）",
+    ),
+    (
+        "cli.interrupt_handler",
+        "Failed to install Ctrl-C handler",
+        "Ctrl-Cハンドラの設定に失敗しました",
+    ),
+    (
+        "cli.flag.quiet",
+        "Do not print status messages or progress (errors are still printed)",
+        "状態や進捗を表示しない（エラーは表示します）",
+    ),
+    ("cli.stdin", "standard input", "標準入力"),
+    ("cli.stdout", "standard output", "標準出力"),
+    (
+        "cli.stdout_terminal",
+        "Refusing to write PDF data to a terminal; redirect standard output or specify an output file",
+        "PDFのデータを端末には出力しません。標準出力をリダイレクトするか出力ファイルを指定してください",
+    ),
+    (
+        "cli.read_stdin",
+        "Failed to read standard input",
+        "標準入力の読み込みに失敗しました",
+    ),
+    (
+        "cli.write_stdout",
+        "Failed to write to standard output",
+        "標準出力への書き出しに失敗しました",
+    ),
+    ("cli.error", "Error: {}", "エラー: {}"),
+    // batchサブコマンド
+    (
+        "cli.batch.description",
+        "Convert every PDF in directories or glob patterns into an output directory",
+        "ディレクトリやglobパターンに含まれるPDFをまとめて出力ディレクトリに変換する",
+    ),
+    (
+        "cli.flag.out_dir",
+        "Output directory (mirrors the input directory structure)",
+        "出力ディレクトリ（入力のディレクトリ構造を再現します）",
+    ),
+    (
+        "cli.flag.force",
+        "Convert again even if the output file already exists",
+        "出力ファイルが既にあっても変換し直す",
+    ),
+    (
+        "cli.batch.missing_inputs",
+        "Specify at least one input directory, PDF file or glob pattern",
+        "入力のディレクトリ、PDFファイル、globパターンのいずれかを指定してください",
+    ),
+    (
+        "cli.warning.image_analysis_failed",
+        "Warning: cannot analyze the images in {} for --dpi auto, so every page was converted at {} DPI: {}",
+        "警告: --dpi autoのために{}の画像を調べられなかったため、すべてのページを{} DPIで変換しました: {}",
+    ),
+    (
+        "cli.batch.missing_out_dir",
+        "Specify the output directory with --out-dir",
+        "--out-dirで出力ディレクトリを指定してください",
+    ),
+    (
+        "cli.batch.no_match",
+        "Warning: no PDF files found for {}",
+        "警告: {}に一致するPDFファイルがありません",
+    ),
+    (
+        "cli.batch.invalid_pattern",
+        "Invalid glob pattern: {}",
+        "globパターンが正しくありません: {}",
+    ),
+    (
+        "cli.batch.unreadable",
+        "Warning: cannot read {}",
+        "警告: {}を読み込めません",
+    ),
+    (
+        "cli.batch.no_files",
+        "No PDF files to convert",
+        "変換するPDFファイルがありません",
+    ),
+    (
+        "cli.batch.renamed",
+        "Warning: another input has the same output name, so {} is written to {}",
+        "警告: 他の入力と出力先の名前が同じため、{}は{}に出力します",
+    ),
+    ("cli.batch.found", "PDF files: {}", "PDFファイル: {}件"),
+    (
+        "cli.batch.skipping",
+        "Skipping {} files that are already converted (use --force to convert them again)",
+        "変換済みの{}件をスキップします（変換し直す場合は--forceを指定してください）",
+    ),
+    (
+        "cli.batch.create_dir",
+        "Failed to create directory: {}",
+        "ディレクトリの作成に失敗しました: {}",
+    ),
+    ("cli.batch.converted", "[{}/{}] {} → {}", "[{}/{}] {} → {}"),
+    (
+        "cli.batch.failed",
+        "[{}/{}] Failed: {}: {}",
+        "[{}/{}] 失敗: {}: {}",
+    ),
+    (
+        "cli.batch.summary",
+        "Converted: {}, skipped: {}, failed: {}",
+        "変換: {}件、スキップ: {}件、失敗: {}件",
+    ),
+    ("cli.batch.failures", "Failed files:", "失敗したファイル:"),
+    // infoサブコマンド
+    (
+        "cli.info.description",
+        "Show page sizes, fonts, images and other details of a PDF before converting it",
+        "変換前にPDFのページサイズ・フォント・画像などの詳細を表示する",
+    ),
+    (
+        "cli.flag.json",
+        "Print the details as JSON",
+        "詳細をJSONで出力する",
+    ),
+    (
+        "cli.info.missing_input",
+        "Specify the PDF to inspect",
+        "調べるPDFを指定してください",
+    ),
+    ("cli.info.file", "File: {}", "ファイル: {}"),
+    ("cli.info.version", "PDF version: {}", "PDFバージョン: {}"),
+    ("cli.info.encrypted", "Encrypted: {}", "暗号化: {}"),
+    ("cli.info.pages", "Pages: {}", "ページ数: {}"),
+    ("cli.info.transparency", "Transparency: {}", "透明効果: {}"),
+    ("cli.info.javascript", "JavaScript: {}", "JavaScript: {}"),
+    ("cli.info.yes", "yes", "あり"),
+    ("cli.info.no", "no", "なし"),
+    (
+        "cli.info.page",
+        "Page {}: {} x {} pt ({} x {} mm), rotation {}°",
+        "ページ {}: {} x {} pt（{} x {} mm）、回転 {}°",
+    ),
+    ("cli.info.images", "  Images: {}", "  画像: {}個"),
+    ("cli.info.image", "{} x {} px ({}), {}", "{} x {} px（{}）、{}"),
+    ("cli.info.bits", "{} bit", "{}ビット"),
+    ("cli.info.image_dpi", "{} x {} dpi on the page", "ページ上で {} x {} dpi"),
+    (
+        "cli.info.image_dpi_unknown",
+        "resolution on the page unknown",
+        "ページ上の解像度は不明",
+    ),
+    (
+        "cli.info.max_dpi",
+        "  Highest image resolution: {} dpi",
+        "  画像の最高解像度: {} dpi",
+    ),
+    ("cli.info.fonts", "Fonts: {}", "フォント: {}個"),
+    ("cli.info.font", "  {} ({}) on pages {}", "  {}（{}）ページ {}"),
+    ("cli.info.embedded", "embedded", "埋め込み"),
+    ("cli.info.not_embedded", "not embedded", "埋め込みなし"),
+    // --report
+    (
+        "cli.flag.report",
+        "Print a report of the conversion in the given format (json)",
+        "変換結果の詳細を指定した形式（json）で出力する",
+    ),
+    (
+        "cli.flag.report_file",
+        "Write the report to this file instead of standard output",
+        "詳細を標準出力ではなくこのファイルに書き出す",
+    ),
+    (
+        "cli.report.unknown_format",
+        "Unsupported report format: {} (available: json)",
+        "対応していない詳細の形式です: {}（jsonを指定してください）",
+    ),
+    (
+        "cli.report.stdout_conflict",
+        "The output PDF is written to standard output; specify --report-file for the report",
+        "出力PDFを標準出力に書き出すため、詳細の出力先を--report-fileで指定してください",
+    ),
+    (
+        "cli.report.write",
+        "Failed to write the report: {}",
+        "詳細の書き出しに失敗しました: {}",
+    ),
+    (
+        "cli.unknown_lang",
+        "Unsupported language: {} (available: en, ja)",
+        "対応していない言語です: {}（en、jaから指定してください）",
+    ),
+    // Webアプリ
+    ("web.version", "Version {}", "バージョン {}"),
+    (
+        "web.offline_ready",
+        "Available offline",
+        "オフラインで利用できます",
+    ),
+    (
+        "web.update_ready",
+        "A new version is available. Reload to update.",
+        "新しいバージョンがあります。再読み込みすると更新されます。",
+    ),
+    ("web.update_apply", "Update", "更新"),
+    ("web.update_later", "Later", "後で"),
+    ("web.language", "Language", "表示言語"),
+    (
+        "web.worker_unavailable",
+        "Could not start a Web Worker; converting on the main thread",
+        "Workerを起動できないため、メインスレッドで変換します",
+    ),
+    (
+        "web.estimator_unavailable",
+        "Could not start the size estimation worker",
+        "見積もり用のWorkerを起動できませんでした",
+    ),
+    (
+        "web.pool_unavailable",
+        "Could not start the worker pool",
+        "ワーカープールを起動できませんでした",
+    ),
+    (
+        "web.worker_crashed_log",
+        "A worker stopped with an error; converting the file again without it: {}",
+        "Workerがエラーで停止したため、そのWorkerを使わずにファイルを変換し直します: {}",
+    ),
+    (
+        "web.file_workers_unavailable",
+        "Could not start workers for converting files in parallel",
+        "ファイル並列変換用のWorkerを起動できませんでした",
+    ),
+    ("web.empty_file", "The file is empty", "空のファイルです"),
+    (
+        "web.file_too_large",
+        "The file is too large ({}MB, limit {}MB)",
+        "ファイルサイズが大きすぎます（{}MB、上限{}MB）",
+    ),
+    (
+        "web.file_loaded",
+        "Loaded file: {} ({} pages, PDF {})",
+        "ファイルを読み込みました: {}（{}ページ、PDF {}）",
+    ),
+    ("web.read_error", "Read error: {}", "読み込みエラー: {}"),
+    (
+        "web.processing_log",
+        "Processing PDFs... (DPI: {})",
+        "PDFを処理中... (DPI: {})",
+    ),
+    (
+        "web.start_log",
+        "Starting conversion: {}",
+        "変換を開始します: {}",
+    ),
+    (
+        "web.pool_log",
+        "Converting in parallel with {} workers",
+        "{}個のWorkerで並列に変換します",
+    ),
+    (
+        "web.done_log",
+        "Finished converting: {}",
+        "PDF処理が完了しました: {}",
+    ),
+    (
+        "web.cancel_log",
+        "Cancellation requested",
+        "変換のキャンセルを要求しました",
+    ),
+    (
+        "web.cancelled_log",
+        "Cancelled conversion: {}",
+        "変換をキャンセルしました: {}",
+    ),
+    ("web.error_log", "Error: {}", "エラー: {}"),
+    (
+        "web.preview_failed",
+        "Failed to create preview: {}",
+        "プレビューの作成に失敗しました: {}",
+    ),
+    (
+        "web.thumbnail_failed",
+        "Failed to create thumbnail: {}",
+        "サムネイルの作成に失敗しました: {}",
+    ),
+    ("web.select_pdf", "Choose PDFs", "PDFを選択"),
+    (
+        "web.drop_hint",
+        "You can also drag and drop PDFs here (multiple files, up to {}MB each)",
+        "ここにPDFをドラッグ＆ドロップすることもできます（複数可、1ファイル{}MBまで）",
+    ),
+    (
+        "web.dpi_hint",
+        "Resolution (72-600)",
+        "解像度を指定します（72-600）",
+    ),
+    ("web.page_ranges", "Pages: ", "ページ範囲: "),
+    (
+        "web.page_ranges_placeholder",
+        "All (e.g. 1-3,5)",
+        "すべて（例: 1-3,5）",
+    ),
+    ("web.encoding", "Image format: ", "画像形式: "),
+    (
+        "web.encoding_jpeg",
+        "JPEG (lossy, small)",
+        "JPEG（非可逆・小さい）",
+    ),
+    (
+        "web.encoding_flate",
+        "Deflate (lossless, large)",
+        "Deflate（可逆・大きい）",
+    ),
+    ("web.quality", "JPEG quality: ", "JPEG品質: "),
+    ("web.color", "Color: ", "色: "),
+    ("web.color_rgb", "Color", "カラー"),
+    ("web.color_gray", "Grayscale", "グレースケール"),
+    (
+        "web.parallel",
+        " Parallel processing ({} workers)",
+        " 並列処理（Worker {}個）",
+    ),
+    (
+        "web.parallel_files",
+        " Convert multiple files at once",
+        " 複数のファイルを同時に変換",
+    ),
+    ("web.processing", "Processing...", "処理中..."),
+    ("web.convert", "Convert", "変換"),
+    ("web.cancel", "Cancel", "キャンセル"),
+    ("web.cancelling", "Cancelling...", "キャンセルしています..."),
+    ("web.starting", "Starting...", "処理を開始しています..."),
+    (
+        "web.done_count",
+        "✓ {} file(s) converted",
+        "✓ {}件の変換完了",
+    ),
+    (
+        "web.download_zip",
+        "Download all as ZIP",
+        "ZIPでまとめてダウンロード",
+    ),
+    ("web.download", "Download", "ダウンロード"),
+    ("web.clear_queue", "Clear list", "リストをクリア"),
+    ("web.remove", "Remove from list", "リストから削除"),
+    ("web.show_preview", "Show preview", "プレビューを表示"),
+    ("web.status_loading", "Loading...", "読み込み中..."),
+    ("web.status_ready", "Ready", "変換待ち"),
+    ("web.status_queued", "Queued", "順番待ち"),
+    (
+        "web.status_done",
+        "✓ Converted ({}MB)",
+        "✓ 変換完了（{}MB）",
+    ),
+    ("web.status_cancelled", "Cancelled", "キャンセルしました"),
+    (
+        "web.progress_parsed",
+        "Loaded {} pages",
+        "{}ページを読み込みました",
+    ),
+    (
+        "web.progress_rendered",
+        "Rendered page {}/{}",
+        "ページ {}/{} を画像化しました",
+    ),
+    (
+        "web.progress_assembling",
+        "Wrote page {}/{}",
+        "ページ {}/{} を書き出しました",
+    ),
+    (
+        "web.progress_saved",
+        "Done! ({}MB)",
+        "完了しました！（{}MB）",
+    ),
+    ("web.page_count", "{} pages", "{}ページ"),
+    ("web.page_count_selected", "{}/{} pages", "{}/{}ページ"),
+    ("web.preset", "Preset: ", "プリセット: "),
+    ("web.preset_apply", "Apply", "適用"),
+    ("web.preset_export", "Export", "エクスポート"),
+    ("web.preset_delete", "Delete", "削除"),
+    ("web.preset_name", "Preset name", "プリセット名"),
+    (
+        "web.preset_save",
+        "Save current settings",
+        "現在の設定を保存",
+    ),
+    ("web.preset_import", "Import", "インポート"),
+    (
+        "web.preset_name_required",
+        "Enter a preset name",
+        "プリセット名を入力してください",
+    ),
+    (
+        "web.preset_builtin",
+        "The built-in preset \"{}\" cannot be overwritten",
+        "組み込みのプリセット「{}」は上書きできません",
+    ),
+    (
+        "web.preset_invalid",
+        "Invalid preset format: {}",
+        "プリセットの形式が正しくありません: {}",
+    ),
+    (
+        "web.preset_applied",
+        "Applied preset: {}",
+        "プリセットを適用しました: {}",
+    ),
+    (
+        "web.preset_imported",
+        "Imported preset: {}",
+        "プリセットをインポートしました: {}",
+    ),
+    (
+        "web.estimate_pending",
+        "Estimated size: calculating...",
+        "推定サイズ: 計算中...",
+    ),
+    (
+        "web.estimate_ready",
+        "Estimated size: about {}MB ({} pages, based on page {} at {}KB)",
+        "推定サイズ: 約{}MB（{}ページ、ページ{}の画像{}KBから推定）",
+    ),
+    (
+        "web.estimate_failed",
+        "Estimated size: unavailable ({})",
+        "推定サイズ: 計算できませんでした（{}）",
+    ),
+    ("web.page", "Page {}", "ページ{}"),
+    (
+        "web.pages_selected",
+        "Converting {}/{} pages",
+        "{}/{}ページを変換",
+    ),
+    ("web.select_all", "Select all", "すべて選択"),
+    ("web.select_none", "Deselect all", "選択を解除"),
+    (
+        "web.compare_hint",
+        "Click a thumbnail to compare the page before and after conversion",
+        "サムネイルをクリックすると変換前後の画像を比較できます",
+    ),
+    (
+        "web.select_hint",
+        "Unchecked pages are left out of the converted PDF",
+        "チェックを外したページは変換後のPDFに含まれません",
+    ),
+    (
+        "web.rendering_page",
+        "Rendering page {}...",
+        "ページ {} を画像化しています...",
+    ),
+    (
+        "web.image_unavailable",
+        "Could not display the image",
+        "画像を表示できませんでした",
+    ),
+    ("web.before", "Before", "変換前"),
+    ("web.after", "After", "変換後"),
+    ("web.after_size", "After ({}KB)", "変換後（{}KB）"),
+    (
+        "web.compare_page",
+        "Page {} (DPI: {})",
+        "ページ {}（DPI: {}）",
+    ),
+    ("web.side_by_side", "Side by side", "並べて表示"),
+    ("web.slider", "Slider", "スライダー"),
+    ("web.close_comparison", "Close comparison", "比較を閉じる"),
+];
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// PDFファイルの先頭に置かれるヘッダー
+pub const PDF_HEADER: &[u8] = b"%PDF-";
+
+/// ヘッダーを探す範囲（先頭に余分なデータが付いたPDFも受け付けるため）
+pub const HEADER_SEARCH_LIMIT: usize = 1024;
+
+/// PDFファイルの概要
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PdfInfo {
+    /// ページ数
+    pub pages: usize,
+    /// ヘッダーに記載されたPDFのバージョン（例: "1.7"）
+    pub version: String,
+}
+
+/// ヘッダーからPDFのバージョンを取得する
+///
+/// 先頭`HEADER_SEARCH_LIMIT`バイト以内に`%PDF-`が見つからなければ`None`を返す。
+pub fn pdf_version(data: &[u8]) -> Option<String> {
+    let head = &data[..data.len().min(HEADER_SEARCH_LIMIT)];
+    let start = head
+        .windows(PDF_HEADER.len())
+        .position(|window| window == PDF_HEADER)?
+        + PDF_HEADER.len();
+
+    let version: String = data[start..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit() || **b == b'.')
+        .map(|&b| b as char)
+        .collect();
+    Some(version)
+}
+
+/// データがPDFファイルかどうかをヘッダーで判定する
+pub fn is_pdf(data: &[u8]) -> bool {
+    pdf_version(data).is_some()
+}
+
+/// PDFをパースしてページ数とバージョンを取得する
+pub fn pdf_info<D>(pdf_data: D) -> Result<PdfInfo>
+where
+    D: AsRef<[u8]> + Send + Sync + 'static,
+{
+    let Some(version) = pdf_version(pdf_data.as_ref()) else {
+        anyhow::bail!(crate::Error::NotPdf);
+    };
+    let pdf = crate::parse_pdf(pdf_data)?;
+
+    Ok(PdfInfo {
+        pages: pdf.pages().len(),
+        version,
+    })
+}
@@ -0,0 +1,925 @@
+//! 変換前にPDFの内容を調べる
+//!
+//! ページのボックスや回転、フォント、埋め込み画像の実効解像度、透明効果や
+//! JavaScriptの有無など、DPIを決めるための情報をlopdfで取得する。
+
+use anyhow::Result;
+use flate2::read::ZlibDecoder;
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document as LoDocument, Object, ObjectId, Stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::rc::Rc;
+
+use crate::info::pdf_version;
+use crate::Error;
+
+/// フォームXObjectをたどる深さの上限（循環参照に備える）
+const MAX_FORM_DEPTH: usize = 8;
+
+/// 文書全体で解釈するコンテンツストリームの演算子の数の上限
+const MAX_OPERATIONS: usize = 10_000_000;
+
+/// 文書全体で展開するコンテンツストリームの合計サイズの上限（バイト）
+const MAX_CONTENT_BYTES: usize = 256 * 1024 * 1024;
+
+/// ページツリーをたどる深さの上限
+const MAX_TREE_DEPTH: usize = 32;
+
+/// 単位行列
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// 変換行列 `[a b c d e f]`
+type Matrix = [f32; 6];
+
+/// PDFの詳細
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PdfDetails {
+    /// PDFのバージョン（カタログの`/Version`がヘッダーより新しければそちら）
+    pub version: String,
+    /// 暗号化されているか
+    pub encrypted: bool,
+    pub pages: Vec<PageDetails>,
+    /// 使われているフォント（名前順）
+    pub fonts: Vec<FontDetails>,
+    /// 透明効果（ソフトマスク、不透明度、ブレンドモード、透明グループ）を含むか
+    pub transparency: bool,
+    /// JavaScriptを含むか
+    pub javascript: bool,
+}
+
+/// 1ページの詳細
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageDetails {
+    /// ページ番号（0始まり）
+    pub index: usize,
+    pub media_box: PageBox,
+    pub crop_box: Option<PageBox>,
+    pub bleed_box: Option<PageBox>,
+    pub trim_box: Option<PageBox>,
+    pub art_box: Option<PageBox>,
+    /// 回転（0・90・180・270度）
+    pub rotation: u32,
+    /// ページに描画される画像（描画された回数だけ含む）
+    pub images: Vec<ImageDetails>,
+}
+
+/// ページのボックス（ポイント）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PageBox {
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+    pub top: f32,
+}
+
+/// ページに描画される画像
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageDetails {
+    /// 画像の幅（ピクセル）
+    pub width: u32,
+    /// 画像の高さ（ピクセル）
+    pub height: u32,
+    pub bits_per_component: Option<u32>,
+    /// 色空間の名前（例: "DeviceRGB"、"ICCBased"）
+    pub color_space: Option<String>,
+    /// 圧縮方式（例: "DCTDecode"）
+    pub filters: Vec<String>,
+    /// ページ上に描画される大きさでの横方向の解像度
+    pub dpi_x: Option<f32>,
+    /// ページ上に描画される大きさでの縦方向の解像度
+    pub dpi_y: Option<f32>,
+}
+
+/// ページで使われているフォント
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FontDetails {
+    /// `/BaseFont`（ない場合はリソース名）
+    pub name: String,
+    /// 種類（例: "Type1"、"TrueType"、"Type0"）
+    pub subtype: Option<String>,
+    /// フォントファイルが埋め込まれているか
+    pub embedded: bool,
+    /// 使われているページの番号（0始まり）
+    pub pages: Vec<usize>,
+}
+
+impl PageBox {
+    /// MediaBoxがない壊れたPDFで使う大きさ（レターサイズ）
+    const LETTER: Self = Self {
+        left: 0.0,
+        bottom: 0.0,
+        right: 612.0,
+        top: 792.0,
+    };
+
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.top - self.bottom
+    }
+}
+
+impl PageDetails {
+    /// 表示されるページの大きさ（ポイント、回転を反映）
+    ///
+    /// CropBoxがあればCropBox、なければMediaBoxの大きさ。
+    pub fn size(&self) -> (f32, f32) {
+        let visible = self.crop_box.unwrap_or(self.media_box);
+        if self.rotation % 180 == 90 {
+            (visible.height(), visible.width())
+        } else {
+            (visible.width(), visible.height())
+        }
+    }
+
+    /// ページに描画される画像の解像度のうち最も高いもの
+    pub fn max_image_dpi(&self) -> Option<f32> {
+        max_dpi(&self.images)
+    }
+}
+
+/// PDFをパースして詳細を取得する
+///
+/// 暗号化されたPDFは空のパスワードで復号を試み、復号できなければ
+/// ページの内容から得られる情報（画像）を含めずに返す。
+pub fn inspect_pdf(data: &[u8]) -> Result<PdfDetails> {
+    let Some(header_version) = pdf_version(data) else {
+        anyhow::bail!(Error::NotPdf);
+    };
+    let mut doc = load(data)?;
+    let encrypted = doc.is_encrypted();
+    if encrypted {
+        let _ = doc.decrypt("");
+    }
+
+    let mut fonts: BTreeMap<(String, Option<String>, bool), Vec<usize>> = BTreeMap::new();
+    let mut scanner = ImageScanner::new(&doc);
+    let mut pages = Vec::new();
+    for (index, page_id) in doc.get_pages().into_values().enumerate() {
+        let page = doc.get_dictionary(page_id).map_err(|e| Error::ParsePdf {
+            reason: e.to_string(),
+        })?;
+
+        for (resource_name, font) in doc.get_page_fonts(page_id).unwrap_or_default() {
+            let name = name_of(&doc, font, b"BaseFont")
+                .unwrap_or_else(|| String::from_utf8_lossy(&resource_name).into_owned());
+            let key = (
+                name,
+                name_of(&doc, font, b"Subtype"),
+                font_embedded(&doc, font),
+            );
+            let font_pages = fonts.entry(key).or_default();
+            if font_pages.last() != Some(&index) {
+                font_pages.push(index);
+            }
+        }
+
+        pages.push(PageDetails {
+            index,
+            media_box: inherited(&doc, page, b"MediaBox")
+                .and_then(|object| page_box(&doc, object))
+                .unwrap_or(PageBox::LETTER),
+            crop_box: inherited(&doc, page, b"CropBox").and_then(|object| page_box(&doc, object)),
+            bleed_box: own_box(&doc, page, b"BleedBox"),
+            trim_box: own_box(&doc, page, b"TrimBox"),
+            art_box: own_box(&doc, page, b"ArtBox"),
+            rotation: inherited(&doc, page, b"Rotate")
+                .and_then(|object| object.as_i64().ok())
+                .map_or(0, |rotation| rotation.rem_euclid(360) as u32),
+            images: scanner
+                .page_images(page_id, page)
+                .unwrap_or_else(|partial| partial),
+        });
+    }
+
+    Ok(PdfDetails {
+        version: catalog_version(&doc)
+            .filter(|version| *version > header_version)
+            .unwrap_or(header_version),
+        encrypted,
+        pages,
+        fonts: fonts
+            .into_iter()
+            .map(|((name, subtype, embedded), pages)| FontDetails {
+                name,
+                subtype,
+                embedded,
+                pages,
+            })
+            .collect(),
+        transparency: doc.objects.values().any(|object| {
+            object_dictionary(object).is_some_and(|dict| uses_transparency(&doc, dict))
+        }),
+        javascript: doc
+            .objects
+            .values()
+            .any(|object| object_dictionary(object).is_some_and(has_javascript)),
+    })
+}
+
+/// ページごとに、描画される画像の解像度のうち最も高いものを返す（`auto_dpi`用）
+///
+/// 解析の上限に達して画像を調べきれなかったページは`None`にする。
+pub(crate) fn page_image_dpis(data: &[u8]) -> Result<Vec<Option<f32>>> {
+    let mut doc = load(data)?;
+    if doc.is_encrypted() {
+        let _ = doc.decrypt("");
+    }
+
+    let mut scanner = ImageScanner::new(&doc);
+    Ok(doc
+        .get_pages()
+        .into_values()
+        .map(|page_id| {
+            let page = doc.get_dictionary(page_id).ok()?;
+            max_dpi(&scanner.page_images(page_id, page).ok()?)
+        })
+        .collect())
+}
+
+fn max_dpi(images: &[ImageDetails]) -> Option<f32> {
+    images
+        .iter()
+        .flat_map(|image| [image.dpi_x, image.dpi_y])
+        .flatten()
+        .reduce(f32::max)
+}
+
+fn load(data: &[u8]) -> Result<LoDocument> {
+    LoDocument::load_mem(data).map_err(|e| {
+        Error::ParsePdf {
+            reason: e.to_string(),
+        }
+        .into()
+    })
+}
+
+/// 参照をたどった先のオブジェクト
+fn resolve<'a>(doc: &'a LoDocument, object: &'a Object) -> &'a Object {
+    doc.dereference(object)
+        .map(|(_, object)| object)
+        .unwrap_or(object)
+}
+
+/// 辞書の値を参照をたどって取得する
+fn get<'a>(doc: &'a LoDocument, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+    dict.get(key).ok().map(|object| resolve(doc, object))
+}
+
+fn name_of(doc: &LoDocument, dict: &Dictionary, key: &[u8]) -> Option<String> {
+    get(doc, dict, key)?
+        .as_name()
+        .ok()
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+}
+
+/// オブジェクトが辞書（ストリームなら辞書部分）であれば返す
+fn object_dictionary(object: &Object) -> Option<&Dictionary> {
+    match object {
+        Object::Dictionary(dict) => Some(dict),
+        Object::Stream(stream) => Some(&stream.dict),
+        _ => None,
+    }
+}
+
+/// ページツリーの親から継承される属性を取得する
+fn inherited<'a>(doc: &'a LoDocument, page: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+    let mut node = page;
+    for _ in 0..MAX_TREE_DEPTH {
+        if let Some(value) = get(doc, node, key) {
+            return Some(value);
+        }
+        node = get(doc, node, b"Parent")?.as_dict().ok()?;
+    }
+    None
+}
+
+fn own_box(doc: &LoDocument, page: &Dictionary, key: &[u8]) -> Option<PageBox> {
+    get(doc, page, key).and_then(|object| page_box(doc, object))
+}
+
+/// `[x0 y0 x1 y1]`の配列をボックスにする（座標の順序は正規化する）
+fn page_box(doc: &LoDocument, object: &Object) -> Option<PageBox> {
+    let values: Vec<f32> = object
+        .as_array()
+        .ok()?
+        .iter()
+        .map(|value| resolve(doc, value).as_float().ok())
+        .collect::<Option<_>>()?;
+    let [x0, y0, x1, y1] = values[..] else {
+        return None;
+    };
+    Some(PageBox {
+        left: x0.min(x1),
+        bottom: y0.min(y1),
+        right: x0.max(x1),
+        top: y0.max(y1),
+    })
+}
+
+/// カタログの`/Version`（PDF 1.4以降、ヘッダーより新しいバージョンを示す）
+fn catalog_version(doc: &LoDocument) -> Option<String> {
+    name_of(doc, doc.catalog().ok()?, b"Version")
+}
+
+/// フォントファイルが埋め込まれているか
+///
+/// Type3フォントはグリフをPDF内で定義するため常に埋め込みとみなす。
+/// Type0フォントは子孫フォントのFontDescriptorを調べる。
+fn font_embedded(doc: &LoDocument, font: &Dictionary) -> bool {
+    let font = match name_of(doc, font, b"Subtype").as_deref() {
+        Some("Type3") => return true,
+        Some("Type0") => {
+            match get(doc, font, b"DescendantFonts")
+                .and_then(|fonts| fonts.as_array().ok())
+                .and_then(|fonts| fonts.first())
+                .and_then(|descendant| resolve(doc, descendant).as_dict().ok())
+            {
+                Some(descendant) => descendant,
+                None => return false,
+            }
+        }
+        _ => font,
+    };
+
+    get(doc, font, b"FontDescriptor")
+        .and_then(|descriptor| descriptor.as_dict().ok())
+        .is_some_and(|descriptor| {
+            [&b"FontFile"[..], b"FontFile2", b"FontFile3"]
+                .iter()
+                .any(|key| descriptor.has(key))
+        })
+}
+
+/// 透明効果を使う辞書か
+fn uses_transparency(doc: &LoDocument, dict: &Dictionary) -> bool {
+    let soft_mask = get(doc, dict, b"SMask")
+        .is_some_and(|mask| mask.as_name().map_or(true, |name| name != b"None"));
+    let opacity = [&b"CA"[..], b"ca"].iter().any(|key| {
+        get(doc, dict, key)
+            .and_then(|alpha| alpha.as_float().ok())
+            .is_some_and(|alpha| alpha < 1.0)
+    });
+    let blend_mode =
+        name_of(doc, dict, b"BM").is_some_and(|mode| mode != "Normal" && mode != "Compatible");
+    let group = get(doc, dict, b"Group")
+        .and_then(|group| group.as_dict().ok())
+        .is_some_and(|group| name_of(doc, group, b"S").as_deref() == Some("Transparency"));
+
+    soft_mask || opacity || blend_mode || group
+}
+
+/// JavaScriptのアクションや名前ツリーを含む辞書か
+fn has_javascript(dict: &Dictionary) -> bool {
+    dict.has(b"JS")
+        || dict.has(b"JavaScript")
+        || dict
+            .get(b"S")
+            .and_then(Object::as_name)
+            .is_ok_and(|name| name == b"JavaScript")
+}
+
+/// 画像と、描画されるときの変換行列（解析したコンテンツストリームの座標系）
+#[derive(Debug, Clone)]
+struct PlacedImage {
+    /// 画像の詳細（解像度は`matrix`から計算したもの）
+    details: ImageDetails,
+    matrix: Matrix,
+}
+
+/// ページの内容をたどり、描画される画像とその大きさを集める
+///
+/// フォームXObjectの結果はオブジェクトごとに保持し、同じフォームを何度描画しても
+/// 解析は1回にする。悪意のあるPDFに備え、文書全体で解釈する演算子の数と
+/// 展開するコンテンツストリームのサイズに上限を設ける。
+struct ImageScanner<'a> {
+    doc: &'a LoDocument,
+    /// フォームXObjectごとの画像（フォームの`/Matrix`を含む、フォームを描画する側の座標系）
+    forms: HashMap<ObjectId, Rc<Vec<PlacedImage>>>,
+    /// 残りの演算子の数
+    operations: usize,
+    /// 残りの展開サイズ（バイト）
+    content_bytes: usize,
+    /// 上限に達した、または展開できないストリームがあり、調べきれなかったか
+    incomplete: bool,
+}
+
+impl<'a> ImageScanner<'a> {
+    fn new(doc: &'a LoDocument) -> Self {
+        Self {
+            doc,
+            forms: HashMap::new(),
+            operations: MAX_OPERATIONS,
+            content_bytes: MAX_CONTENT_BYTES,
+            incomplete: false,
+        }
+    }
+
+    /// ページに描画される画像を返す（調べきれなかった場合は見つかった分を`Err`で返す）
+    fn page_images(
+        &mut self,
+        page_id: ObjectId,
+        page: &'a Dictionary,
+    ) -> Result<Vec<ImageDetails>, Vec<ImageDetails>> {
+        self.incomplete = false;
+        let doc = self.doc;
+        let mut content = Vec::new();
+        for stream_id in doc.get_page_contents(page_id) {
+            let Ok(stream) = doc.get_object(stream_id).and_then(Object::as_stream) else {
+                continue;
+            };
+            match self.decode(stream) {
+                Some(data) => content.extend_from_slice(&data),
+                None => self.incomplete = true,
+            }
+        }
+        let resources = inherited(doc, page, b"Resources").and_then(|object| object.as_dict().ok());
+
+        let images: Vec<ImageDetails> = self
+            .collect(&content, resources, 0)
+            .into_iter()
+            .map(|image| image.details)
+            .collect();
+        if self.incomplete {
+            Err(images)
+        } else {
+            Ok(images)
+        }
+    }
+
+    /// コンテンツストリームの`cm`・`q`・`Q`で変換行列を追跡し、`Do`で描画される画像を集める
+    ///
+    /// フォームXObjectは`MAX_FORM_DEPTH`の深さまでたどる。
+    fn collect(
+        &mut self,
+        content: &[u8],
+        resources: Option<&'a Dictionary>,
+        depth: usize,
+    ) -> Vec<PlacedImage> {
+        let doc = self.doc;
+        let Ok(content) = Content::decode(content) else {
+            return Vec::new();
+        };
+        let xobjects = resources
+            .and_then(|resources| get(doc, resources, b"XObject"))
+            .and_then(|xobjects| xobjects.as_dict().ok());
+
+        let mut images = Vec::new();
+        let mut ctm = IDENTITY;
+        let mut stack = Vec::new();
+        for operation in &content.operations {
+            if self.operations == 0 {
+                self.incomplete = true;
+                break;
+            }
+            self.operations -= 1;
+
+            match operation.operator.as_str() {
+                "q" => stack.push(ctm),
+                "Q" => ctm = stack.pop().unwrap_or(ctm),
+                "cm" => {
+                    if let Some(matrix) = matrix(doc, &operation.operands) {
+                        ctm = concat(matrix, ctm);
+                    }
+                }
+                "Do" => {
+                    let Some(xobject) = operation
+                        .operands
+                        .first()
+                        .and_then(|name| name.as_name().ok())
+                        .and_then(|name| xobjects?.get(name).ok())
+                    else {
+                        continue;
+                    };
+                    let Ok(stream) = resolve(doc, xobject).as_stream() else {
+                        continue;
+                    };
+
+                    match name_of(doc, &stream.dict, b"Subtype").as_deref() {
+                        Some("Image") => images.extend(
+                            image_details(doc, &stream.dict).map(|details| place(details, ctm)),
+                        ),
+                        Some("Form") if depth < MAX_FORM_DEPTH => {
+                            let form = self.form(xobject, stream, resources, depth);
+                            // 結果を再利用しても、描画される画像の数だけ演算子の上限を消費する
+                            if form.len() > self.operations {
+                                self.incomplete = true;
+                                break;
+                            }
+                            self.operations -= form.len();
+                            images.extend(form.iter().map(|image| {
+                                place(image.details.clone(), concat(image.matrix, ctm))
+                            }));
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        images
+    }
+
+    /// フォームXObjectが描画する画像（参照されたフォームは結果を再利用する）
+    fn form(
+        &mut self,
+        xobject: &Object,
+        stream: &'a Stream,
+        resources: Option<&'a Dictionary>,
+        depth: usize,
+    ) -> Rc<Vec<PlacedImage>> {
+        let doc = self.doc;
+        let id = xobject.as_reference().ok();
+        if let Some(cached) = id.and_then(|id| self.forms.get(&id)) {
+            return cached.clone();
+        }
+        // 自分自身を描画する循環参照は、解析中に空の結果を見つけて止まる
+        if let Some(id) = id {
+            self.forms.insert(id, Rc::default());
+        }
+
+        let form_matrix = get(doc, &stream.dict, b"Matrix")
+            .and_then(|matrix_object| matrix_object.as_array().ok())
+            .and_then(|values| matrix(doc, values))
+            .unwrap_or(IDENTITY);
+        let form_resources = get(doc, &stream.dict, b"Resources")
+            .and_then(|object| object.as_dict().ok())
+            .or(resources);
+        let images = match self.decode(stream) {
+            Some(content) => self.collect(&content, form_resources, depth + 1),
+            None => {
+                self.incomplete = true;
+                Vec::new()
+            }
+        };
+        let images: Rc<Vec<PlacedImage>> = Rc::new(
+            images
+                .into_iter()
+                .map(|image| PlacedImage {
+                    matrix: concat(image.matrix, form_matrix),
+                    details: image.details,
+                })
+                .collect(),
+        );
+
+        if let Some(id) = id {
+            self.forms.insert(id, images.clone());
+        }
+        images
+    }
+
+    /// ストリームを展開する（残りの展開サイズを超える場合や対応しない圧縮方式では`None`）
+    ///
+    /// 展開後のサイズが分からない圧縮ストリームでも上限を超えて展開しないよう、
+    /// FlateDecodeは自前で上限まで読み込む。
+    fn decode(&mut self, stream: &Stream) -> Option<Vec<u8>> {
+        let filters = stream.filters().unwrap_or_default();
+        let data = match filters.as_slice() {
+            [] => (stream.content.len() <= self.content_bytes).then(|| stream.content.clone())?,
+            [filter] if *filter == b"FlateDecode" && !stream.dict.has(b"DecodeParms") => {
+                let mut data = Vec::new();
+                ZlibDecoder::new(stream.content.as_slice())
+                    .take(self.content_bytes as u64 + 1)
+                    .read_to_end(&mut data)
+                    .ok()?;
+                (data.len() <= self.content_bytes).then_some(data)?
+            }
+            _ => return None,
+        };
+        self.content_bytes -= data.len();
+        Some(data)
+    }
+}
+
+/// 変換行列`matrix`で描画される画像の解像度を計算する
+fn place(mut details: ImageDetails, matrix: Matrix) -> PlacedImage {
+    // 画像は単位正方形に描画されるため、変換行列の各軸の長さがページ上の大きさになる
+    let dpi = |pixels: u32, (a, b): (f32, f32)| {
+        let points = a.hypot(b);
+        (points > 0.0).then(|| pixels as f32 * 72.0 / points)
+    };
+    details.dpi_x = dpi(details.width, (matrix[0], matrix[1]));
+    details.dpi_y = dpi(details.height, (matrix[2], matrix[3]));
+    PlacedImage { details, matrix }
+}
+
+/// 画像XObjectの辞書から詳細を作成する（解像度は描画する変換行列が決まってから`place`で計算する）
+fn image_details(doc: &LoDocument, dict: &Dictionary) -> Option<ImageDetails> {
+    let dimension = |key: &[u8]| {
+        get(doc, dict, key)
+            .and_then(|value| value.as_i64().ok())
+            .and_then(|value| u32::try_from(value).ok())
+    };
+    let width = dimension(b"Width")?;
+    let height = dimension(b"Height")?;
+
+    let color_space = get(doc, dict, b"ColorSpace").and_then(|color_space| {
+        let name = match color_space {
+            Object::Array(array) => resolve(doc, array.first()?),
+            name => name,
+        };
+        name.as_name()
+            .ok()
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+    });
+    let filters = match get(doc, dict, b"Filter") {
+        Some(Object::Array(filters)) => filters
+            .iter()
+            .filter_map(|filter| resolve(doc, filter).as_name().ok())
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .collect(),
+        Some(Object::Name(name)) => vec![String::from_utf8_lossy(name).into_owned()],
+        _ => Vec::new(),
+    };
+
+    Some(ImageDetails {
+        width,
+        height,
+        bits_per_component: dimension(b"BitsPerComponent"),
+        color_space,
+        filters,
+        dpi_x: None,
+        dpi_y: None,
+    })
+}
+
+/// `cm`などの6つの数値を変換行列にする
+fn matrix(doc: &LoDocument, operands: &[Object]) -> Option<Matrix> {
+    let values: Vec<f32> = operands
+        .iter()
+        .map(|value| resolve(doc, value).as_float().ok())
+        .collect::<Option<_>>()?;
+    values.try_into().ok()
+}
+
+/// `m`を`ctm`の前に掛けた変換行列（`m × ctm`）
+fn concat(m: Matrix, ctm: Matrix) -> Matrix {
+    [
+        m[0] * ctm[0] + m[1] * ctm[2],
+        m[0] * ctm[1] + m[1] * ctm[3],
+        m[2] * ctm[0] + m[3] * ctm[2],
+        m[2] * ctm[1] + m[3] * ctm[3],
+        m[4] * ctm[0] + m[5] * ctm[2] + ctm[4],
+        m[4] * ctm[1] + m[5] * ctm[3] + ctm[5],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use lopdf::dictionary;
+    use std::io::Write;
+
+    /// `width` x `height`ピクセルのグレースケール画像
+    fn add_image(doc: &mut LoDocument, width: i64, height: i64) -> ObjectId {
+        doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => width,
+                "Height" => height,
+                "BitsPerComponent" => 8,
+                "ColorSpace" => "DeviceGray",
+            },
+            Vec::new(),
+        ))
+    }
+
+    /// `xobjects`を名前で描画できるフォームXObject
+    fn form(content: &str, xobjects: Dictionary, matrix: Option<[f32; 6]>) -> Stream {
+        let mut dict = dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => vec![0.into(), 0.into(), 1.into(), 1.into()],
+            "Resources" => dictionary! { "XObject" => xobjects },
+        };
+        if let Some(matrix) = matrix {
+            dict.set("Matrix", matrix.map(Object::Real).to_vec());
+        }
+        Stream::new(dict, content.as_bytes().to_vec())
+    }
+
+    /// ページツリーのない単独のページ
+    fn add_page(doc: &mut LoDocument, contents: Stream, xobjects: Dictionary) -> ObjectId {
+        let contents = doc.add_object(contents);
+        doc.add_object(dictionary! {
+            "Type" => "Page",
+            "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            "Contents" => contents,
+            "Resources" => dictionary! { "XObject" => xobjects },
+        })
+    }
+
+    fn content(operators: &str) -> Stream {
+        Stream::new(Dictionary::new(), operators.as_bytes().to_vec())
+    }
+
+    fn flate(operators: &str) -> Stream {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(operators.as_bytes()).unwrap();
+        Stream::new(
+            dictionary! { "Filter" => "FlateDecode" },
+            encoder.finish().unwrap(),
+        )
+    }
+
+    fn scan(
+        scanner: &mut ImageScanner,
+        page_id: ObjectId,
+    ) -> Result<Vec<ImageDetails>, Vec<ImageDetails>> {
+        let page = scanner.doc.get_dictionary(page_id).unwrap();
+        scanner.page_images(page_id, page)
+    }
+
+    fn resolutions(images: &[ImageDetails]) -> Vec<(f32, f32)> {
+        images
+            .iter()
+            .map(|image| (image.dpi_x.unwrap(), image.dpi_y.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn resolution_follows_the_transformation_matrix() {
+        let mut doc = LoDocument::with_version("1.7");
+        let image = add_image(&mut doc, 300, 600);
+        let page = add_page(
+            &mut doc,
+            content("q 144 0 0 288 0 0 cm /Im0 Do Q q 0 72 -72 0 0 0 cm /Im0 Do Q"),
+            dictionary! { "Im0" => image },
+        );
+
+        let images = scan(&mut ImageScanner::new(&doc), page).unwrap();
+        // 回転していても各軸の長さで解像度を求める
+        assert_eq!(resolutions(&images), vec![(150.0, 150.0), (300.0, 600.0)]);
+        assert_eq!(max_dpi(&images), Some(600.0));
+    }
+
+    #[test]
+    fn forms_apply_their_matrix_and_the_caller_transform() {
+        let mut doc = LoDocument::with_version("1.7");
+        let image = add_image(&mut doc, 300, 300);
+        let form = doc.add_object(form(
+            "q 144 0 0 144 0 0 cm /Im0 Do Q",
+            dictionary! { "Im0" => image },
+            Some([0.5, 0.0, 0.0, 0.5, 0.0, 0.0]),
+        ));
+        let page = add_page(
+            &mut doc,
+            content("q 2 0 0 2 0 0 cm /Fm0 Do Q /Fm0 Do"),
+            dictionary! { "Fm0" => form },
+        );
+
+        let images = scan(&mut ImageScanner::new(&doc), page).unwrap();
+        assert_eq!(resolutions(&images), vec![(150.0, 150.0), (300.0, 300.0)]);
+    }
+
+    #[test]
+    fn forms_drawn_repeatedly_are_scanned_once() {
+        let mut doc = LoDocument::with_version("1.7");
+        let image = add_image(&mut doc, 72, 72);
+        let form = doc.add_object(form(
+            "q 1 0 0 1 0 0 cm /Im0 Do Q",
+            dictionary! { "Im0" => image },
+            None,
+        ));
+        let page = add_page(
+            &mut doc,
+            content("/Fm0 Do /Fm0 Do /Fm0 Do"),
+            dictionary! { "Fm0" => form },
+        );
+
+        let mut scanner = ImageScanner::new(&doc);
+        assert_eq!(scan(&mut scanner, page).unwrap().len(), 3);
+        assert_eq!(scanner.forms.len(), 1);
+        // ページの3つ、フォームの4つ（1回だけ）、再利用した画像3つ
+        assert_eq!(MAX_OPERATIONS - scanner.operations, 10);
+    }
+
+    #[test]
+    fn self_referencing_forms_terminate() {
+        let mut doc = LoDocument::with_version("1.7");
+        let image = add_image(&mut doc, 72, 72);
+        let form_id = doc.new_object_id();
+        doc.objects.insert(
+            form_id,
+            Object::Stream(form(
+                "q 72 0 0 72 0 0 cm /Im0 Do Q /Fm0 Do",
+                dictionary! { "Im0" => image, "Fm0" => form_id },
+                None,
+            )),
+        );
+        let page = add_page(
+            &mut doc,
+            content("/Fm0 Do"),
+            dictionary! { "Fm0" => form_id },
+        );
+
+        let images = scan(&mut ImageScanner::new(&doc), page).unwrap();
+        assert_eq!(resolutions(&images), vec![(72.0, 72.0)]);
+    }
+
+    #[test]
+    fn exhausting_the_operator_budget_returns_a_partial_result() {
+        // 10個ずつ描画するフォームを重ねると、描画される画像は10^4個になる
+        let mut doc = LoDocument::with_version("1.7");
+        let mut inner = add_image(&mut doc, 72, 72);
+        for _ in 0..4 {
+            inner = doc.add_object(form(
+                &"/X Do ".repeat(10),
+                dictionary! { "X" => inner },
+                None,
+            ));
+        }
+        let page = add_page(&mut doc, content("/X Do"), dictionary! { "X" => inner });
+
+        let mut scanner = ImageScanner {
+            operations: 1_000,
+            ..ImageScanner::new(&doc)
+        };
+        let partial = scan(&mut scanner, page).unwrap_err();
+        assert!(partial.len() < 1_000);
+
+        let mut scanner = ImageScanner::new(&doc);
+        assert_eq!(scan(&mut scanner, page).unwrap().len(), 10_000);
+    }
+
+    #[test]
+    fn flate_content_is_decoded_within_the_size_budget() {
+        let mut doc = LoDocument::with_version("1.7");
+        let image = add_image(&mut doc, 144, 144);
+        let page = add_page(
+            &mut doc,
+            flate("q 72 0 0 72 0 0 cm /Im0 Do Q"),
+            dictionary! { "Im0" => image },
+        );
+
+        let images = scan(&mut ImageScanner::new(&doc), page).unwrap();
+        assert_eq!(resolutions(&images), vec![(144.0, 144.0)]);
+
+        let mut scanner = ImageScanner {
+            content_bytes: 10,
+            ..ImageScanner::new(&doc)
+        };
+        assert_eq!(scan(&mut scanner, page), Err(Vec::new()));
+    }
+
+    #[test]
+    fn unsupported_filters_make_the_page_incomplete() {
+        let mut doc = LoDocument::with_version("1.7");
+        let image = add_image(&mut doc, 144, 144);
+        let mut contents = content("q 72 0 0 72 0 0 cm /Im0 Do Q");
+        contents.dict.set("Filter", "LZWDecode");
+        let page = add_page(&mut doc, contents, dictionary! { "Im0" => image });
+
+        assert_eq!(scan(&mut ImageScanner::new(&doc), page), Err(Vec::new()));
+    }
+
+    #[test]
+    fn page_image_dpis_lists_every_page_in_order() {
+        let mut doc = LoDocument::with_version("1.7");
+        let image = add_image(&mut doc, 300, 300);
+        let pages_id = doc.new_object_id();
+        let mut lzw = content("/Im0 Do");
+        lzw.dict.set("Filter", "LZWDecode");
+        let kids: Vec<Object> = [
+            content("q 144 0 0 144 0 0 cm /Im0 Do Q"),
+            content("BT ET"),
+            lzw,
+        ]
+        .into_iter()
+        .map(|contents| {
+            let page = add_page(&mut doc, contents, dictionary! { "Im0" => image });
+            doc.get_dictionary_mut(page)
+                .unwrap()
+                .set("Parent", pages_id);
+            page.into()
+        })
+        .collect();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+            }),
+        );
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog);
+        let mut data = Vec::new();
+        doc.save_to(&mut data).unwrap();
+
+        // 画像のないページと調べきれなかったページは`None`
+        assert_eq!(
+            page_image_dpis(&data).unwrap(),
+            vec![Some(150.0), None, None]
+        );
+        assert!(page_image_dpis(b"not a pdf").is_err());
+    }
+}
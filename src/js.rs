@@ -0,0 +1,192 @@
+//! Yewに依存しないJavaScript向けのAPI
+//!
+//! `wasm-pack build --no-default-features --features js`でビルドすると、
+//! TypeScriptの型定義付きのパッケージとして任意のフロントエンドから呼び出せる。
+//! エラーは`Error`のメッセージを持つJavaScriptの`Error`として返し、
+//! `kind`などのフィールドは`Error`をシリアライズした内容と同じになる。
+
+use js_sys::{Function, Object, Reflect, JSON};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::{Document, Error, PageFailed, ProgressEvent, RasterizeOptions};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT_TYPES: &str = r#"
+/** 変換のオプション（省略した項目はデフォルト値になる） */
+export interface RasterizeOptions {
+    /** 解像度（デフォルト: 72、`auto_dpi`を指定した場合は画像を含まないページの解像度と自動で決める解像度の下限） */
+    dpi?: number;
+    /** ページごとに埋め込み画像の解像度から決める場合の解像度の上限 */
+    auto_dpi?: number;
+    /** レンダリングする画像の最大幅（ピクセル） */
+    max_width?: number;
+    /** レンダリングする画像の最大高さ（ピクセル） */
+    max_height?: number;
+    /** レンダリングする画像の最大画素数（メガピクセル） */
+    max_megapixels?: number;
+    /** 出力PDFのページを揃える用紙サイズ（省略すると元のページの大きさ） */
+    paper?: "a4" | "letter";
+    /** 用紙サイズに揃えるときの配置方法（デフォルト: "fit"） */
+    paper_fit?: "fit" | "fill";
+    /** JPEG品質 1〜100（デフォルト: 85、`encoding`が"jpeg"の場合のみ使用） */
+    quality?: number;
+    /** 出力PDFに埋め込む画像の色空間（デフォルト: "rgb"） */
+    color_space?: "rgb" | "gray";
+    /** 出力PDFに埋め込む画像の圧縮方式（デフォルト: "jpeg"） */
+    encoding?: "jpeg" | "flate";
+    /** 変換するページの番号（0始まり）。省略するとすべてのページを変換する */
+    pages?: number[];
+    /** 信頼できない入力PDFに対する上限 */
+    limits?: SafetyLimits;
+}
+
+/** 信頼できない入力PDFに対する上限（省略した項目はデフォルト値、`null`で制限しない） */
+export interface SafetyLimits {
+    /** 入力PDFの最大サイズ（バイト） */
+    max_input_bytes?: number | null;
+    /** 入力PDFの最大ページ数 */
+    max_pages?: number | null;
+    /** 1ページの最大画素数（デフォルト: 200000000） */
+    max_page_pixels?: number | null;
+    /** 変換するすべてのページの画素数の合計の上限 */
+    max_total_pixels?: number | null;
+}
+
+/** 変換の進捗 */
+export type ProgressEvent =
+    | { kind: "parsed"; pages: number }
+    | { kind: "page_rendered"; index: number; bytes: number }
+    | { kind: "assembling"; done: number; total: number }
+    | { kind: "saved"; bytes: number };
+
+/** 進捗を受け取るコールバック */
+export type ProgressCallback = (event: ProgressEvent) => void;
+
+/** 失敗したときにPromiseが拒否される値 */
+export interface RasterizeError extends Error {
+    /** エラーの種類（例: "not_pdf"、"page_not_found"） */
+    kind: string;
+    /** 特定のページの処理で失敗した場合はそのページ（1始まり） */
+    page?: number;
+    [field: string]: unknown;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "RasterizeOptions")]
+    pub type JsRasterizeOptions;
+
+    #[wasm_bindgen(typescript_type = "ProgressCallback")]
+    pub type JsProgressCallback;
+}
+
+/// PDFを画像化し、変換後のPDFを返す
+///
+/// ブラウザへ制御を戻すのはページの間だけで、1ページのレンダリング中は呼び出したスレッドが止まる。
+/// 高DPIや大きなページでは画面が固まるため、Web Workerから呼び出すことを勧める。
+/// `progress`が投げた例外は無視して変換を続ける。
+#[wasm_bindgen]
+pub async fn rasterize(
+    data: Vec<u8>,
+    options: Option<JsRasterizeOptions>,
+    progress: Option<JsProgressCallback>,
+) -> Result<Vec<u8>, JsValue> {
+    let options = parse_options(options)?;
+    let progress = progress.map(JsCast::unchecked_into::<Function>);
+
+    crate::rasterize_pdf_with_progress(data, &options, |event| {
+        if let Some(progress) = &progress {
+            let _ = progress.call1(&JsValue::NULL, &progress_event(event));
+        }
+    })
+    .await
+    .map_err(|e| {
+        let error = js_error(&Error::from_anyhow(&e));
+        if let Some(failed) = e.downcast_ref::<PageFailed>() {
+            let _ = Reflect::set(&error, &"page".into(), &(failed.page as f64).into());
+        }
+        error
+    })
+}
+
+/// PDFのページ数を返す
+#[wasm_bindgen(js_name = pageCount)]
+pub fn page_count(data: Vec<u8>) -> Result<usize, JsValue> {
+    crate::pdf_info(data)
+        .map(|info| info.pages)
+        .map_err(|e| js_error(&Error::from_anyhow(&e)))
+}
+
+/// ページ（`index`は0始まり）を指定したDPIでレンダリングしてPNGにする
+///
+/// `dpi`を省略した場合は`RasterizeOptions`のデフォルトと同じ72になる。
+#[wasm_bindgen(js_name = renderPageToPng)]
+pub fn render_page_to_png(
+    data: Vec<u8>,
+    index: usize,
+    dpi: Option<u32>,
+) -> Result<Vec<u8>, JsValue> {
+    let dpi = dpi.unwrap_or_else(|| RasterizeOptions::default().dpi);
+
+    Document::load(data)
+        .and_then(|document| document.render_page_png(index, dpi))
+        .map_err(|e| js_error(&Error::from_anyhow(&e)))
+}
+
+/// オプションのオブジェクトを`RasterizeOptions`に変換する
+///
+/// プリセットと同じJSON形式として解釈する。
+fn parse_options(options: Option<JsRasterizeOptions>) -> Result<RasterizeOptions, JsValue> {
+    let Some(options) = options else {
+        return Ok(RasterizeOptions::default());
+    };
+
+    let json: String = JSON::stringify(&options)
+        .map_err(|_| invalid_options("not serializable as JSON"))?
+        .into();
+    serde_json::from_str(&json).map_err(|e| invalid_options(&e.to_string()))
+}
+
+fn invalid_options(reason: &str) -> JsValue {
+    js_error(&Error::InvalidOptions {
+        reason: reason.to_string(),
+    })
+}
+
+/// `Error`をメッセージとフィールドを持つJavaScriptの`Error`にする
+fn js_error(error: &Error) -> JsValue {
+    let js_error = js_sys::Error::new(&error.to_string());
+    let fields = serde_json::to_string(error)
+        .ok()
+        .and_then(|json| JSON::parse(&json).ok())
+        .and_then(|fields| fields.dyn_into::<Object>().ok());
+    if let Some(fields) = fields {
+        Object::assign(&js_error, &fields);
+    }
+    js_error.into()
+}
+
+/// 進捗を`ProgressEvent`型のオブジェクトにする
+fn progress_event(event: ProgressEvent) -> JsValue {
+    let (kind, fields): (&str, Vec<(&str, f64)>) = match event {
+        ProgressEvent::Parsed { pages } => ("parsed", vec![("pages", pages as f64)]),
+        ProgressEvent::PageRendered { index, bytes } => (
+            "page_rendered",
+            vec![("index", index as f64), ("bytes", bytes as f64)],
+        ),
+        ProgressEvent::Assembling { done, total } => (
+            "assembling",
+            vec![("done", done as f64), ("total", total as f64)],
+        ),
+        ProgressEvent::Saved { bytes } => ("saved", vec![("bytes", bytes as f64)]),
+    };
+
+    let object = Object::new();
+    let _ = Reflect::set(&object, &"kind".into(), &kind.into());
+    for (name, value) in fields {
+        let _ = Reflect::set(&object, &name.into(), &value.into());
+    }
+    object.into()
+}
@@ -0,0 +1,333 @@
+use hayro::RenderSettings;
+use hayro_syntax::page::Page;
+use serde::{Deserialize, Serialize};
+
+use crate::RasterizeOptions;
+
+/// 出力PDFのページを揃える用紙サイズ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaperSize {
+    /// A4（210 x 297 mm）
+    A4,
+    /// レター（8.5 x 11 inch）
+    Letter,
+}
+
+/// 用紙サイズに揃えるときのページの配置方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaperFit {
+    /// ページ全体が用紙に収まるように縮小・拡大し、余白を空ける
+    #[default]
+    Fit,
+    /// 用紙全体を覆うように縮小・拡大し、はみ出した部分は切り取る
+    Fill,
+}
+
+impl PaperSize {
+    /// 縦向きの大きさ（ポイント）
+    pub fn size(self) -> (f32, f32) {
+        match self {
+            Self::A4 => (595.276, 841.89),
+            Self::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+/// 出力PDFでのページの大きさと画像の配置（ポイント）
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PageLayout {
+    pub page_width: f32,
+    pub page_height: f32,
+    pub image_x: f32,
+    pub image_y: f32,
+    pub image_width: f32,
+    pub image_height: f32,
+}
+
+impl PageLayout {
+    /// 画像をDPIに応じた大きさのページ全体に配置する
+    pub fn from_dpi(width: u32, height: u32, dpi: f32) -> Self {
+        let page_width = width as f32 / dpi * 72.0;
+        let page_height = height as f32 / dpi * 72.0;
+        Self {
+            page_width,
+            page_height,
+            image_x: 0.0,
+            image_y: 0.0,
+            image_width: page_width,
+            image_height: page_height,
+        }
+    }
+}
+
+/// 1ページをレンダリングする倍率と出力PDFでの配置の決め方
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PagePlan {
+    /// レンダリングの倍率（1.0で72 DPI）
+    pub scale: f32,
+    /// 出力PDFのページ上での画像の解像度
+    resolution: f32,
+    /// 用紙の大きさ（ポイント、元のページと同じ向き）
+    paper: Option<(f32, f32)>,
+}
+
+impl PagePlan {
+    /// DPIと用紙・最大サイズの指定からページのレンダリング方法を決める
+    ///
+    /// 用紙に揃える場合は、用紙に配置した大きさで`dpi`になるようにレンダリングする。
+    /// 最大幅・最大高さ・最大画素数を超える場合は、縦横比を保ったまま倍率を下げる。
+    pub fn new(page: &Page, dpi: u32, options: &RasterizeOptions) -> Self {
+        let (width, height) = page.render_dimensions();
+        Self::for_size(width, height, dpi, options)
+    }
+
+    /// 大きさ`width` x `height`（ポイント）のページのレンダリング方法を決める
+    fn for_size(width: f32, height: f32, dpi: u32, options: &RasterizeOptions) -> Self {
+        let paper = options.paper.map(|paper| {
+            let (short, long) = paper.size();
+            if width > height {
+                (long, short)
+            } else {
+                (short, long)
+            }
+        });
+        let placement = match paper {
+            Some((paper_width, paper_height)) if width > 0.0 && height > 0.0 => {
+                let x = paper_width / width;
+                let y = paper_height / height;
+                match options.paper_fit {
+                    PaperFit::Fit => x.min(y),
+                    PaperFit::Fill => x.max(y),
+                }
+            }
+            _ => 1.0,
+        };
+
+        let uncapped = dpi as f32 / 72.0 * placement;
+        let mut scale = uncapped;
+        if let Some(max_width) = options.max_width.filter(|_| width > 0.0) {
+            scale = scale.min(max_width as f32 / width);
+        }
+        if let Some(max_height) = options.max_height.filter(|_| height > 0.0) {
+            scale = scale.min(max_height as f32 / height);
+        }
+        if let Some(max_megapixels) = options.max_megapixels.filter(|_| width * height > 0.0) {
+            scale = scale.min((max_megapixels * 1_000_000.0 / (width * height)).sqrt());
+        }
+
+        // 倍率を下げなければ指定されたDPIをそのまま使い、ページの大きさの誤差を避ける
+        let resolution = if scale < uncapped {
+            scale * 72.0 / placement
+        } else {
+            dpi as f32
+        };
+
+        Self {
+            scale,
+            resolution,
+            paper,
+        }
+    }
+
+    pub fn render_settings(&self) -> RenderSettings {
+        RenderSettings {
+            x_scale: self.scale,
+            y_scale: self.scale,
+            width: None,  // 自動計算
+            height: None, // 自動計算
+        }
+    }
+
+    /// レンダリングした画像（ピクセル）の出力PDFでの配置
+    pub fn layout(&self, width: u32, height: u32) -> PageLayout {
+        let layout = PageLayout::from_dpi(width, height, self.resolution);
+        let Some((page_width, page_height)) = self.paper else {
+            return layout;
+        };
+
+        PageLayout {
+            page_width,
+            page_height,
+            image_x: (page_width - layout.image_width) / 2.0,
+            image_y: (page_height - layout.image_height) / 2.0,
+            ..layout
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LETTER: (f32, f32) = (612.0, 792.0);
+
+    fn options() -> RasterizeOptions {
+        RasterizeOptions::default()
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    /// 72 DPIでは1ピクセルが1ポイントのため、画素数の丸めによる1ピクセル未満の誤差を許す
+    fn assert_within_pixel(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1.0,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn from_dpi_keeps_the_page_size() {
+        let layout = PageLayout::from_dpi(1275, 1650, 150.0);
+        assert_close(layout.page_width, 612.0);
+        assert_close(layout.page_height, 792.0);
+        assert_eq!((layout.image_x, layout.image_y), (0.0, 0.0));
+        assert_eq!(layout.image_width, layout.page_width);
+        assert_eq!(layout.image_height, layout.page_height);
+    }
+
+    #[test]
+    fn scales_to_the_requested_dpi() {
+        let plan = PagePlan::for_size(LETTER.0, LETTER.1, 144, &options());
+        assert_close(plan.scale, 2.0);
+        assert_eq!(
+            plan.layout(1224, 1584),
+            PageLayout::from_dpi(1224, 1584, 144.0)
+        );
+    }
+
+    #[test]
+    fn max_width_and_height_lower_the_scale_but_keep_the_page_size() {
+        let options = RasterizeOptions {
+            max_width: Some(612),
+            ..options()
+        };
+        let plan = PagePlan::for_size(LETTER.0, LETTER.1, 300, &options);
+        assert_close(plan.scale, 1.0);
+        let layout = plan.layout(612, 792);
+        assert_close(layout.page_width, 612.0);
+        assert_close(layout.page_height, 792.0);
+
+        let options = RasterizeOptions {
+            max_width: Some(10_000),
+            max_height: Some(396),
+            ..options
+        };
+        let plan = PagePlan::for_size(LETTER.0, LETTER.1, 300, &options);
+        assert_close(plan.scale, 0.5);
+    }
+
+    #[test]
+    fn max_megapixels_limits_the_pixel_count() {
+        let options = RasterizeOptions {
+            max_megapixels: Some(1.0),
+            ..options()
+        };
+        let plan = PagePlan::for_size(LETTER.0, LETTER.1, 600, &options);
+        let pixels = (LETTER.0 * plan.scale) * (LETTER.1 * plan.scale);
+        assert_close(pixels / 1_000_000.0, 1.0);
+    }
+
+    #[test]
+    fn limits_do_not_raise_the_scale() {
+        let options = RasterizeOptions {
+            max_width: Some(100_000),
+            max_height: Some(100_000),
+            max_megapixels: Some(1000.0),
+            ..options()
+        };
+        let plan = PagePlan::for_size(LETTER.0, LETTER.1, 72, &options);
+        assert_close(plan.scale, 1.0);
+    }
+
+    #[test]
+    fn fit_places_the_page_inside_the_paper() {
+        let options = RasterizeOptions {
+            paper: Some(PaperSize::A4),
+            paper_fit: PaperFit::Fit,
+            ..options()
+        };
+        let plan = PagePlan::for_size(LETTER.0, LETTER.1, 72, &options);
+        let (a4_width, a4_height) = PaperSize::A4.size();
+        // 幅で合わせるため、上下に余白が空く
+        assert_close(plan.scale, a4_width / LETTER.0);
+
+        let width = (LETTER.0 * plan.scale).round() as u32;
+        let height = (LETTER.1 * plan.scale).round() as u32;
+        let layout = plan.layout(width, height);
+        assert_eq!(
+            (layout.page_width, layout.page_height),
+            (a4_width, a4_height)
+        );
+        assert_within_pixel(layout.image_x, 0.0);
+        assert!(layout.image_y > 0.0);
+        assert_within_pixel(layout.image_y * 2.0 + layout.image_height, a4_height);
+    }
+
+    #[test]
+    fn fill_covers_the_paper_and_crops_the_rest() {
+        let options = RasterizeOptions {
+            paper: Some(PaperSize::A4),
+            paper_fit: PaperFit::Fill,
+            ..options()
+        };
+        let plan = PagePlan::for_size(LETTER.0, LETTER.1, 72, &options);
+        let (a4_width, a4_height) = PaperSize::A4.size();
+        // 高さで合わせるため、左右がはみ出す
+        assert_close(plan.scale, a4_height / LETTER.1);
+
+        let width = (LETTER.0 * plan.scale).round() as u32;
+        let height = (LETTER.1 * plan.scale).round() as u32;
+        let layout = plan.layout(width, height);
+        assert!(layout.image_x < 0.0);
+        assert_within_pixel(layout.image_y, 0.0);
+        assert_within_pixel(layout.image_x * 2.0 + layout.image_width, a4_width);
+    }
+
+    #[test]
+    fn landscape_pages_use_landscape_paper() {
+        let options = RasterizeOptions {
+            paper: Some(PaperSize::Letter),
+            ..options()
+        };
+        let plan = PagePlan::for_size(LETTER.1, LETTER.0, 150, &options);
+        let layout = plan.layout(1650, 1275);
+        assert_eq!((layout.page_width, layout.page_height), (792.0, 612.0));
+    }
+
+    #[test]
+    fn dpi_on_paper_is_measured_at_the_placed_size() {
+        // 用紙の半分の大きさのページは、2倍に拡大した大きさで150 DPIになる
+        let options = RasterizeOptions {
+            paper: Some(PaperSize::Letter),
+            ..options()
+        };
+        let plan = PagePlan::for_size(LETTER.0 / 2.0, LETTER.1 / 2.0, 150, &options);
+        assert_close(plan.scale, 150.0 / 72.0 * 2.0);
+        let layout = plan.layout(1275, 1650);
+        assert_close(layout.image_width, 612.0);
+        assert_close(layout.image_height, 792.0);
+    }
+
+    #[test]
+    fn empty_pages_do_not_divide_by_zero() {
+        let options = RasterizeOptions {
+            paper: Some(PaperSize::A4),
+            max_width: Some(100),
+            max_megapixels: Some(1.0),
+            ..options()
+        };
+        let plan = PagePlan::for_size(0.0, 0.0, 72, &options);
+        assert!(plan.scale.is_finite());
+    }
+}
@@ -1,203 +1,649 @@
 use anyhow::{Context, Result};
 use hayro::{InterpreterSettings, Pdf, RenderSettings};
+use hayro_syntax::page::Page;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+mod cancel;
+#[cfg(feature = "capi")]
+mod capi;
+mod document;
+mod encoding;
+mod error;
+pub mod i18n;
+mod info;
+mod inspect;
+mod layout;
+mod limits;
+mod options;
+mod page_range;
+mod progress;
+mod report;
+mod writer;
+
+pub use cancel::{CancellationToken, Cancelled};
+pub use document::{Document, PagePreview, SizeEstimate};
+use encoding::PageImage;
+pub use encoding::{ColorSpace, ImageEncoding, DEFAULT_JPEG_QUALITY};
+pub use error::Error;
+pub use info::{is_pdf, pdf_info, pdf_version, PdfInfo, HEADER_SEARCH_LIMIT, PDF_HEADER};
+pub use inspect::{inspect_pdf, FontDetails, ImageDetails, PageBox, PageDetails, PdfDetails};
+use layout::PagePlan;
+pub use layout::{PaperFit, PaperSize};
+pub use limits::{SafetyLimits, DEFAULT_MAX_PAGE_PIXELS, MAX_PAGE_DIMENSION};
+use options::PageDpis;
+pub use options::{RasterizeOptions, DEFAULT_MAX_AUTO_DPI, DEFAULT_MEMORY_LIMIT};
+pub use page_range::{format_page_ranges, parse_page_ranges, MAX_PAGE_NUMBER};
+pub use progress::{ProgressCallback, ProgressEvent};
+use report::Stopwatch;
+pub use report::{PageFailed, PageReport, RasterizeReport, StageTimings, Warning};
+use writer::{PdfWriter, RenderedPage};
+
 #[cfg(feature = "wasm")]
 mod app;
 
+#[cfg(feature = "wasm")]
+mod archive;
+
+#[cfg(feature = "wasm")]
+mod pwa;
+
+#[cfg(feature = "wasm")]
+mod settings;
+
 #[cfg(feature = "wasm")]
 pub use app::App;
 
 #[cfg(feature = "wasm")]
+pub mod worker;
+
+#[cfg(feature = "js")]
+mod js;
+
+#[cfg(feature = "js")]
 use gloo_timers::future::TimeoutFuture;
 
 /// PDFファイルを画像化してから再度PDFに変換する
 pub fn rasterize_pdf(pdf_data: Vec<u8>, dpi: u32) -> Result<Vec<u8>> {
-    let pdf = Pdf::new(Arc::new(pdf_data))
-        .map_err(|e| anyhow::anyhow!("PDFのパースに失敗しました: {:?}", e))?;
+    let options = RasterizeOptions {
+        dpi,
+        ..Default::default()
+    };
+    rasterize_pdf_with_options(pdf_data, &options)
+}
 
-    let page_count = pdf.pages().len();
+/// オプションを指定してPDFファイルを画像化する
+pub fn rasterize_pdf_with_options(
+    pdf_data: Vec<u8>,
+    options: &RasterizeOptions,
+) -> Result<Vec<u8>> {
+    rasterize_to_writer(pdf_data, Vec::new(), options)
+}
+
+/// PDFを画像化し、結果を任意のWriterへ順次書き出す
+///
+/// 出力全体をメモリ上に保持しないため、ファイルやソケットへ直接書き出せる。
+/// 書き出し後のWriterを返す。
+pub fn rasterize_to_writer<D, W>(pdf_data: D, output: W, options: &RasterizeOptions) -> Result<W>
+where
+    D: AsRef<[u8]> + Send + Sync + 'static,
+    W: Write,
+{
+    rasterize_to_writer_with_report(pdf_data, output, options).map(|(output, _)| output)
+}
 
-    #[cfg(feature = "wasm")]
+/// `rasterize_to_writer`と同じく変換し、書き出し後のWriterと変換結果の詳細を返す
+pub fn rasterize_to_writer_with_report<D, W>(
+    pdf_data: D,
+    output: W,
+    options: &RasterizeOptions,
+) -> Result<(W, RasterizeReport)>
+where
+    D: AsRef<[u8]> + Send + Sync + 'static,
+    W: Write,
+{
+    let input = load_input(pdf_data, options)?;
+    rasterize_into(&input, options, output)
+}
+
+/// 入力PDFファイルを画像化して出力PDFファイルへ保存し、変換結果の詳細を返す
+///
+/// 入力はメモリマップで読み込むため（利用できない環境では通常の読み込み）、
+/// 入力・出力ともにファイル全体のコピーをメモリ上に作らない。
+/// 出力は`partial_output_path`に書き出してから名前を変更するため、失敗しても既存の出力ファイルは残る。
+/// 入力と出力が同じファイルの場合はエラーにする。
+pub fn rasterize_file(
+    input: &Path,
+    output: &Path,
+    options: &RasterizeOptions,
+) -> Result<RasterizeReport> {
+    if is_same_file(input, output) {
+        anyhow::bail!(Error::SameInputOutput {
+            path: output.to_path_buf(),
+        });
+    }
+    let input_file = open_input(input)?;
+
+    write_output_file(output, |writer| {
+        let input = read_input(input_file, input, options)?;
+        rasterize_into(&input, options, writer)
+    })
+}
+
+/// 入力PDFファイルを画像化し、結果を任意のWriterへ順次書き出す
+///
+/// 入力は`rasterize_file`と同じくメモリマップで読み込む。
+/// 書き出し後のWriterと変換結果の詳細を返す。
+pub fn rasterize_file_to_writer<W>(
+    input: &Path,
+    output: W,
+    options: &RasterizeOptions,
+) -> Result<(W, RasterizeReport)>
+where
+    W: Write,
+{
+    let input = read_input(open_input(input)?, input, options)?;
+    rasterize_into(&input, options, output)
+}
+
+/// PDFデータを画像化して出力PDFファイルへ保存し、変換結果の詳細を返す
+///
+/// 標準入力など、ファイル以外から読み込んだPDFを保存する場合に使う。
+/// `rasterize_file`と同じく、失敗しても既存の出力ファイルは残る。
+pub fn rasterize_to_file<D>(
+    pdf_data: D,
+    output: &Path,
+    options: &RasterizeOptions,
+) -> Result<RasterizeReport>
+where
+    D: AsRef<[u8]> + Send + Sync + 'static,
+{
+    write_output_file(output, |writer| {
+        rasterize_to_writer_with_report(pdf_data, writer, options)
+    })
+}
+
+fn open_input(input: &Path) -> Result<File> {
+    File::open(input).with_context(|| Error::ReadInput {
+        path: input.to_path_buf(),
+    })
+}
+
+/// 出力ファイルへ書き出している間の一時ファイルのパス（出力と同じディレクトリの隠しファイル）
+///
+/// 変換が成功すると出力ファイルへ名前を変更する。強制終了した場合に残ったファイルを
+/// 呼び出し側で削除できるように公開している。
+pub fn partial_output_path(output: &Path) -> PathBuf {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(output.file_name().unwrap_or_default());
+    name.push(".partial");
+    output.with_file_name(name)
+}
+
+/// 2つのパスが同じファイルを指しているか（どちらかが存在しなければ`false`）
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::metadata(a), std::fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
     {
-        use gloo_console::log;
-        log!(format!("{}ページを処理します", page_count));
-        log!(format!("PDFをJPEG画像に変換中（DPI: {}）...", dpi));
+        match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
     }
+}
 
-    // DPIからスケールを計算（72 DPI = 1.0スケール）
-    let scale = dpi as f32 / 72.0;
+/// 一時ファイルへ`write`で書き出してディスクへ同期し、出力ファイルへ名前を変更する
+///
+/// `write`が返した値をそのまま返す。失敗した場合は一時ファイルを削除し、出力ファイルには触れない。
+fn write_output_file<F, T>(output: &Path, write: F) -> Result<T>
+where
+    F: FnOnce(BufWriter<File>) -> Result<(BufWriter<File>, T)>,
+{
+    let partial = partial_output_path(output);
+    let partial_file = File::create(&partial).with_context(|| Error::CreateOutput {
+        path: output.to_path_buf(),
+    })?;
+
+    let result = write(BufWriter::new(partial_file)).and_then(|(writer, value)| {
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())
+            .and_then(|file| file.sync_all())
+            .and_then(|()| std::fs::rename(&partial, output))
+            .context(Error::SaveOutput)?;
+        Ok(value)
+    });
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
 
-    let render_settings = RenderSettings {
-        x_scale: scale,
-        y_scale: scale,
-        width: None,  // 自動計算
-        height: None, // 自動計算
-    };
+    result
+}
 
-    let interpreter_settings = InterpreterSettings::default();
+/// パース済みの入力PDF
+struct Input {
+    pdf: Pdf,
+    /// 入力PDFのサイズ（バイト）
+    bytes: u64,
+    /// ページごとのDPI
+    dpis: PageDpis,
+    /// パース（DPIの自動決定を含む）にかかった時間（ミリ秒）
+    parse_ms: f64,
+}
 
-    // 各ページをメモリ上で画像に変換
-    #[cfg(feature = "cli")]
-    let image_data: Result<Vec<_>> = {
-        use rayon::prelude::*;
-        pdf.pages()
-            .par_iter()
-            .enumerate()
-            .map(|(page_index, page)| process_page(page, page_index, &interpreter_settings, &render_settings))
-            .collect()
-    };
+/// PDFデータをパースし、レポート用にサイズと時間を記録する
+///
+/// 入力サイズとページ数の上限を確認してから、ページごとのDPIを決める。
+fn load_input<D>(pdf_data: D, options: &RasterizeOptions) -> Result<Input>
+where
+    D: AsRef<[u8]> + Send + Sync + 'static,
+{
+    let bytes = pdf_data.as_ref().len() as u64;
+    options.limits.check_input(bytes)?;
+    let stopwatch = Stopwatch::start();
+    let pdf = parse_pdf(pdf_data)?;
+    options.limits.check_page_count(pdf.pages().len())?;
+    let dpis = PageDpis::new(options, pdf.data().as_ref().as_ref());
+
+    Ok(Input {
+        pdf,
+        bytes,
+        dpis,
+        parse_ms: stopwatch.elapsed_ms(),
+    })
+}
 
-    #[cfg(not(feature = "cli"))]
-    let image_data: Result<Vec<_>> = pdf
-        .pages()
+/// 入力ファイルをメモリマップしてパースする
+#[cfg(feature = "cli")]
+fn read_input(file: File, path: &Path, options: &RasterizeOptions) -> Result<Input> {
+    // 大きすぎるファイルはマップする前に断る
+    if let Ok(metadata) = file.metadata() {
+        options.limits.check_input(metadata.len())?;
+    }
+
+    // SAFETY: 変換中に他のプロセスが入力ファイルを書き換えないことを前提とする
+    match unsafe { memmap2::Mmap::map(&file) } {
+        Ok(mmap) => load_input(mmap, options),
+        Err(_) => read_input_to_memory(file, path, options),
+    }
+}
+
+/// メモリマップが使えない環境では入力ファイルをすべて読み込む
+#[cfg(not(feature = "cli"))]
+fn read_input(file: File, path: &Path, options: &RasterizeOptions) -> Result<Input> {
+    read_input_to_memory(file, path, options)
+}
+
+fn read_input_to_memory(file: File, path: &Path, options: &RasterizeOptions) -> Result<Input> {
+    use std::io::Read;
+
+    // 上限を超えたことが分かる分だけ読み込み、残りは読まない
+    let read_limit = options
+        .limits
+        .max_input_bytes
+        .map_or(u64::MAX, |limit| limit.saturating_add(1));
+    let mut pdf_data = Vec::new();
+    file.take(read_limit)
+        .read_to_end(&mut pdf_data)
+        .with_context(|| Error::ReadInput {
+            path: path.to_path_buf(),
+        })?;
+
+    load_input(pdf_data, options)
+}
+
+/// PDFデータをパースする
+fn parse_pdf<D>(pdf_data: D) -> Result<Pdf>
+where
+    D: AsRef<[u8]> + Send + Sync + 'static,
+{
+    Pdf::new(Arc::new(pdf_data)).map_err(|e| {
+        Error::ParsePdf {
+            reason: format!("{:?}", e),
+        }
+        .into()
+    })
+}
+
+/// ページをメモリ上限に収まる単位でレンダリングし、順にWriterへ書き出す
+fn rasterize_into<W: Write>(
+    input: &Input,
+    options: &RasterizeOptions,
+    output: W,
+) -> Result<(W, RasterizeReport)> {
+    let stopwatch = Stopwatch::start();
+    let all_pages = input.pdf.pages();
+    let selected = options.selected_pages(all_pages.len())?;
+    let pages: Vec<&Page> = selected.iter().map(|&index| &all_pages[index]).collect();
+    let dpis: Vec<u32> = selected
         .iter()
-        .enumerate()
-        .map(|(page_index, page)| process_page(page, page_index, &interpreter_settings, &render_settings))
+        .map(|&index| input.dpis.get(index))
         .collect();
+    let plans: Vec<PagePlan> = pages
+        .iter()
+        .zip(&dpis)
+        .map(|(page, &dpi)| PagePlan::new(page, dpi, options))
+        .collect();
+    check_page_sizes(&pages, &plans, &selected, &options.limits)?;
+    options.emit(ProgressEvent::Parsed { pages: pages.len() });
 
-    let mut image_data = image_data?;
-    // ページ順にソート
-    image_data.sort_by_key(|(idx, _, _, _)| *idx);
+    let interpreter_settings = InterpreterSettings::default();
 
-    #[cfg(feature = "wasm")]
-    {
-        use gloo_console::log;
-        log!(format!("{}ページの画像を生成しました", image_data.len()));
-        log!("画像からPDFを作成中...");
+    let executor = Executor::new(options.threads)?;
+    let mut writer = PdfWriter::new(output)?;
+    let mut timings = StageTimings {
+        parse_ms: input.parse_ms,
+        ..Default::default()
+    };
+    let mut page_reports = Vec::with_capacity(pages.len());
+
+    for window in page_windows(&pages, &plans, options.memory_limit) {
+        options.cancel.check()?;
+
+        // ウィンドウ内のページのみを同時にレンダリング
+        let image_data = executor.render_pages(
+            &pages[window.clone()],
+            &plans[window.clone()],
+            &selected[window.clone()],
+            window.start,
+            &interpreter_settings,
+            options,
+        )?;
+
+        // collectはページ順を保持するので、そのまま書き出せる
+        for (offset, rendered) in image_data.iter().enumerate() {
+            let index = window.start + offset;
+            let plan = &plans[index];
+            let layout = plan.layout(rendered.page.width, rendered.page.height);
+            let write_stopwatch = Stopwatch::start();
+            writer.add_page(&rendered.page, &layout)?;
+            timings.write_ms += write_stopwatch.elapsed_ms();
+            timings.render_ms += rendered.render_ms;
+            timings.encode_ms += rendered.encode_ms;
+
+            page_reports.push(PageReport {
+                source_index: selected[index],
+                dpi: dpis[index],
+                width: rendered.page.width,
+                height: rendered.page.height,
+                encoded_bytes: rendered.page.data.len(),
+                render_ms: rendered.render_ms,
+                encode_ms: rendered.encode_ms,
+                warnings: page_warnings(pages[index], plan.scale, options.memory_limit),
+            });
+            options.emit(ProgressEvent::Assembling {
+                done: index + 1,
+                total: pages.len(),
+            });
+        }
     }
 
-    // lopdfを使ってPDF ドキュメントを作成
-    let mut doc = lopdf::Document::with_version("1.5");
-
-    // 各画像をPDFページとして追加
-    for (page_num, (_, jpeg_bytes, img_w, img_h)) in image_data.iter().enumerate() {
-        let img_w = *img_w as f32;
-        let img_h = *img_h as f32;
-
-        let page_width = (img_w / dpi as f32) * 72.0;  // ポイント単位に変換
-        let page_height = (img_h / dpi as f32) * 72.0;
-
-        // ページIDを作成
-        let page_id = doc.new_object_id();
-
-        // 画像XObjectを作成
-        let image_id = doc.add_object(lopdf::Stream::new(
-            lopdf::Dictionary::from_iter(vec![
-                ("Type", lopdf::Object::Name(b"XObject".to_vec())),
-                ("Subtype", lopdf::Object::Name(b"Image".to_vec())),
-                ("Width", lopdf::Object::Integer(img_w as i64)),
-                ("Height", lopdf::Object::Integer(img_h as i64)),
-                ("ColorSpace", lopdf::Object::Name(b"DeviceRGB".to_vec())),
-                ("BitsPerComponent", lopdf::Object::Integer(8)),
-                ("Filter", lopdf::Object::Name(b"DCTDecode".to_vec())),
-            ]),
-            jpeg_bytes.clone(),
-        ));
-
-        // コンテンツストリームを作成（画像を配置）
-        let content = format!(
-            "q\n{} 0 0 {} 0 0 cm\n/Im{} Do\nQ",
-            page_width, page_height, page_num
-        );
-
-        let content_id = doc.add_object(lopdf::Stream::new(
-            lopdf::Dictionary::new(),
-            content.into_bytes(),
-        ));
-
-        // XObject辞書を作成
-        let mut xobject_dict = lopdf::Dictionary::new();
-        xobject_dict.set(
-            format!("Im{}", page_num).into_bytes(),
-            lopdf::Object::Reference(image_id),
-        );
-
-        // Resourcesディクショナリを作成
-        let mut resources_dict = lopdf::Dictionary::new();
-        resources_dict.set("XObject", xobject_dict);
-        let resources_id = doc.add_object(resources_dict);
-
-        // ページオブジェクトを作成
-        let page_dict = lopdf::Dictionary::from_iter(vec![
-            ("Type", lopdf::Object::Name(b"Page".to_vec())),
-            (
-                "MediaBox",
-                vec![0.into(), 0.into(), page_width.into(), page_height.into()].into(),
-            ),
-            ("Contents", lopdf::Object::Reference(content_id)),
-            ("Resources", lopdf::Object::Reference(resources_id)),
-        ]);
-
-        doc.objects.insert(page_id, lopdf::Object::Dictionary(page_dict));
+    let write_stopwatch = Stopwatch::start();
+    let (output, bytes) = writer.finish()?;
+    timings.write_ms += write_stopwatch.elapsed_ms();
+    timings.total_ms = input.parse_ms + stopwatch.elapsed_ms();
+    options.emit(ProgressEvent::Saved { bytes });
+
+    let report = RasterizeReport {
+        input_bytes: input.bytes,
+        output_bytes: bytes,
+        source_pages: all_pages.len(),
+        dpi: options.dpi,
+        auto_dpi: options.auto_dpi,
+        encoding: options.encoding,
+        color_space: options.color_space,
+        quality: (options.encoding == ImageEncoding::Jpeg).then_some(options.quality),
+        pages: page_reports,
+        warnings: input.dpis.warnings(),
+        timings,
+    };
+
+    Ok((output, report))
+}
+
+/// レンダリングする前に、各ページと全ページの合計の画素数が上限に収まるか確認する
+///
+/// `selected`は各ページの入力PDFでの番号（0始まり）。
+fn check_page_sizes(
+    pages: &[&Page],
+    plans: &[PagePlan],
+    selected: &[usize],
+    limits: &SafetyLimits,
+) -> Result<()> {
+    let mut total_pixels = 0u64;
+    for ((page, plan), &index) in pages.iter().zip(plans).zip(selected) {
+        let pixels = limits
+            .check_page(page, index, plan.scale)
+            .map_err(|e| report::page_error(e, index))?;
+        total_pixels = total_pixels.saturating_add(pixels);
     }
+    limits.check_total_pixels(total_pixels)
+}
+
+/// ページの変換で注意が必要な状況を調べる
+fn page_warnings(page: &Page, scale: f32, memory_limit: u64) -> Vec<Warning> {
+    let estimated_bytes = estimate_page_memory(page, scale);
+    if estimated_bytes > memory_limit {
+        vec![Warning::MemoryLimitExceeded {
+            estimated_bytes,
+            memory_limit,
+        }]
+    } else {
+        Vec::new()
+    }
+}
+
+/// レンダリングしたページとかかった時間（ミリ秒）
+struct TimedPage {
+    page: RenderedPage,
+    render_ms: f64,
+    encode_ms: f64,
+}
 
-    // すべてのページを収集
-    let page_ids: Vec<_> = image_data.iter().enumerate().map(|(i, _)| {
-        // ページIDは追加した順序で計算される
-        (1 + i * 4) as u32 // 各ページにつき4つのオブジェクトが作成されている
-    }).collect();
-
-    // Pagesオブジェクトを作成
-    let pages_id = doc.new_object_id();
-    doc.objects.insert(
-        pages_id,
-        lopdf::Dictionary::from_iter(vec![
-            ("Type", "Pages".into()),
-            ("Count", (image_data.len() as i64).into()),
-            (
-                "Kids",
-                lopdf::Object::Array(
-                    page_ids.iter().map(|&id| lopdf::Object::Reference((id, 0))).collect()
-                ),
-            ),
-        ])
-        .into(),
-    );
-
-    // すべてのページにParentを設定
-    for &page_id_val in &page_ids {
-        if let Some(page_obj) = doc.objects.get_mut(&(page_id_val, 0)) {
-            if let Ok(page_dict) = page_obj.as_dict_mut() {
-                page_dict.set("Parent", lopdf::Object::Reference(pages_id));
+/// ページのレンダリングを実行する方法
+enum Executor {
+    /// 呼び出し元のスレッドで1ページずつ処理する
+    Sequential,
+    /// rayonのグローバルスレッドプールで並列に処理する
+    #[cfg(feature = "cli")]
+    Global,
+    /// 変換専用のスレッドプールで並列に処理する
+    #[cfg(feature = "cli")]
+    Pool(rayon::ThreadPool),
+}
+
+impl Executor {
+    /// スレッド数の指定から実行方法を決める
+    #[cfg(feature = "cli")]
+    fn new(threads: Option<usize>) -> Result<Self> {
+        match threads {
+            None | Some(0) => Ok(Self::Global),
+            Some(1) => Ok(Self::Sequential),
+            Some(n) => rayon::ThreadPoolBuilder::new()
+                .num_threads(n)
+                .thread_name(|i| format!("pdf_rasterizer-{}", i))
+                .build()
+                .map(Self::Pool)
+                .context(Error::ThreadPool),
+        }
+    }
+
+    /// 並列処理が使えない環境では常に逐次処理する
+    #[cfg(not(feature = "cli"))]
+    fn new(_threads: Option<usize>) -> Result<Self> {
+        Ok(Self::Sequential)
+    }
+
+    /// ページをレンダリングし、ページ順に結果を返す
+    ///
+    /// 各ページの処理を始める前に中断が要求されていないか確認する。
+    /// `plans`は各ページのレンダリング方法、`source_indices`は各ページの入力PDFでの番号、
+    /// `first_index`は`pages`の先頭ページの出力PDF内での番号。
+    fn render_pages(
+        &self,
+        pages: &[&Page],
+        plans: &[PagePlan],
+        source_indices: &[usize],
+        first_index: usize,
+        interpreter_settings: &InterpreterSettings,
+        options: &RasterizeOptions,
+    ) -> Result<Vec<TimedPage>> {
+        let render_page = |offset: usize, page: &Page| -> Result<TimedPage> {
+            options.cancel.check()?;
+            let (image, render_ms) = render_page_timed(
+                page,
+                interpreter_settings,
+                &plans[offset].render_settings(),
+                options,
+                source_indices[offset],
+            )?;
+
+            let stopwatch = Stopwatch::start();
+            let rendered = encode_page_image(&image, options)?;
+            let encode_ms = stopwatch.elapsed_ms();
+
+            options.emit(ProgressEvent::PageRendered {
+                index: first_index + offset,
+                bytes: rendered.data.len(),
+            });
+            Ok(TimedPage {
+                page: rendered,
+                render_ms,
+                encode_ms,
+            })
+        };
+        let render = |(offset, page): (usize, &&Page)| {
+            render_page(offset, page).map_err(|e| report::page_error(e, source_indices[offset]))
+        };
+
+        match self {
+            Self::Sequential => pages.iter().enumerate().map(render).collect(),
+            #[cfg(feature = "cli")]
+            Self::Global => {
+                use rayon::prelude::*;
+                pages.par_iter().enumerate().map(render).collect()
+            }
+            #[cfg(feature = "cli")]
+            Self::Pool(pool) => {
+                use rayon::prelude::*;
+                pool.install(|| pages.par_iter().enumerate().map(render).collect())
             }
         }
     }
+}
 
-    // Catalogオブジェクトを作成
-    let catalog_id = doc.new_object_id();
-    doc.objects.insert(
-        catalog_id,
-        lopdf::Dictionary::from_iter(vec![
-            ("Type", "Catalog".into()),
-            ("Pages", lopdf::Object::Reference(pages_id)),
-        ])
-        .into(),
-    );
-
-    // Trailerを設定
-    doc.trailer.set("Root", lopdf::Object::Reference(catalog_id));
-
-    #[cfg(feature = "wasm")]
-    {
-        use gloo_console::log;
-        log!("PDFを生成しています...");
+/// DPIからレンダリング設定を作成する
+fn render_settings(dpi: u32) -> RenderSettings {
+    // DPIからスケールを計算（72 DPI = 1.0スケール）
+    let scale = dpi as f32 / 72.0;
+
+    RenderSettings {
+        x_scale: scale,
+        y_scale: scale,
+        width: None,  // 自動計算
+        height: None, // 自動計算
     }
+}
 
-    // PDFをバイト列として保存
-    let mut output = Vec::new();
-    doc.save_to(&mut output)
-        .context("PDFの保存に失敗しました")?;
+/// 倍率`scale`でレンダリングするときに1ページが使用するメモリ量を見積もる
+fn estimate_page_memory(page: &Page, scale: f32) -> u64 {
+    let (width, height) = limits::render_size(page, scale);
 
-    Ok(output)
+    // RGBAのピクセルマップ、RGBバッファ、JPEGデータを同時に保持する
+    width.saturating_mul(height).saturating_mul(8)
+}
+
+/// 見積もりメモリの合計が上限に収まるようにページを分割する（`plans`は各ページのレンダリング方法）
+fn page_windows(pages: &[&Page], plans: &[PagePlan], memory_limit: u64) -> Vec<Range<usize>> {
+    let mut windows = Vec::new();
+    let mut start = 0;
+    let mut used = 0u64;
+
+    for (index, (page, plan)) in pages.iter().zip(plans).enumerate() {
+        let estimate = estimate_page_memory(page, plan.scale);
+        if index > start && used.saturating_add(estimate) > memory_limit {
+            windows.push(start..index);
+            start = index;
+            used = 0;
+        }
+        used = used.saturating_add(estimate);
+    }
+
+    if start < pages.len() {
+        windows.push(start..pages.len());
+    }
+
+    windows
 }
 
 fn process_page(
-    page: &hayro_syntax::page::Page,
-    page_index: usize,
+    page: &Page,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings,
+    options: &RasterizeOptions,
+) -> Result<RenderedPage> {
+    let image = render_page_image(page, interpreter_settings, render_settings, options)?;
+    encode_page_image(&image, options)
+}
+
+/// ページをレンダリングし、出力する色空間の画像にする
+fn render_page_image(
+    page: &Page,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings,
+    options: &RasterizeOptions,
+) -> Result<PageImage> {
+    Ok(PageImage::new(
+        render_page_rgb(page, interpreter_settings, render_settings)?,
+        options.color_space,
+    ))
+}
+
+/// ページをレンダリングし、かかった時間（ミリ秒）を`SafetyLimits::max_page_render_ms`と照らし合わせる
+///
+/// `index`は入力PDFでの0始まりの番号。すべての変換の経路でこの関数を通してレンダリングする。
+fn render_page_timed(
+    page: &Page,
     interpreter_settings: &InterpreterSettings,
     render_settings: &RenderSettings,
-) -> Result<(usize, Vec<u8>, u32, u32)> {
+    options: &RasterizeOptions,
+    index: usize,
+) -> Result<(PageImage, f64)> {
+    let stopwatch = Stopwatch::start();
+    let image = render_page_image(page, interpreter_settings, render_settings, options)?;
+    let render_ms = stopwatch.elapsed_ms();
+    options.limits.check_render_time(index, render_ms)?;
+    Ok((image, render_ms))
+}
+
+/// 画像を出力PDFに埋め込む形式にエンコードする
+fn encode_page_image(image: &PageImage, options: &RasterizeOptions) -> Result<RenderedPage> {
+    Ok(RenderedPage {
+        data: image.encode(options.encoding, options.quality)?,
+        width: image.width(),
+        height: image.height(),
+        color_space: options.color_space,
+        encoding: options.encoding,
+    })
+}
+
+/// ページをレンダリングしてRGB画像を作成する
+fn render_page_rgb(
+    page: &Page,
+    interpreter_settings: &InterpreterSettings,
+    render_settings: &RenderSettings,
+) -> Result<image::RgbImage> {
     // ページをレンダリング
     let pixmap = hayro::render(page, interpreter_settings, render_settings);
 
@@ -209,7 +655,8 @@ fn process_page(
     let rgba_data = pixmap.take_u8();
 
     // RGBAからRGBに変換（alphaチャンネルを除去し、un-premultiply）
-    let mut rgb_data = Vec::with_capacity((width * height * 3) as usize);
+    // 幅×高さで計算するとu32であふれるため、確保済みのRGBAデータの大きさから求める
+    let mut rgb_data = Vec::with_capacity(rgba_data.len() / 4 * 3);
     for chunk in rgba_data.chunks_exact(4) {
         let r = chunk[0];
         let g = chunk[1];
@@ -230,220 +677,81 @@ fn process_page(
     }
 
     // RGB ImageBufferを作成
-    let image_buffer = image::RgbImage::from_vec(width, height, rgb_data)
-        .context("RGB画像バッファの作成に失敗しました")?;
-
-    // JPEG品質85でメモリ上にエンコード
-    let mut jpeg_data = Vec::new();
-    let mut jpeg_encoder =
-        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg_data, 85);
-    jpeg_encoder
-        .encode(
-            image_buffer.as_raw(),
-            width,
-            height,
-            image::ColorType::Rgb8.into(),
-        )
-        .context("JPEG画像のエンコードに失敗しました")?;
-
-    Ok((page_index, jpeg_data, width, height))
+    image::RgbImage::from_vec(width, height, rgb_data).context(Error::ImageBuffer)
 }
 
 /// 進捗コールバック付きでPDFを処理する（WASM専用）
-#[cfg(feature = "wasm")]
+///
+/// ページごとにブラウザへ制御を戻しながら処理する。
+#[cfg(feature = "js")]
 pub async fn rasterize_pdf_with_progress<F>(
     pdf_data: Vec<u8>,
-    dpi: u32,
+    options: &RasterizeOptions,
     progress_callback: F,
 ) -> Result<Vec<u8>>
 where
-    F: Fn(String),
+    F: Fn(ProgressEvent),
 {
-    use gloo_console::log;
-
-    let pdf = Pdf::new(Arc::new(pdf_data))
-        .map_err(|e| anyhow::anyhow!("PDFのパースに失敗しました: {:?}", e))?;
-
-    let page_count = pdf.pages().len();
-    log!(format!("{}ページを処理します", page_count));
-    progress_callback(format!("{}ページを読み込みました", page_count));
+    options.limits.check_input(pdf_data.len() as u64)?;
+    let pdf = parse_pdf(pdf_data)?;
+
+    options.limits.check_page_count(pdf.pages().len())?;
+    let dpis = PageDpis::new(options, pdf.data().as_ref().as_ref());
+    let selected = options.selected_pages(pdf.pages().len())?;
+    let page_count = selected.len();
+    let pages: Vec<&Page> = selected.iter().map(|&index| &pdf.pages()[index]).collect();
+    let plans: Vec<PagePlan> = pages
+        .iter()
+        .zip(&selected)
+        .map(|(page, &index)| PagePlan::new(page, dpis.get(index), options))
+        .collect();
+    check_page_sizes(&pages, &plans, &selected, &options.limits)?;
+    progress_callback(ProgressEvent::Parsed { pages: page_count });
 
     // UIを更新するために少し待機
     TimeoutFuture::new(10).await;
 
-    // DPIからスケールを計算（72 DPI = 1.0スケール）
-    let scale = dpi as f32 / 72.0;
-
-    let render_settings = RenderSettings {
-        x_scale: scale,
-        y_scale: scale,
-        width: None,
-        height: None,
-    };
-
     let interpreter_settings = InterpreterSettings::default();
 
-    // 各ページを順番に処理（非同期）
-    let mut image_data = Vec::new();
-    for (page_index, page) in pdf.pages().iter().enumerate() {
-        progress_callback(format!(
-            "ページ {}/{} を画像化中...",
-            page_index + 1,
-            page_count
-        ));
-        log!(format!("ページ {}/{} を処理中", page_index + 1, page_count));
+    let mut writer = PdfWriter::new(Vec::new())?;
+
+    // 各ページを順番に処理し、画像化したページはすぐにPDFへ書き出す（非同期）
+    for (page_index, (page, plan)) in pages.iter().zip(&plans).enumerate() {
+        options.cancel.check()?;
 
-        let result = process_page(page, page_index, &interpreter_settings, &render_settings)?;
-        image_data.push(result);
+        let index = selected[page_index];
+        let (image, _) = render_page_timed(
+            page,
+            &interpreter_settings,
+            &plan.render_settings(),
+            options,
+            index,
+        )
+        .map_err(|e| report::page_error(e, index))?;
+        let rendered =
+            encode_page_image(&image, options).map_err(|e| report::page_error(e, index))?;
+        progress_callback(ProgressEvent::PageRendered {
+            index: page_index,
+            bytes: rendered.data.len(),
+        });
+
+        writer.add_page(&rendered, &plan.layout(rendered.width, rendered.height))?;
+        progress_callback(ProgressEvent::Assembling {
+            done: page_index + 1,
+            total: page_count,
+        });
 
         // 各ページ処理後にブラウザに制御を戻す
         TimeoutFuture::new(1).await;
     }
 
-    log!(format!("{}ページの画像を生成しました", image_data.len()));
-    progress_callback("PDFを作成中...".to_string());
-
-    // UIを更新するために少し待機
-    TimeoutFuture::new(10).await;
-
-    // lopdfを使ってPDF ドキュメントを作成
-    let mut doc = lopdf::Document::with_version("1.5");
-
-    // 各画像をPDFページとして追加
-    for (page_num, (_, jpeg_bytes, img_w, img_h)) in image_data.iter().enumerate() {
-        if page_num % 5 == 0 {
-            progress_callback(format!(
-                "PDF作成中... ({}/{})",
-                page_num + 1,
-                image_data.len()
-            ));
-            // 5ページごとにUIを更新
-            TimeoutFuture::new(1).await;
-        }
-
-        let img_w = *img_w as f32;
-        let img_h = *img_h as f32;
-
-        let page_width = (img_w / dpi as f32) * 72.0;
-        let page_height = (img_h / dpi as f32) * 72.0;
-
-        // ページIDを作成
-        let page_id = doc.new_object_id();
-
-        // 画像XObjectを作成
-        let image_id = doc.add_object(lopdf::Stream::new(
-            lopdf::Dictionary::from_iter(vec![
-                ("Type", lopdf::Object::Name(b"XObject".to_vec())),
-                ("Subtype", lopdf::Object::Name(b"Image".to_vec())),
-                ("Width", lopdf::Object::Integer(img_w as i64)),
-                ("Height", lopdf::Object::Integer(img_h as i64)),
-                ("ColorSpace", lopdf::Object::Name(b"DeviceRGB".to_vec())),
-                ("BitsPerComponent", lopdf::Object::Integer(8)),
-                ("Filter", lopdf::Object::Name(b"DCTDecode".to_vec())),
-            ]),
-            jpeg_bytes.clone(),
-        ));
-
-        // コンテンツストリームを作成（画像を配置）
-        let content = format!(
-            "q\n{} 0 0 {} 0 0 cm\n/Im{} Do\nQ",
-            page_width, page_height, page_num
-        );
-
-        let content_id = doc.add_object(lopdf::Stream::new(
-            lopdf::Dictionary::new(),
-            content.into_bytes(),
-        ));
-
-        // XObject辞書を作成
-        let mut xobject_dict = lopdf::Dictionary::new();
-        xobject_dict.set(
-            format!("Im{}", page_num).into_bytes(),
-            lopdf::Object::Reference(image_id),
-        );
-
-        // Resourcesディクショナリを作成
-        let mut resources_dict = lopdf::Dictionary::new();
-        resources_dict.set("XObject", xobject_dict);
-        let resources_id = doc.add_object(resources_dict);
-
-        // ページオブジェクトを作成
-        let page_dict = lopdf::Dictionary::from_iter(vec![
-            ("Type", lopdf::Object::Name(b"Page".to_vec())),
-            (
-                "MediaBox",
-                vec![0.into(), 0.into(), page_width.into(), page_height.into()].into(),
-            ),
-            ("Contents", lopdf::Object::Reference(content_id)),
-            ("Resources", lopdf::Object::Reference(resources_id)),
-        ]);
-
-        doc.objects.insert(page_id, lopdf::Object::Dictionary(page_dict));
-    }
-
-    // すべてのページを収集
-    let page_ids: Vec<_> = image_data
-        .iter()
-        .enumerate()
-        .map(|(i, _)| (1 + i * 4) as u32)
-        .collect();
-
-    // Pagesオブジェクトを作成
-    let pages_id = doc.new_object_id();
-    doc.objects.insert(
-        pages_id,
-        lopdf::Dictionary::from_iter(vec![
-            ("Type", "Pages".into()),
-            ("Count", (image_data.len() as i64).into()),
-            (
-                "Kids",
-                lopdf::Object::Array(
-                    page_ids
-                        .iter()
-                        .map(|&id| lopdf::Object::Reference((id, 0)))
-                        .collect(),
-                ),
-            ),
-        ])
-        .into(),
-    );
-
-    // すべてのページにParentを設定
-    for &page_id_val in &page_ids {
-        if let Some(page_obj) = doc.objects.get_mut(&(page_id_val, 0)) {
-            if let Ok(page_dict) = page_obj.as_dict_mut() {
-                page_dict.set("Parent", lopdf::Object::Reference(pages_id));
-            }
-        }
-    }
-
-    // Catalogオブジェクトを作成
-    let catalog_id = doc.new_object_id();
-    doc.objects.insert(
-        catalog_id,
-        lopdf::Dictionary::from_iter(vec![
-            ("Type", "Catalog".into()),
-            ("Pages", lopdf::Object::Reference(pages_id)),
-        ])
-        .into(),
-    );
-
-    // Trailerを設定
-    doc.trailer.set("Root", lopdf::Object::Reference(catalog_id));
-
-    progress_callback("PDFを保存中...".to_string());
-    log!("PDFを生成しています...");
-
     // UIを更新するために少し待機
     TimeoutFuture::new(10).await;
+    options.cancel.check()?;
 
-    // PDFをバイト列として保存
-    let mut output = Vec::new();
-    doc.save_to(&mut output)
-        .context("PDFの保存に失敗しました")?;
+    let (output, bytes) = writer.finish()?;
 
-    log!("完了しました");
-    progress_callback("完了しました！".to_string());
+    progress_callback(ProgressEvent::Saved { bytes });
 
     Ok(output)
 }
@@ -0,0 +1,279 @@
+use anyhow::{bail, Result};
+use hayro_syntax::page::Page;
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// 1ページの画素数のデフォルトの上限（2億ピクセル、RGBAで約800MB）
+pub const DEFAULT_MAX_PAGE_PIXELS: u64 = 200_000_000;
+
+/// レンダリングできる画像の幅・高さの上限（ピクセル）
+///
+/// レンダラーのピクセルマップは幅と高さを16ビットで持つため、設定に関わらず常に適用する。
+pub const MAX_PAGE_DIMENSION: u64 = u16::MAX as u64;
+
+/// 信頼できない入力PDFから変換処理を守るための上限
+///
+/// 小さなファイルでも巨大なMediaBoxや大量のページを持つPDFは、レンダリング時に
+/// 膨大なメモリや時間を使う。上限を超える場合は、メモリを確保する前に型付きのエラーで失敗する。
+/// `None`の項目は制限しない。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SafetyLimits {
+    /// 入力PDFの最大サイズ（バイト）
+    pub max_input_bytes: Option<u64>,
+    /// 入力PDFの最大ページ数（変換するページの選択に関わらず文書全体で数える）
+    pub max_pages: Option<usize>,
+    /// 1ページをレンダリングした画像の最大画素数
+    ///
+    /// `RasterizeOptions::max_megapixels`と違い、解像度を下げずにエラーにする。
+    /// 最大サイズの指定で解像度を下げた後の画素数で判定する。
+    pub max_page_pixels: Option<u64>,
+    /// 変換するすべてのページの画素数の合計の上限
+    pub max_total_pixels: Option<u64>,
+    /// 1ページのレンダリングにかかった時間の上限（ミリ秒）
+    ///
+    /// タイムアウトではなく、レンダリングが終わった後に確認する。
+    /// レンダリング中のページは途中で止められないため、時間を超えたページもレンダリングは最後まで続き、
+    /// 終わった時点で失敗してまだ始めていないページは処理しない。1ページにかかる時間そのものは制限しないため、
+    /// 時間を確実に制限するには別のプロセスで変換して外から止める。
+    /// CLI・ライブラリ・JavaScript API・Webアプリ（ワーカープールを含む）のすべての変換で確認する。
+    pub max_page_render_ms: Option<u64>,
+}
+
+impl Default for SafetyLimits {
+    fn default() -> Self {
+        Self {
+            max_input_bytes: None,
+            max_pages: None,
+            max_page_pixels: Some(DEFAULT_MAX_PAGE_PIXELS),
+            max_total_pixels: None,
+            max_page_render_ms: None,
+        }
+    }
+}
+
+impl SafetyLimits {
+    /// 入力PDFのサイズを確認する
+    pub(crate) fn check_input(&self, bytes: u64) -> Result<()> {
+        match self.max_input_bytes {
+            Some(limit) if bytes > limit => bail!(Error::InputTooLarge { bytes, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// 入力PDFのページ数を確認する
+    pub(crate) fn check_page_count(&self, pages: usize) -> Result<()> {
+        match self.max_pages {
+            Some(limit) if pages > limit => bail!(Error::TooManyPages { pages, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// 倍率`scale`でレンダリングしたときのページの大きさを確認し、画素数を返す
+    ///
+    /// `index`は入力PDFでのページ番号（0始まり）。
+    pub(crate) fn check_page(&self, page: &Page, index: usize, scale: f32) -> Result<u64> {
+        self.check_page_size(index, render_size(page, scale))
+    }
+
+    /// レンダリングした画像の幅と高さ（ピクセル）を確認し、画素数を返す
+    fn check_page_size(&self, index: usize, (width, height): (u64, u64)) -> Result<u64> {
+        if width > MAX_PAGE_DIMENSION || height > MAX_PAGE_DIMENSION {
+            bail!(Error::PageDimensionTooLarge {
+                page: index + 1,
+                width,
+                height,
+                max: MAX_PAGE_DIMENSION,
+            });
+        }
+
+        let pixels = width * height;
+        match self.max_page_pixels {
+            Some(limit) if pixels > limit => bail!(Error::PageTooLarge {
+                page: index + 1,
+                width,
+                height,
+                limit,
+            }),
+            _ => Ok(pixels),
+        }
+    }
+
+    /// 変換するページの画素数の合計を確認する
+    pub(crate) fn check_total_pixels(&self, pixels: u64) -> Result<()> {
+        match self.max_total_pixels {
+            Some(limit) if pixels > limit => bail!(Error::TotalPixelsExceeded { pixels, limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// レンダリングが終わったページについて、かかった時間を確認する（`index`は0始まり）
+    pub(crate) fn check_render_time(&self, index: usize, elapsed_ms: f64) -> Result<()> {
+        match self.max_page_render_ms {
+            Some(limit_ms) if elapsed_ms > limit_ms as f64 => bail!(Error::PageRenderTooSlow {
+                page: index + 1,
+                elapsed_ms: elapsed_ms as u64,
+                limit_ms,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 倍率`scale`でレンダリングしたときの画像の幅と高さ（ピクセル）
+///
+/// 非常に大きな値は`u64`の範囲に丸められるため、掛け算してもあふれないよう呼び出し側で確認する。
+pub(crate) fn render_size(page: &Page, scale: f32) -> (u64, u64) {
+    scaled_size(page.render_dimensions(), scale)
+}
+
+/// 大きさ`width` x `height`（ポイント）のページを倍率`scale`でレンダリングした画像の幅と高さ
+fn scaled_size((width, height): (f32, f32), scale: f32) -> (u64, u64) {
+    (
+        (width * scale).ceil() as u64,
+        (height * scale).ceil() as u64,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(result: Result<impl std::fmt::Debug>) -> Error {
+        result
+            .unwrap_err()
+            .downcast::<Error>()
+            .expect("library error")
+    }
+
+    fn unlimited() -> SafetyLimits {
+        SafetyLimits {
+            max_input_bytes: None,
+            max_pages: None,
+            max_page_pixels: None,
+            max_total_pixels: None,
+            max_page_render_ms: None,
+        }
+    }
+
+    #[test]
+    fn default_limits_only_the_page_pixels() {
+        assert_eq!(
+            SafetyLimits::default(),
+            SafetyLimits {
+                max_page_pixels: Some(DEFAULT_MAX_PAGE_PIXELS),
+                ..unlimited()
+            }
+        );
+    }
+
+    #[test]
+    fn unset_limits_accept_anything() {
+        let limits = unlimited();
+        limits.check_input(u64::MAX).unwrap();
+        limits.check_page_count(usize::MAX).unwrap();
+        limits.check_total_pixels(u64::MAX).unwrap();
+        limits.check_render_time(0, f64::MAX).unwrap();
+        assert_eq!(
+            limits.check_page_size(0, (60_000, 60_000)).unwrap(),
+            3_600_000_000
+        );
+    }
+
+    #[test]
+    fn limits_allow_values_up_to_the_limit() {
+        let limits = SafetyLimits {
+            max_input_bytes: Some(100),
+            max_pages: Some(10),
+            max_page_pixels: Some(1_000),
+            max_total_pixels: Some(5_000),
+            max_page_render_ms: Some(50),
+        };
+        limits.check_input(100).unwrap();
+        limits.check_page_count(10).unwrap();
+        assert_eq!(limits.check_page_size(0, (10, 100)).unwrap(), 1_000);
+        limits.check_total_pixels(5_000).unwrap();
+        limits.check_render_time(0, 50.0).unwrap();
+    }
+
+    #[test]
+    fn limits_reject_values_over_the_limit() {
+        let limits = SafetyLimits {
+            max_input_bytes: Some(100),
+            max_pages: Some(10),
+            max_page_pixels: Some(1_000),
+            max_total_pixels: Some(5_000),
+            max_page_render_ms: Some(50),
+        };
+        assert_eq!(
+            error(limits.check_input(101)),
+            Error::InputTooLarge {
+                bytes: 101,
+                limit: 100,
+            }
+        );
+        assert_eq!(
+            error(limits.check_page_count(11)),
+            Error::TooManyPages {
+                pages: 11,
+                limit: 10,
+            }
+        );
+        assert_eq!(
+            error(limits.check_page_size(2, (11, 100))),
+            Error::PageTooLarge {
+                page: 3,
+                width: 11,
+                height: 100,
+                limit: 1_000,
+            }
+        );
+        assert_eq!(
+            error(limits.check_total_pixels(5_001)),
+            Error::TotalPixelsExceeded {
+                pixels: 5_001,
+                limit: 5_000,
+            }
+        );
+        assert_eq!(
+            error(limits.check_render_time(4, 50.5)),
+            Error::PageRenderTooSlow {
+                page: 5,
+                elapsed_ms: 50,
+                limit_ms: 50,
+            }
+        );
+    }
+
+    #[test]
+    fn page_dimensions_are_limited_regardless_of_settings() {
+        let limits = unlimited();
+        limits
+            .check_page_size(0, (MAX_PAGE_DIMENSION, MAX_PAGE_DIMENSION))
+            .unwrap();
+        assert_eq!(
+            error(limits.check_page_size(0, (MAX_PAGE_DIMENSION + 1, 1))),
+            Error::PageDimensionTooLarge {
+                page: 1,
+                width: MAX_PAGE_DIMENSION + 1,
+                height: 1,
+                max: MAX_PAGE_DIMENSION,
+            }
+        );
+    }
+
+    #[test]
+    fn scaled_size_rounds_up_and_saturates() {
+        assert_eq!(scaled_size((612.0, 792.0), 1.0), (612, 792));
+        assert_eq!(scaled_size((612.0, 792.0), 150.0 / 72.0), (1275, 1650));
+        assert_eq!(scaled_size((10.2, 0.1), 1.0), (11, 1));
+        // 巨大なMediaBoxでも`u64`の範囲に収まり、画素数の確認で弾かれる
+        let (width, height) = scaled_size((f32::MAX, f32::MAX), 10.0);
+        assert_eq!((width, height), (u64::MAX, u64::MAX));
+        assert!(matches!(
+            error(SafetyLimits::default().check_page_size(0, (width, height))),
+            Error::PageDimensionTooLarge { .. }
+        ));
+    }
+}
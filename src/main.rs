@@ -1,65 +1,802 @@
 use anyhow::{Context, Result};
-use seahorse::{App, Flag, FlagType};
+use pdf_rasterizer::i18n::{error_message, tr, trf, Lang};
+use pdf_rasterizer::{
+    CancellationToken, Cancelled, PaperFit, PaperSize, ProgressCallback, ProgressEvent,
+    RasterizeOptions, RasterizeReport, SafetyLimits, Warning, DEFAULT_MAX_AUTO_DPI,
+};
+use seahorse::{App, Command, Flag, FlagType};
 use std::env;
-use std::path::PathBuf;
+use std::io::{BufWriter, IsTerminal, Read, StdoutLock, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use cli_report::{FileReport, FileStatus};
+
+/// 状態や進捗を標準エラー出力に表示する（`--quiet`が指定されていれば表示しない）
+///
+/// 標準出力は`-`を指定したときの出力PDFのために空けておく。
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::QUIET.load(std::sync::atomic::Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+mod batch;
+mod cli_config;
+mod cli_info;
+mod cli_report;
+mod watch;
+
+/// `--quiet`が指定されたかどうか
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// 入出力のパスに指定すると標準入力・標準出力を使う
+const STDIO_PATH: &str = "-";
+
+/// `watch`で`--error-dir`を省略した場合のエラーフォルダー（出力フォルダーからの相対パス）
+const WATCH_ERROR_DIR: &str = "errors";
+
+/// 書き出し中の出力ファイル（強制終了するときに書きかけの一時ファイルを削除する）
+pub type PartialOutputs = Arc<Mutex<Vec<PathBuf>>>;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
+    // ヘルプの説明文も翻訳するため、コマンドラインの解析より前に言語を決める
+    let lang = cli_lang().unwrap_or_else(|code| {
+        eprintln!("{}", trf(Lang::from_env(), "cli.unknown_lang", &[&code]));
+        std::process::exit(1);
+    });
+
     let app = App::new(env!("CARGO_PKG_NAME"))
-        .description("PDFファイルを画像化してから再度PDFに変換するツール")
+        .description(tr(lang, "app.description"))
         .version(env!("CARGO_PKG_VERSION"))
-        .usage("pdf_rasterizer <input> <output> [--dpi <value>]")
-        .flag(Flag::new("dpi", FlagType::Int).description("ラスタライズ時のDPI（解像度）"))
+        .usage("pdf_rasterizer <input|-> <output|-> [--dpi <value|auto>] [--max-dpi <value>] [--max-width <px>] [--max-height <px>] [--max-megapixels <MP>] [--paper <a4|letter>] [--paper-fit <fit|fill>] [--memory-limit <MB>] [--jobs <N>] [--lang <en|ja>] [--quiet] [--report json] [--report-file <path>] [--profile <name>]");
+    let app = conversion_flags(lang)
+        .into_iter()
+        .fold(app, App::flag)
+        .command(batch_command(lang))
+        .command(info_command(lang))
+        .command(watch_command(lang))
+        .command(config_command(lang))
         .action(|c| {
+            // 指定された言語は起動時に確認済み
+            let lang = cli_lang().unwrap_or_default();
+            QUIET.store(c.bool_flag("quiet"), Ordering::Relaxed);
             let input = PathBuf::from(
                 c.args
-                    .get(0)
-                    .expect("入力PDFファイルのパスを指定してください"),
+                    .first()
+                    .unwrap_or_else(|| panic!("{}", tr(lang, "cli.missing_input"))),
             );
             let output = PathBuf::from(
                 c.args
                     .get(1)
-                    .expect("出力PDFファイルのパスを指定してください"),
+                    .unwrap_or_else(|| panic!("{}", tr(lang, "cli.missing_output"))),
+            );
+            let options = RasterizeOptions {
+                progress: (!QUIET.load(Ordering::Relaxed)).then(|| progress_bar(lang)),
+                ..conversion_options(lang, c)
+            };
+            if is_stdio(&output) && std::io::stdout().is_terminal() {
+                exit_with_error(lang, tr(lang, "cli.stdout_terminal"));
+            }
+            let report_target = cli_report::target(lang, c)
+                .unwrap_or_else(|message| exit_with_error(lang, &message));
+            if report_target
+                .as_ref()
+                .is_some_and(|target| target.path.is_none() && is_stdio(&output))
+            {
+                exit_with_error(lang, tr(lang, "cli.report.stdout_conflict"));
+            }
+
+            status!("{}", tr(lang, "cli.optimizing"));
+            status!(
+                "{}",
+                trf(
+                    lang,
+                    "cli.input",
+                    &[&display_path(lang, &input, "cli.stdin")]
+                )
+            );
+            status!(
+                "{}",
+                trf(
+                    lang,
+                    "cli.output",
+                    &[&display_path(lang, &output, "cli.stdout")]
+                )
             );
-            let dpi = c.int_flag("dpi").unwrap_or(72) as u32;
+            print_settings(lang, &options, options.threads);
 
-            println!("PDFを最適化しています...");
-            println!("入力: {}", input.display());
-            println!("出力: {}", output.display());
-            println!("DPI: {}", dpi);
+            let partial_outputs = PartialOutputs::default();
+            if !is_stdio(&output) {
+                partial_outputs
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(output.clone());
+            }
+            if let Err(e) = install_interrupt_handler(lang, options.cancel.clone(), partial_outputs)
+            {
+                eprintln!("{}", trf(lang, "cli.error", &[&error_message(lang, &e)]));
+                std::process::exit(1);
+            }
 
-            if let Err(e) = process_pdf(&input, &output, dpi) {
-                eprintln!("エラー: {}", e);
+            let result = process_pdf(lang, &input, &output, &options);
+            if let Some(target) = &report_target {
+                let status = FileStatus::from_result(lang, &result);
+                if let Err(e) =
+                    cli_report::write(lang, target, &FileReport::new(&input, &output, status))
+                {
+                    eprintln!("{}", trf(lang, "cli.error", &[&error_message(lang, &e)]));
+                }
+            }
+            if let Err(e) = result {
+                if e.is::<Cancelled>() {
+                    eprintln!("{}", tr(lang, "cli.cancelled"));
+                    std::process::exit(130);
+                }
+                eprintln!("{}", trf(lang, "cli.error", &[&error_message(lang, &e)]));
                 std::process::exit(1);
             }
 
-            println!("✓ 最適化が完了しました");
+            if let Ok(report) = &result {
+                print_warnings(lang, &input, report);
+            }
+            status!("{}", tr(lang, "cli.done"));
 
             Ok(())
         });
 
     if let Err(e) = app.run(args) {
-        eprintln!("エラー: {}", e);
+        eprintln!("{}", trf(lang, "cli.error", &[&e]));
         std::process::exit(1);
     }
 }
 
-fn process_pdf(input_path: &PathBuf, output_path: &PathBuf, dpi: u32) -> Result<()> {
-    println!("  hayroを使用してPDFを画像化します...");
+/// 変換の設定を指定するフラグ（通常の変換と`batch`で共通）
+fn conversion_flags(lang: Lang) -> Vec<Flag> {
+    vec![
+        Flag::new("dpi", FlagType::String).description(tr(lang, "cli.flag.dpi")),
+        Flag::new("max-dpi", FlagType::Int).description(tr(lang, "cli.flag.max_dpi")),
+        Flag::new("max-width", FlagType::Int).description(tr(lang, "cli.flag.max_width")),
+        Flag::new("max-height", FlagType::Int).description(tr(lang, "cli.flag.max_height")),
+        Flag::new("max-megapixels", FlagType::Float)
+            .description(tr(lang, "cli.flag.max_megapixels")),
+        Flag::new("paper", FlagType::String).description(tr(lang, "cli.flag.paper")),
+        Flag::new("paper-fit", FlagType::String).description(tr(lang, "cli.flag.paper_fit")),
+        Flag::new("memory-limit", FlagType::Int).description(tr(lang, "cli.flag.memory_limit")),
+        Flag::new("limit-input-size", FlagType::Int)
+            .description(tr(lang, "cli.flag.limit_input_size")),
+        Flag::new("limit-pages", FlagType::Int).description(tr(lang, "cli.flag.limit_pages")),
+        Flag::new("limit-page-megapixels", FlagType::Float)
+            .description(tr(lang, "cli.flag.limit_page_megapixels")),
+        Flag::new("limit-total-megapixels", FlagType::Float)
+            .description(tr(lang, "cli.flag.limit_total_megapixels")),
+        Flag::new("limit-page-render-time", FlagType::Float)
+            .description(tr(lang, "cli.flag.limit_page_render_time")),
+        Flag::new("jobs", FlagType::Int)
+            .description(tr(lang, "cli.flag.jobs"))
+            .alias("j"),
+        Flag::new("lang", FlagType::String).description(tr(lang, "cli.flag.lang")),
+        Flag::new("quiet", FlagType::Bool)
+            .description(tr(lang, "cli.flag.quiet"))
+            .alias("q"),
+        Flag::new("report", FlagType::String).description(tr(lang, "cli.flag.report")),
+        Flag::new("report-file", FlagType::String).description(tr(lang, "cli.flag.report_file")),
+        Flag::new("profile", FlagType::String).description(tr(lang, "cli.flag.profile")),
+    ]
+}
+
+/// 設定ファイルとフラグから変換のオプションを作成する
+fn conversion_options(lang: Lang, c: &seahorse::Context) -> RasterizeOptions {
+    conversion_config(lang, c).options
+}
 
-    // PDFファイルを読み込み
-    let pdf_data = std::fs::read(input_path).with_context(|| {
-        format!(
-            "PDFファイルの読み込みに失敗しました: {}",
-            input_path.display()
+/// 設定ファイルを読み込み、`--profile`で選んだプロファイルとフラグで上書きする
+///
+/// 設定ファイル・`--dpi`・`--paper`・`--paper-fit`・MB単位のサイズの指定が正しくなければエラーを表示して終了する。
+fn conversion_config(lang: Lang, c: &seahorse::Context) -> cli_config::Config {
+    let profile = c.string_flag("profile").ok();
+    let config = cli_config::load(lang, profile.as_deref())
+        .unwrap_or_else(|e| exit_with_error(lang, &error_message(lang, &e)));
+    let options = apply_flags(lang, c, config.options);
+    cli_config::Config { options, ..config }
+}
+
+/// 指定されたフラグで`base`を上書きする（指定されていない項目は`base`のまま）
+fn apply_flags(lang: Lang, c: &seahorse::Context, base: RasterizeOptions) -> RasterizeOptions {
+    let max_dpi = c.int_flag("max-dpi").ok().map(|dpi| dpi.max(1) as u32);
+    let (dpi, auto_dpi) = match c.string_flag("dpi") {
+        Ok(value) if value == "auto" => {
+            let max_dpi = max_dpi.or(base.auto_dpi).unwrap_or(DEFAULT_MAX_AUTO_DPI);
+            (base.dpi, Some(max_dpi))
+        }
+        Ok(value) => match value.parse::<u32>() {
+            Ok(dpi) if dpi > 0 => (dpi, None),
+            _ => exit_with_error(lang, &trf(lang, "cli.invalid_dpi", &[&value])),
+        },
+        Err(_) => (base.dpi, base.auto_dpi.map(|auto| max_dpi.unwrap_or(auto))),
+    };
+    let paper = match c.string_flag("paper") {
+        Ok(value) => match value.to_ascii_lowercase().as_str() {
+            "a4" => Some(PaperSize::A4),
+            "letter" => Some(PaperSize::Letter),
+            _ => exit_with_error(lang, &trf(lang, "cli.invalid_paper", &[&value])),
+        },
+        Err(_) => base.paper,
+    };
+    let paper_fit = match c.string_flag("paper-fit") {
+        Ok(value) => match value.to_ascii_lowercase().as_str() {
+            "fit" => PaperFit::Fit,
+            "fill" => PaperFit::Fill,
+            _ => exit_with_error(lang, &trf(lang, "cli.invalid_paper_fit", &[&value])),
+        },
+        Err(_) => base.paper_fit,
+    };
+    // 0を指定した場合は設定ファイルの値も取り消して制限しない
+    let positive = |value: isize| u32::try_from(value).ok().filter(|&value| value > 0);
+    let pixels = |megapixels: f64| (megapixels > 0.0).then_some((megapixels * 1_000_000.0) as u64);
+    // MB単位の指定をバイトにする（0以下やバイト数が桁あふれする値はエラー）
+    let bytes = |flag: &str, mb: isize| {
+        u64::try_from(mb)
+            .ok()
+            .filter(|&mb| mb > 0)
+            .and_then(|mb| mb.checked_mul(1024 * 1024))
+            .unwrap_or_else(|| {
+                exit_with_error(lang, &trf(lang, "cli.invalid_megabytes", &[&flag, &mb]))
+            })
+    };
+    let limits = SafetyLimits {
+        max_input_bytes: match c.int_flag("limit-input-size") {
+            Ok(mb) => (mb > 0).then(|| bytes("limit-input-size", mb)),
+            Err(_) => base.limits.max_input_bytes,
+        },
+        max_pages: match c.int_flag("limit-pages") {
+            Ok(pages) => (pages > 0).then_some(pages as usize),
+            Err(_) => base.limits.max_pages,
+        },
+        max_page_pixels: match c.float_flag("limit-page-megapixels") {
+            Ok(megapixels) => pixels(megapixels),
+            Err(_) => base.limits.max_page_pixels,
+        },
+        max_total_pixels: match c.float_flag("limit-total-megapixels") {
+            Ok(megapixels) => pixels(megapixels),
+            Err(_) => base.limits.max_total_pixels,
+        },
+        max_page_render_ms: match c.float_flag("limit-page-render-time") {
+            Ok(seconds) => (seconds > 0.0).then_some((seconds * 1000.0).round() as u64),
+            Err(_) => base.limits.max_page_render_ms,
+        },
+    };
+
+    RasterizeOptions {
+        dpi,
+        auto_dpi,
+        max_width: c.int_flag("max-width").map_or(base.max_width, positive),
+        max_height: c.int_flag("max-height").map_or(base.max_height, positive),
+        max_megapixels: c
+            .float_flag("max-megapixels")
+            .map_or(base.max_megapixels, |megapixels| {
+                (megapixels > 0.0).then_some(megapixels as f32)
+            }),
+        paper,
+        paper_fit,
+        memory_limit: c
+            .int_flag("memory-limit")
+            .map_or(base.memory_limit, |mb| bytes("memory-limit", mb)),
+        threads: c
+            .int_flag("jobs")
+            .map_or(base.threads, |n| Some(n.max(0) as usize)),
+        limits,
+        ..base
+    }
+}
+
+/// 変換の設定を表示する
+fn print_settings(lang: Lang, options: &RasterizeOptions, threads: Option<usize>) {
+    match options.auto_dpi {
+        Some(max_dpi) => status!(
+            "{}",
+            trf(
+                lang,
+                "cli.dpi_auto",
+                &[&options.dpi.min(max_dpi), &max_dpi, &options.dpi]
+            )
+        ),
+        None => status!("{}", trf(lang, "cli.dpi", &[&options.dpi])),
+    }
+    let limits: Vec<String> = [
+        options
+            .max_width
+            .map(|width| trf(lang, "cli.max_width", &[&width])),
+        options
+            .max_height
+            .map(|height| trf(lang, "cli.max_height", &[&height])),
+        options
+            .max_megapixels
+            .map(|megapixels| trf(lang, "cli.max_megapixels", &[&megapixels])),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !limits.is_empty() {
+        status!("{}", trf(lang, "cli.max_size", &[&limits.join(", ")]));
+    }
+    if let Some(paper) = options.paper {
+        let paper = match paper {
+            PaperSize::A4 => "A4",
+            PaperSize::Letter => "Letter",
+        };
+        let fit = match options.paper_fit {
+            PaperFit::Fit => tr(lang, "cli.paper_fit.fit"),
+            PaperFit::Fill => tr(lang, "cli.paper_fit.fill"),
+        };
+        status!("{}", trf(lang, "cli.paper", &[&paper, &fit]));
+    }
+    let safety_limits: Vec<String> = [
+        options
+            .limits
+            .max_input_bytes
+            .map(|bytes| trf(lang, "cli.limit.input_size", &[&(bytes / 1024 / 1024)])),
+        options
+            .limits
+            .max_pages
+            .map(|pages| trf(lang, "cli.limit.pages", &[&pages])),
+        options
+            .limits
+            .max_page_pixels
+            .map(|pixels| trf(lang, "cli.limit.page_megapixels", &[&megapixels(pixels)])),
+        options
+            .limits
+            .max_total_pixels
+            .map(|pixels| trf(lang, "cli.limit.total_megapixels", &[&megapixels(pixels)])),
+        options
+            .limits
+            .max_page_render_ms
+            .map(|ms| trf(lang, "cli.limit.page_render_time", &[&(ms as f64 / 1000.0)])),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !safety_limits.is_empty() {
+        status!("{}", trf(lang, "cli.limits", &[&safety_limits.join(", ")]));
+    }
+    status!(
+        "{}",
+        trf(
+            lang,
+            "cli.memory_limit",
+            &[&(options.memory_limit / 1024 / 1024)]
         )
-    })?;
+    );
+    match threads {
+        Some(n) if n > 0 => status!("{}", trf(lang, "cli.threads", &[&n])),
+        _ => status!("{}", tr(lang, "cli.threads_auto")),
+    }
+}
+
+/// 画素数をメガピクセルで表す
+fn megapixels(pixels: u64) -> f64 {
+    pixels as f64 / 1_000_000.0
+}
 
-    let output_data = pdf_rasterizer::rasterize_pdf(pdf_data, dpi)?;
+/// `batch`サブコマンド
+fn batch_command(lang: Lang) -> Command {
+    let command = Command::new("batch")
+        .description(tr(lang, "cli.batch.description"))
+        .usage("pdf_rasterizer batch <dir-or-glob>... --out-dir <dir> [--force] [--dpi <value|auto>] [--max-dpi <value>] [--max-width <px>] [--max-height <px>] [--max-megapixels <MP>] [--paper <a4|letter>] [--paper-fit <fit|fill>] [--memory-limit <MB>] [--jobs <N>] [--lang <en|ja>] [--quiet] [--report json] [--report-file <path>] [--profile <name>]")
+        .flag(Flag::new("out-dir", FlagType::String).description(tr(lang, "cli.flag.out_dir")))
+        .flag(Flag::new("force", FlagType::Bool).description(tr(lang, "cli.flag.force")));
+    conversion_flags(lang)
+        .into_iter()
+        .fold(command, Command::flag)
+        .action(|c| {
+            run_batch(c);
+            Ok(())
+        })
+}
+
+/// ディレクトリやglobパターンに含まれるPDFをまとめて変換する
+///
+/// 1つでも失敗したファイルがあれば終了コード1で終了する。
+fn run_batch(c: &seahorse::Context) {
+    let lang = cli_lang().unwrap_or_default();
+    QUIET.store(c.bool_flag("quiet"), Ordering::Relaxed);
+    if c.args.is_empty() {
+        exit_with_error(lang, tr(lang, "cli.batch.missing_inputs"));
+    }
+    let Ok(out_dir) = c.string_flag("out-dir").map(PathBuf::from) else {
+        exit_with_error(lang, tr(lang, "cli.batch.missing_out_dir"));
+    };
+    let report_target =
+        cli_report::target(lang, c).unwrap_or_else(|message| exit_with_error(lang, &message));
+
+    // ファイルとページの両方をスレッドプールで並列に処理するため、
+    // `--jobs`はファイルごとではなくバッチ全体のスレッド数として使う
+    let options = conversion_options(lang, c);
+    let threads = options.threads;
+    let options = RasterizeOptions {
+        threads: None,
+        ..options
+    };
+
+    let jobs = batch::collect_jobs(lang, &c.args, &out_dir)
+        .unwrap_or_else(|e| exit_with_error(lang, &error_message(lang, &e)));
+    if jobs.is_empty() {
+        exit_with_error(lang, tr(lang, "cli.batch.no_files"));
+    }
 
-    println!("  PDFを保存しています...");
-    std::fs::write(output_path, output_data)
-        .context("PDFの保存に失敗しました")?;
+    status!("{}", trf(lang, "cli.batch.found", &[&jobs.len()]));
+    status!("{}", trf(lang, "cli.output", &[&out_dir.display()]));
+    print_settings(lang, &options, threads);
 
-    Ok(())
+    let partial_outputs = PartialOutputs::default();
+    if let Err(e) = install_interrupt_handler(lang, options.cancel.clone(), partial_outputs.clone())
+    {
+        exit_with_error(lang, &error_message(lang, &e));
+    }
+
+    let force = c.bool_flag("force");
+    let summary = batch::run(lang, &jobs, &options, threads, force, &partial_outputs)
+        .unwrap_or_else(|e| exit_with_error(lang, &error_message(lang, &e)));
+    batch::print_summary(lang, &summary);
+    if let Some(target) = &report_target {
+        let report = cli_report::BatchReport {
+            files: &summary.files,
+            converted: summary.converted,
+            skipped: summary.skipped,
+            failed: summary.failed.len(),
+        };
+        if let Err(e) = cli_report::write(lang, target, &report) {
+            eprintln!("{}", trf(lang, "cli.error", &[&error_message(lang, &e)]));
+        }
+    }
+
+    if summary.cancelled {
+        eprintln!("{}", tr(lang, "cli.cancelled"));
+        std::process::exit(130);
+    }
+    if !summary.failed.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// `watch`サブコマンド
+fn watch_command(lang: Lang) -> Command {
+    let command = Command::new("watch")
+        .description(tr(lang, "cli.watch.description"))
+        .usage("pdf_rasterizer watch <in-dir> --out-dir <dir> [--error-dir <dir>] [--interval <seconds>] [--settle <seconds>] [--profile <name>] [--dpi <value|auto>] [...]")
+        .flag(Flag::new("out-dir", FlagType::String).description(tr(lang, "cli.flag.watch_out_dir")))
+        .flag(Flag::new("error-dir", FlagType::String).description(tr(lang, "cli.flag.error_dir")))
+        .flag(Flag::new("interval", FlagType::Float).description(tr(lang, "cli.flag.interval")))
+        .flag(Flag::new("settle", FlagType::Float).description(tr(lang, "cli.flag.settle")));
+    // 変換結果の詳細はファイルごとに出力しないため、`--report`は受け付けない
+    conversion_flags(lang)
+        .into_iter()
+        .filter(|flag| !flag.name.starts_with("report"))
+        .fold(command, Command::flag)
+        .action(|c| {
+            run_watch(c);
+            Ok(())
+        })
+}
+
+/// フォルダーを監視し、置かれたPDFを変換する
+///
+/// Ctrl-Cで中断するまで終了しない。
+fn run_watch(c: &seahorse::Context) {
+    let lang = cli_lang().unwrap_or_default();
+    QUIET.store(c.bool_flag("quiet"), Ordering::Relaxed);
+    let Some(in_dir) = c.args.first().map(PathBuf::from) else {
+        exit_with_error(lang, tr(lang, "cli.watch.missing_input"));
+    };
+    if !in_dir.is_dir() {
+        exit_with_error(lang, &trf(lang, "cli.watch.not_dir", &[&in_dir.display()]));
+    }
+    let Ok(out_dir) = c.string_flag("out-dir").map(PathBuf::from) else {
+        exit_with_error(lang, tr(lang, "cli.batch.missing_out_dir"));
+    };
+    let error_dir = c
+        .string_flag("error-dir")
+        .map_or_else(|_| out_dir.join(WATCH_ERROR_DIR), PathBuf::from);
+    // 出力やエラーフォルダーへ移したPDFを入力として拾わないようにする
+    let in_dir_absolute = std::path::absolute(&in_dir).unwrap_or_else(|_| in_dir.clone());
+    for dir in [&out_dir, &error_dir] {
+        if std::path::absolute(dir).is_ok_and(|dir| dir == in_dir_absolute) {
+            exit_with_error(lang, &trf(lang, "cli.watch.same_dir", &[&dir.display()]));
+        }
+    }
+    let seconds = |name: &str, default: f64| {
+        let seconds = c.float_flag(name).unwrap_or(default);
+        Duration::try_from_secs_f64(seconds).unwrap_or_else(|_| {
+            exit_with_error(
+                lang,
+                &trf(lang, "cli.watch.invalid_seconds", &[&name, &seconds]),
+            )
+        })
+    };
+    let interval = seconds("interval", 2.0);
+    if interval < watch::MIN_INTERVAL {
+        exit_with_error(
+            lang,
+            &trf(
+                lang,
+                "cli.watch.interval_too_short",
+                &[&interval.as_secs_f64(), &watch::MIN_INTERVAL.as_secs_f64()],
+            ),
+        );
+    }
+    let settings = watch::WatchSettings {
+        in_dir,
+        out_dir,
+        error_dir,
+        interval,
+        settle: seconds("settle", 3.0),
+    };
+
+    let options = conversion_options(lang, c);
+    watch::print_start(lang, &settings);
+    print_settings(lang, &options, options.threads);
+
+    let partial_outputs = PartialOutputs::default();
+    if let Err(e) = install_interrupt_handler(lang, options.cancel.clone(), partial_outputs.clone())
+    {
+        exit_with_error(lang, &error_message(lang, &e));
+    }
+    if let Err(e) = watch::run(lang, &settings, &options, &partial_outputs) {
+        exit_with_error(lang, &error_message(lang, &e));
+    }
+    status!("{}", tr(lang, "cli.watch.stopped"));
+}
+
+/// `info`サブコマンド
+fn info_command(lang: Lang) -> Command {
+    Command::new("info")
+        .description(tr(lang, "cli.info.description"))
+        .usage("pdf_rasterizer info <input|-> [--json] [--lang <en|ja>]")
+        .flag(Flag::new("json", FlagType::Bool).description(tr(lang, "cli.flag.json")))
+        .flag(Flag::new("lang", FlagType::String).description(tr(lang, "cli.flag.lang")))
+        .action(|c| {
+            let lang = cli_lang().unwrap_or_default();
+            let Some(input) = c.args.first() else {
+                exit_with_error(lang, tr(lang, "cli.info.missing_input"));
+            };
+            if let Err(e) = cli_info::run(lang, Path::new(input), c.bool_flag("json")) {
+                exit_with_error(lang, &error_message(lang, &e));
+            }
+            Ok(())
+        })
+}
+
+/// `config`サブコマンド
+fn config_command(lang: Lang) -> Command {
+    let command = Command::new("config")
+        .description(tr(lang, "cli.config.description"))
+        .usage("pdf_rasterizer config show [--profile <name>] [--dpi <value|auto>] [...]");
+    conversion_flags(lang)
+        .into_iter()
+        .fold(command, Command::flag)
+        .action(|c| {
+            let lang = cli_lang().unwrap_or_default();
+            match c.args.first().map(String::as_str) {
+                Some("show") => {}
+                Some(action) => {
+                    exit_with_error(lang, &trf(lang, "cli.config.unknown_action", &[&action]))
+                }
+                None => exit_with_error(lang, tr(lang, "cli.config.missing_action")),
+            }
+            if let Err(e) = cli_config::show(lang, &conversion_config(lang, c)) {
+                exit_with_error(lang, &error_message(lang, &e));
+            }
+            Ok(())
+        })
+}
+
+/// エラーを表示して終了コード1で終了する
+fn exit_with_error(lang: Lang, message: &str) -> ! {
+    eprintln!("{}", trf(lang, "cli.error", &[&message]));
+    std::process::exit(1);
+}
+
+/// `--lang`、指定がなければ環境変数から表示言語を決める
+///
+/// 対応していない言語が指定された場合は指定された値を`Err`で返す。
+fn cli_lang() -> Result<Lang, String> {
+    let args: Vec<String> = env::args().collect();
+    match requested_lang(&args) {
+        Some(code) => Lang::from_locale(&code).ok_or(code),
+        None => Ok(Lang::from_env()),
+    }
+}
+
+/// `--lang <code>`または`--lang=<code>`で指定された言語
+fn requested_lang(args: &[String]) -> Option<String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--lang" {
+            return args.next().cloned();
+        }
+        if let Some(code) = arg.strip_prefix("--lang=") {
+            return Some(code.to_string());
+        }
+    }
+    None
+}
+
+fn process_pdf(
+    lang: Lang,
+    input_path: &Path,
+    output_path: &Path,
+    options: &RasterizeOptions,
+) -> Result<RasterizeReport> {
+    status!("{}", tr(lang, "cli.rasterizing"));
+
+    // ファイルの入力はメモリマップし、画像化したページから順に出力へ書き出す。
+    // 標準入力はPDFの末尾から読む必要があるため、すべて読み込んでから変換する。
+    match (is_stdio(input_path), is_stdio(output_path)) {
+        (false, false) => pdf_rasterizer::rasterize_file(input_path, output_path, options),
+        (false, true) => {
+            let (stdout, report) =
+                pdf_rasterizer::rasterize_file_to_writer(input_path, stdout(), options)?;
+            finish_stdout(lang, stdout)?;
+            Ok(report)
+        }
+        (true, false) => pdf_rasterizer::rasterize_to_file(
+            read_stdin(lang, options.limits.max_input_bytes)?,
+            output_path,
+            options,
+        ),
+        (true, true) => {
+            let (stdout, report) = pdf_rasterizer::rasterize_to_writer_with_report(
+                read_stdin(lang, options.limits.max_input_bytes)?,
+                stdout(),
+                options,
+            )?;
+            finish_stdout(lang, stdout)?;
+            Ok(report)
+        }
+    }
+}
+
+/// パスが標準入出力を表す`-`かどうか
+fn is_stdio(path: &Path) -> bool {
+    path == Path::new(STDIO_PATH)
+}
+
+/// 表示用のパス（`-`は標準入力・標準出力と表示する）
+fn display_path(lang: Lang, path: &Path, stdio_key: &'static str) -> String {
+    if is_stdio(path) {
+        tr(lang, stdio_key).to_string()
+    } else {
+        path.display().to_string()
+    }
+}
+
+/// 変換全体に関する警告を表示する
+fn print_warnings(lang: Lang, input: &Path, report: &RasterizeReport) {
+    for warning in &report.warnings {
+        if let Warning::ImageAnalysisFailed { reason } = warning {
+            eprintln!(
+                "{}",
+                trf(
+                    lang,
+                    "cli.warning.image_analysis_failed",
+                    &[&display_path(lang, input, "cli.stdin"), &report.dpi, reason]
+                )
+            );
+        }
+    }
+}
+
+/// 標準入力を読み込む
+///
+/// `max_bytes`を指定した場合は、上限を超えたことが分かる分だけ読み込む（判定は変換時に行う）。
+fn read_stdin(lang: Lang, max_bytes: Option<u64>) -> Result<Vec<u8>> {
+    let mut pdf_data = Vec::new();
+    std::io::stdin()
+        .lock()
+        .take(max_bytes.map_or(u64::MAX, |bytes| bytes.saturating_add(1)))
+        .read_to_end(&mut pdf_data)
+        .context(tr(lang, "cli.read_stdin"))?;
+    Ok(pdf_data)
+}
+
+fn stdout() -> BufWriter<StdoutLock<'static>> {
+    BufWriter::new(std::io::stdout().lock())
+}
+
+/// 標準出力へ書き出した内容をフラッシュする
+fn finish_stdout(lang: Lang, stdout: BufWriter<StdoutLock<'static>>) -> Result<()> {
+    stdout
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .and_then(|mut stdout| stdout.flush())
+        .context(tr(lang, "cli.write_stdout"))
+}
+
+/// 進捗バーの幅（文字数）
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// 標準エラー出力に進捗バーを表示するコールバックを作成する
+///
+/// 標準エラー出力が端末でなければ、ログやリダイレクト先を`\r`の更新で埋めないよう進捗バーは表示せず、
+/// 保存したサイズだけを表示する。
+fn progress_bar(lang: Lang) -> ProgressCallback {
+    let draw = std::io::stderr().is_terminal();
+    let total = AtomicUsize::new(0);
+    let rendered = AtomicUsize::new(0);
+    let written = AtomicUsize::new(0);
+    // 複数のスレッドから同時に描画して表示が崩れないようにする
+    let draw_lock = Mutex::new(());
+
+    ProgressCallback::new(move |event| {
+        match event {
+            ProgressEvent::Parsed { pages } => {
+                total.store(pages, Ordering::SeqCst);
+                return;
+            }
+            ProgressEvent::PageRendered { .. } => {
+                rendered.fetch_add(1, Ordering::SeqCst);
+            }
+            ProgressEvent::Assembling { done, .. } => {
+                written.store(done, Ordering::SeqCst);
+            }
+            ProgressEvent::Saved { bytes } => {
+                // 進捗バーの行を確定させる
+                if draw {
+                    eprintln!();
+                }
+                let megabytes = format!("{:.1}", bytes as f64 / 1024.0 / 1024.0);
+                status!("{}", trf(lang, "cli.saved", &[&megabytes]));
+                return;
+            }
+        }
+        if !draw {
+            return;
+        }
+
+        let total = total.load(Ordering::SeqCst).max(1);
+        let rendered = rendered.load(Ordering::SeqCst);
+        let written = written.load(Ordering::SeqCst);
+        let filled = PROGRESS_BAR_WIDTH * written / total;
+
+        let _guard = draw_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(
+            stderr,
+            "\r  [{}{}] {}",
+            "#".repeat(filled),
+            "-".repeat(PROGRESS_BAR_WIDTH - filled),
+            trf(lang, "cli.progress", &[&rendered, &total, &written, &total])
+        );
+        let _ = stderr.flush();
+    })
+}
+
+/// Ctrl-Cで変換を中断できるようにする
+///
+/// 1回目の割り込みでは処理中のページが終わるのを待って中断し、
+/// 2回目の割り込みでは書きかけの一時ファイルを削除して即座に終了する（既存の出力ファイルは残る）。
+fn install_interrupt_handler(
+    lang: Lang,
+    cancel: CancellationToken,
+    partial_outputs: PartialOutputs,
+) -> Result<()> {
+    let mut interrupted = false;
+    ctrlc::set_handler(move || {
+        if interrupted {
+            let outputs = partial_outputs.lock().unwrap_or_else(|e| e.into_inner());
+            for output in outputs.iter() {
+                let _ = std::fs::remove_file(pdf_rasterizer::partial_output_path(output));
+            }
+            std::process::exit(130);
+        }
+        interrupted = true;
+        eprintln!("\n{}", tr(lang, "cli.interrupting"));
+        cancel.cancel();
+    })
+    .context(tr(lang, "cli.interrupt_handler"))
 }
@@ -0,0 +1,303 @@
+use crate::{
+    CancellationToken, ColorSpace, Error, ImageEncoding, PaperFit, PaperSize, ProgressCallback,
+    ProgressEvent, SafetyLimits, Warning, DEFAULT_JPEG_QUALITY,
+};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// デフォルトのメモリ上限（512MiB）
+pub const DEFAULT_MEMORY_LIMIT: u64 = 512 * 1024 * 1024;
+
+/// DPIを自動で決めるときのデフォルトの上限
+pub const DEFAULT_MAX_AUTO_DPI: u32 = 300;
+
+/// ラスタライズ処理のオプション
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RasterizeOptions {
+    /// ラスタライズ時のDPI（解像度）
+    ///
+    /// `auto_dpi`を設定した場合は、画像を含まないページのDPIと、画像から決めるDPIの下限として使う。
+    pub dpi: u32,
+    /// ページごとにDPIを自動で決める場合の上限
+    ///
+    /// ページに描画される画像の解像度（ピクセル数と描画される大きさの比）のうち
+    /// 最も高いものを、`dpi`以上でこの上限を超えない範囲でそのページのDPIにする。
+    /// 低解像度のロゴなどを含むページの文字が`dpi`より粗くならないよう、`dpi`を下限にする。
+    /// 元の画像より高い解像度にはしないため、スキャンしたPDFの細部を保ちつつ無駄に大きくならない。
+    /// `Document`のプレビューでは使わない（最大サイズ・用紙サイズも同様）。
+    pub auto_dpi: Option<u32>,
+    /// レンダリングする画像の最大幅（ピクセル）
+    ///
+    /// 最大幅・最大高さ・最大画素数のいずれかを超えるページは、縦横比を保ったまま
+    /// 解像度を下げてレンダリングする（出力PDFのページの大きさは変わらない）。
+    pub max_width: Option<u32>,
+    /// レンダリングする画像の最大高さ（ピクセル）
+    pub max_height: Option<u32>,
+    /// レンダリングする画像の最大画素数（メガピクセル）
+    pub max_megapixels: Option<f32>,
+    /// 出力PDFのページを揃える用紙サイズ（`None`の場合は元のページの大きさ）
+    ///
+    /// 用紙は元のページと同じ向き（縦・横）にし、`dpi`は用紙上の解像度になる。
+    pub paper: Option<PaperSize>,
+    /// 用紙サイズに揃えるときの配置方法
+    pub paper_fit: PaperFit,
+    /// JPEG品質（1〜100、`encoding`がJPEGの場合のみ使用）
+    pub quality: u8,
+    /// 出力PDFに埋め込む画像の色空間
+    pub color_space: ColorSpace,
+    /// 出力PDFに埋め込む画像の圧縮方式
+    pub encoding: ImageEncoding,
+    /// 同時にレンダリングするページが使用するメモリのおおよその上限（バイト）
+    ///
+    /// 上限を超える場合でも最低1ページはレンダリングする。
+    pub memory_limit: u64,
+    /// ページのレンダリングに使用するスレッド数
+    ///
+    /// `None`または`0`の場合はrayonのグローバルスレッドプールを使用し、
+    /// `1`の場合は並列処理を行わず呼び出し元のスレッドで順番に処理する。
+    /// 2以上の場合は変換ごとに専用のスレッドプールを作成する。
+    pub threads: Option<usize>,
+    /// 変換するページの番号（0始まり）
+    ///
+    /// `None`の場合はすべてのページを変換する。番号は文書内の順に並べ替えられ、
+    /// 重複は取り除かれる。
+    pub pages: Option<Vec<usize>>,
+    /// 信頼できない入力PDFに対する上限（ページの大きさ・ページ数・入力サイズ・時間）
+    pub limits: SafetyLimits,
+    /// 変換を中断するためのトークン（設定としては保存されない）
+    #[serde(skip)]
+    pub cancel: CancellationToken,
+    /// 進捗を受け取るコールバック（設定としては保存されない）
+    #[serde(skip)]
+    pub progress: Option<ProgressCallback>,
+}
+
+impl Default for RasterizeOptions {
+    fn default() -> Self {
+        Self {
+            dpi: 72,
+            auto_dpi: None,
+            max_width: None,
+            max_height: None,
+            max_megapixels: None,
+            paper: None,
+            paper_fit: PaperFit::default(),
+            quality: DEFAULT_JPEG_QUALITY,
+            color_space: ColorSpace::default(),
+            encoding: ImageEncoding::default(),
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+            threads: None,
+            pages: None,
+            limits: SafetyLimits::default(),
+            cancel: CancellationToken::new(),
+            progress: None,
+        }
+    }
+}
+
+impl RasterizeOptions {
+    /// 変換するページの番号を文書内の順に返す
+    pub(crate) fn selected_pages(&self, page_count: usize) -> Result<Vec<usize>> {
+        let Some(pages) = &self.pages else {
+            return Ok((0..page_count).collect());
+        };
+
+        let mut selected = pages.clone();
+        selected.sort_unstable();
+        selected.dedup();
+
+        if let Some(&index) = selected.iter().find(|&&index| index >= page_count) {
+            bail!(Error::PageOutOfRange {
+                page: index + 1,
+                total: page_count,
+            });
+        }
+        if selected.is_empty() {
+            bail!(Error::NoPagesSelected);
+        }
+
+        Ok(selected)
+    }
+
+    /// 進捗コールバックが設定されていれば進捗を通知する
+    pub(crate) fn emit(&self, event: ProgressEvent) {
+        if let Some(progress) = &self.progress {
+            progress.emit(event);
+        }
+    }
+}
+
+/// ページごとのDPI
+#[derive(Debug, Clone)]
+pub(crate) struct PageDpis {
+    /// 自動で決めなかったページのDPI
+    fixed: u32,
+    /// 画像の解像度から決めたDPI（入力PDFのページ順）
+    auto: Vec<Option<u32>>,
+    /// 画像を調べられなかった場合の警告
+    warning: Option<Warning>,
+}
+
+impl PageDpis {
+    /// `auto_dpi`が設定されていれば入力PDFの画像を調べてページごとのDPIを決める
+    ///
+    /// 画像から決めるDPIは`dpi`以上`auto_dpi`以下にする。
+    /// 画像を調べられなかったページは`dpi`を使い、文書を解析できなかった場合は警告を残す。
+    /// 入力PDFをもう1度読み込むため、入力サイズとページ数の上限を確認してから呼ぶ。
+    pub fn new(options: &RasterizeOptions, pdf_data: &[u8]) -> Self {
+        let Some(max_dpi) = options.auto_dpi else {
+            return Self {
+                fixed: options.dpi,
+                auto: Vec::new(),
+                warning: None,
+            };
+        };
+
+        let max_dpi = max_dpi.max(1);
+        let min_dpi = options.dpi.clamp(1, max_dpi);
+        match crate::inspect::page_image_dpis(pdf_data) {
+            Ok(dpis) => Self {
+                fixed: options.dpi,
+                auto: dpis
+                    .into_iter()
+                    .map(|dpi| dpi.map(|dpi| (dpi.round() as u32).clamp(min_dpi, max_dpi)))
+                    .collect(),
+                warning: None,
+            },
+            Err(e) => Self {
+                fixed: options.dpi,
+                auto: Vec::new(),
+                warning: Some(Warning::ImageAnalysisFailed {
+                    reason: format!("{:#}", e),
+                }),
+            },
+        }
+    }
+
+    /// 入力PDFのページ（0始まり）のDPI
+    pub fn get(&self, index: usize) -> u32 {
+        self.auto
+            .get(index)
+            .copied()
+            .flatten()
+            .unwrap_or(self.fixed)
+    }
+
+    /// 変換全体に関する警告
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.warning.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{dictionary, Document, Object, Stream};
+
+    /// 1ページに1つずつ、指定した解像度（DPI）で画像を描画するPDF
+    fn pdf_with_images(dpis: &[Option<u32>]) -> Vec<u8> {
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+        let image = doc.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 720,
+                "Height" => 720,
+                "BitsPerComponent" => 8,
+                "ColorSpace" => "DeviceGray",
+            },
+            Vec::new(),
+        ));
+        let kids: Vec<Object> = dpis
+            .iter()
+            .map(|dpi| {
+                // 720ピクセルを720 * 72 / dpiポイントに描画する
+                let operators = match dpi {
+                    Some(dpi) => format!("q {0} 0 0 {0} 0 0 cm /Im0 Do Q", 720 * 72 / dpi),
+                    None => String::new(),
+                };
+                let contents = doc.add_object(Stream::new(dictionary! {}, operators.into_bytes()));
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                    "Contents" => contents,
+                    "Resources" => dictionary! { "XObject" => dictionary! { "Im0" => image } },
+                })
+                .into()
+            })
+            .collect();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => kids.len() as i64,
+                "Kids" => kids,
+            }),
+        );
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog);
+        let mut data = Vec::new();
+        doc.save_to(&mut data).unwrap();
+        data
+    }
+
+    fn page_dpis(dpis: &PageDpis, pages: usize) -> Vec<u32> {
+        (0..pages).map(|index| dpis.get(index)).collect()
+    }
+
+    #[test]
+    fn fixed_dpi_without_auto_dpi() {
+        let options = RasterizeOptions {
+            dpi: 100,
+            ..Default::default()
+        };
+        // 自動で決めない場合は入力PDFを調べない
+        let dpis = PageDpis::new(&options, b"not a pdf");
+        assert_eq!(page_dpis(&dpis, 2), vec![100, 100]);
+        assert!(dpis.warnings().is_empty());
+    }
+
+    #[test]
+    fn auto_dpi_is_floored_at_dpi_and_capped_at_auto_dpi() {
+        let options = RasterizeOptions {
+            dpi: 96,
+            auto_dpi: Some(300),
+            ..Default::default()
+        };
+        let data = pdf_with_images(&[Some(72), Some(150), Some(600), None]);
+        let dpis = PageDpis::new(&options, &data);
+        // 画像のないページと範囲外のページは`dpi`
+        assert_eq!(page_dpis(&dpis, 5), vec![96, 150, 300, 96, 96]);
+        assert!(dpis.warnings().is_empty());
+    }
+
+    #[test]
+    fn dpi_above_auto_dpi_is_lowered_to_auto_dpi() {
+        let options = RasterizeOptions {
+            dpi: 400,
+            auto_dpi: Some(200),
+            ..Default::default()
+        };
+        let data = pdf_with_images(&[Some(72), Some(150), None]);
+        let dpis = PageDpis::new(&options, &data);
+        assert_eq!(page_dpis(&dpis, 3), vec![200, 200, 400]);
+    }
+
+    #[test]
+    fn analysis_failure_falls_back_to_dpi_with_a_warning() {
+        let options = RasterizeOptions {
+            dpi: 120,
+            auto_dpi: Some(300),
+            ..Default::default()
+        };
+        let dpis = PageDpis::new(&options, b"not a pdf");
+        assert_eq!(page_dpis(&dpis, 2), vec![120, 120]);
+        assert!(matches!(
+            dpis.warnings().as_slice(),
+            [Warning::ImageAnalysisFailed { reason }] if !reason.is_empty()
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::{bail, Context, Result};
+
+use crate::Error;
+
+/// ページ範囲に指定できる最大のページ番号
+///
+/// 巨大な範囲を展開してメモリを使い果たさないための上限。これを超えるページを持つPDFは扱えない。
+pub const MAX_PAGE_NUMBER: usize = 1_000_000;
+
+/// 「1-3,5」のようなページ範囲の指定を0始まりのページ番号に変換する
+///
+/// ページ番号は1始まりで指定し、範囲は両端を含む。結果は昇順に並べ、重複を取り除く。
+/// ページ番号が`MAX_PAGE_NUMBER`を超える指定はエラーにする。
+pub fn parse_page_ranges(spec: &str) -> Result<Vec<usize>> {
+    let mut ranges = Vec::new();
+
+    for part in spec
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (parse_page_number(start)?, parse_page_number(end)?),
+            None => {
+                let page = parse_page_number(part)?;
+                (page, page)
+            }
+        };
+        if start > end {
+            bail!(Error::ReversedPageRange {
+                range: part.to_string(),
+            });
+        }
+        ranges.push((start, end));
+    }
+
+    // 範囲が重なっても使うメモリが最大のページ番号で決まるよう、ページごとの印で重複を取り除く
+    let Some(last) = ranges.iter().map(|&(_, end)| end).max() else {
+        bail!(Error::EmptyPageRange);
+    };
+    let mut selected = vec![false; last];
+    for (start, end) in ranges {
+        selected[start - 1..end].fill(true);
+    }
+    Ok(selected
+        .iter()
+        .enumerate()
+        .filter_map(|(index, &selected)| selected.then_some(index))
+        .collect())
+}
+
+/// 0始まりのページ番号を「1-3,5」のようなページ範囲の指定に変換する
+pub fn format_page_ranges(pages: &[usize]) -> String {
+    let mut pages = pages.to_vec();
+    pages.sort_unstable();
+    pages.dedup();
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for page in pages {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == page => *end = page,
+            _ => ranges.push((page, page)),
+        }
+    }
+
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                (start + 1).to_string()
+            } else {
+                format!("{}-{}", start + 1, end + 1)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_page_number(value: &str) -> Result<usize> {
+    let page: usize = value
+        .trim()
+        .parse()
+        .with_context(|| Error::InvalidPageNumber {
+            value: value.trim().to_string(),
+        })?;
+    if page == 0 {
+        bail!(Error::PageNumberZero);
+    }
+    if page > MAX_PAGE_NUMBER {
+        bail!(Error::PageNumberTooLarge {
+            page,
+            max: MAX_PAGE_NUMBER,
+        });
+    }
+    Ok(page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(spec: &str) -> Error {
+        parse_page_ranges(spec)
+            .unwrap_err()
+            .downcast::<Error>()
+            .expect("library error")
+    }
+
+    #[test]
+    fn parses_single_pages_and_ranges() {
+        assert_eq!(parse_page_ranges("1-3,5").unwrap(), vec![0, 1, 2, 4]);
+        assert_eq!(parse_page_ranges(" 2 , 4 - 5 ").unwrap(), vec![1, 3, 4]);
+        assert_eq!(parse_page_ranges("7-7").unwrap(), vec![6]);
+    }
+
+    #[test]
+    fn sorts_and_removes_duplicates() {
+        assert_eq!(
+            parse_page_ranges("5,1-3,2,3-4").unwrap(),
+            vec![0, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn ignores_empty_parts() {
+        assert_eq!(parse_page_ranges(",1,,3,").unwrap(), vec![0, 2]);
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        assert_eq!(error(""), Error::EmptyPageRange);
+        assert_eq!(error(" , "), Error::EmptyPageRange);
+        assert_eq!(error("0"), Error::PageNumberZero);
+        assert_eq!(error("0-2"), Error::PageNumberZero);
+        assert_eq!(
+            error("3-1"),
+            Error::ReversedPageRange {
+                range: "3-1".to_string(),
+            }
+        );
+        assert_eq!(
+            error("1,a"),
+            Error::InvalidPageNumber {
+                value: "a".to_string(),
+            }
+        );
+        assert_eq!(
+            error("1-"),
+            Error::InvalidPageNumber {
+                value: String::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_page_numbers_above_the_maximum() {
+        let too_large = Error::PageNumberTooLarge {
+            page: MAX_PAGE_NUMBER + 1,
+            max: MAX_PAGE_NUMBER,
+        };
+        assert_eq!(error(&format!("1-{}", MAX_PAGE_NUMBER + 1)), too_large);
+        assert_eq!(error(&(MAX_PAGE_NUMBER + 1).to_string()), too_large);
+        assert_eq!(
+            error("1-99999999999"),
+            Error::PageNumberTooLarge {
+                page: 99_999_999_999,
+                max: MAX_PAGE_NUMBER,
+            }
+        );
+        // 数値として大きすぎる指定はページ番号として解釈できない
+        assert_eq!(
+            error("1-99999999999999999999999"),
+            Error::InvalidPageNumber {
+                value: "99999999999999999999999".to_string(),
+            }
+        );
+
+        let pages = parse_page_ranges(&format!("1-{0},1-{0}", MAX_PAGE_NUMBER)).unwrap();
+        assert_eq!(pages.len(), MAX_PAGE_NUMBER);
+        assert_eq!(pages.last(), Some(&(MAX_PAGE_NUMBER - 1)));
+    }
+
+    #[test]
+    fn formats_consecutive_pages_as_ranges() {
+        assert_eq!(format_page_ranges(&[0, 1, 2, 4]), "1-3,5");
+        assert_eq!(format_page_ranges(&[4, 0, 2, 1, 1]), "1-3,5");
+        assert_eq!(format_page_ranges(&[9]), "10");
+        assert_eq!(format_page_ranges(&[]), "");
+    }
+
+    #[test]
+    fn formatted_ranges_parse_back() {
+        let pages = vec![0, 2, 3, 4, 8, 10, 11];
+        assert_eq!(
+            parse_page_ranges(&format_page_ranges(&pages)).unwrap(),
+            pages
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// 変換処理の進捗
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProgressEvent {
+    /// PDFのパースが完了した
+    Parsed { pages: usize },
+    /// ページの画像化が完了した（`index`は0始まり、`bytes`はエンコード後のサイズ）
+    PageRendered { index: usize, bytes: usize },
+    /// 出力PDFへページを書き出した
+    Assembling { done: usize, total: usize },
+    /// 出力PDFの保存が完了した
+    Saved { bytes: u64 },
+}
+
+/// 進捗を受け取るコールバック
+///
+/// 並列処理時は複数のスレッドから呼び出されるため、`PageRendered`は
+/// ページ順に届くとは限らない。
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(ProgressEvent) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(ProgressEvent) + Send + Sync + 'static,
+    {
+        Self(Arc::new(callback))
+    }
+
+    pub(crate) fn emit(&self, event: ProgressEvent) {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
@@ -0,0 +1,137 @@
+//! Service Workerの登録と更新の検出
+//!
+//! Service Worker（`pwa/sw.js`）はビルドしたファイルをすべてキャッシュし、
+//! キャッシュから起動する。新しいバージョンは裏でインストールされて待機し、
+//! 利用者が更新を選んだときに有効化してページを再読み込みする。
+
+use std::rc::Rc;
+
+use gloo_timers::callback::Interval;
+use js_sys::Reflect;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{ServiceWorkerContainer, ServiceWorkerRegistration, ServiceWorkerState};
+
+/// Trunkがビルドに含めるService Workerのスクリプト
+pub const SERVICE_WORKER_URL: &str = "./sw.js";
+
+/// 待機中のService Workerを有効にするメッセージ（`pwa/sw.js`と合わせる）
+const SKIP_WAITING: &str = "skipWaiting";
+
+/// 開いたままのページで更新を確認する間隔（1時間）
+const UPDATE_CHECK_INTERVAL_MS: u32 = 60 * 60 * 1000;
+
+/// Service Workerの状態の変化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PwaEvent {
+    /// キャッシュが完了し、オフラインで利用できるようになった
+    OfflineReady,
+    /// 新しいバージョンがインストールされ、有効化を待っている
+    UpdateReady,
+}
+
+/// Service Workerを登録し、状態の変化を`on_event`で受け取る
+///
+/// Service Workerに対応していないブラウザや安全でないオリジンでは何もしない。
+pub fn register<F>(on_event: F)
+where
+    F: Fn(PwaEvent) + 'static,
+{
+    let Some(container) = container() else {
+        return;
+    };
+    let on_event = Rc::new(on_event);
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let registration: ServiceWorkerRegistration =
+            match JsFuture::from(container.register(SERVICE_WORKER_URL)).await {
+                Ok(registration) => registration.unchecked_into(),
+                Err(e) => {
+                    gloo_console::error!("failed to register the service worker", e);
+                    return;
+                }
+            };
+
+        if registration.active().is_some() {
+            on_event(PwaEvent::OfflineReady);
+        }
+        // 前回開いたときにインストールされた新しいバージョン
+        if registration.waiting().is_some() && container.controller().is_some() {
+            on_event(PwaEvent::UpdateReady);
+        }
+
+        let onupdatefound = Closure::<dyn FnMut()>::new({
+            let registration = registration.clone();
+            move || {
+                let Some(installing) = registration.installing() else {
+                    return;
+                };
+                let onstatechange = Closure::<dyn FnMut()>::new({
+                    let installing = installing.clone();
+                    let container = container.clone();
+                    let on_event = on_event.clone();
+                    move || {
+                        if installing.state() != ServiceWorkerState::Installed {
+                            return;
+                        }
+                        // 既存のService Workerがなければ初回のインストール
+                        if container.controller().is_some() {
+                            on_event(PwaEvent::UpdateReady);
+                        } else {
+                            on_event(PwaEvent::OfflineReady);
+                        }
+                    }
+                });
+                installing.set_onstatechange(Some(onstatechange.as_ref().unchecked_ref()));
+                onstatechange.forget();
+            }
+        });
+        registration.set_onupdatefound(Some(onupdatefound.as_ref().unchecked_ref()));
+        onupdatefound.forget();
+
+        Interval::new(UPDATE_CHECK_INTERVAL_MS, move || {
+            let _ = registration.update();
+        })
+        .forget();
+    });
+}
+
+/// 待機中の新しいバージョンを有効にし、切り替わったらページを再読み込みする
+pub fn apply_update() {
+    let Some(container) = container() else {
+        return;
+    };
+
+    let reload = Closure::once_into_js(|| {
+        if let Some(window) = web_sys::window() {
+            let _ = window.location().reload();
+        }
+    });
+    container.set_oncontrollerchange(Some(reload.unchecked_ref()));
+
+    wasm_bindgen_futures::spawn_local(async move {
+        let Ok(registration) = JsFuture::from(container.get_registration()).await else {
+            return;
+        };
+        let Some(waiting) = registration
+            .dyn_into::<ServiceWorkerRegistration>()
+            .ok()
+            .and_then(|registration| registration.waiting())
+        else {
+            return;
+        };
+        if let Err(e) = waiting.post_message(&JsValue::from_str(SKIP_WAITING)) {
+            gloo_console::error!("failed to activate the new service worker", e);
+        }
+    });
+}
+
+/// Service Workerに対応していれば`navigator.serviceWorker`を返す
+fn container() -> Option<ServiceWorkerContainer> {
+    let navigator = web_sys::window()?.navigator();
+    Reflect::has(&navigator, &"serviceWorker".into())
+        .ok()
+        .filter(|&supported| supported)
+        .map(|_| navigator.service_worker())
+}
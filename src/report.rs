@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+use crate::{ColorSpace, ImageEncoding};
+
+/// 変換結果の詳細
+///
+/// 時間はミリ秒。wasm32ではJavaScriptの`Date.now()`で測るため、精度は1ミリ秒になる。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RasterizeReport {
+    /// 入力PDFのサイズ（バイト）
+    pub input_bytes: u64,
+    /// 出力PDFのサイズ（バイト）
+    pub output_bytes: u64,
+    /// 入力PDFのページ数
+    pub source_pages: usize,
+    /// 固定のDPI（`auto_dpi`が設定されている場合は画像を含まないページのDPIと自動で決めるDPIの下限）
+    pub dpi: u32,
+    /// ページごとにDPIを自動で決めた場合の上限
+    pub auto_dpi: Option<u32>,
+    pub encoding: ImageEncoding,
+    pub color_space: ColorSpace,
+    /// JPEG品質（Deflateの場合は`None`）
+    pub quality: Option<u8>,
+    /// 変換したページ（出力PDFの順）
+    pub pages: Vec<PageReport>,
+    /// 特定のページによらない警告
+    pub warnings: Vec<Warning>,
+    pub timings: StageTimings,
+}
+
+/// 変換した1ページの詳細
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageReport {
+    /// 入力PDFでのページ番号（0始まり）
+    pub source_index: usize,
+    /// レンダリングしたDPI
+    pub dpi: u32,
+    /// 画像の幅（ピクセル）
+    pub width: u32,
+    /// 画像の高さ（ピクセル）
+    pub height: u32,
+    /// 出力PDFに埋め込んだ画像のサイズ（バイト）
+    pub encoded_bytes: usize,
+    pub render_ms: f64,
+    pub encode_ms: f64,
+    pub warnings: Vec<Warning>,
+}
+
+/// 処理の段階ごとの時間（ミリ秒）
+///
+/// `render_ms`と`encode_ms`はページごとの時間の合計のため、
+/// 並列処理時は`total_ms`より長くなることがある。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StageTimings {
+    pub parse_ms: f64,
+    pub render_ms: f64,
+    pub encode_ms: f64,
+    pub write_ms: f64,
+    pub total_ms: f64,
+}
+
+/// 変換は続けられたが注意が必要な状況
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Warning {
+    /// 1ページだけでメモリ上限を超えると見積もられた（上限を超えてレンダリングした）
+    MemoryLimitExceeded {
+        estimated_bytes: u64,
+        memory_limit: u64,
+    },
+    /// `auto_dpi`のために画像を調べられなかった（すべてのページを`dpi`で変換した）
+    ImageAnalysisFailed { reason: String },
+}
+
+/// 変換に失敗したページ（エラーに付けられる文脈）
+///
+/// レンダリング・エンコード・上限の確認など、特定のページの処理で失敗したエラーに付く。
+/// `anyhow::Error::downcast_ref::<PageFailed>()`で失敗したページを取り出せ、
+/// `Error::from_anyhow`は元のエラーを返す。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageFailed {
+    /// 入力PDFでのページ番号（1始まり）
+    pub page: usize,
+}
+
+impl fmt::Display for PageFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "page {}", self.page)
+    }
+}
+
+impl std::error::Error for PageFailed {}
+
+/// ページの処理で発生したエラーに`PageFailed`を付ける（`index`は入力PDFでの0始まりの番号）
+///
+/// 中断はページの失敗ではないため、そのまま返す。
+pub(crate) fn page_error(error: anyhow::Error, index: usize) -> anyhow::Error {
+    if error.is::<crate::Cancelled>() {
+        error
+    } else {
+        error.context(PageFailed { page: index + 1 })
+    }
+}
+
+/// 経過時間を測るためのタイマー
+///
+/// wasm32では`std::time::Instant`が使えないため、JavaScriptの`Date.now()`で測る（`js`機能がなければ常に0）。
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: Instant,
+    /// 開始時刻（UNIXエポックからのミリ秒）
+    #[cfg(all(target_arch = "wasm32", feature = "js"))]
+    start_ms: f64,
+}
+
+impl Stopwatch {
+    pub fn start() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: Instant::now(),
+            #[cfg(all(target_arch = "wasm32", feature = "js"))]
+            start_ms: js_sys::Date::now(),
+        }
+    }
+
+    /// 開始からの経過時間（ミリ秒）
+    pub fn elapsed_ms(&self) -> f64 {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.start.elapsed().as_secs_f64() * 1000.0;
+
+        #[cfg(all(target_arch = "wasm32", feature = "js"))]
+        return (js_sys::Date::now() - self.start_ms).max(0.0);
+
+        #[cfg(all(target_arch = "wasm32", not(feature = "js")))]
+        return 0.0;
+    }
+}
@@ -0,0 +1,123 @@
+//! Webアプリの設定とプリセットの保存
+//!
+//! 設定はライブラリの`RasterizeOptions`をそのままJSONにしてlocalStorageへ保存する。
+//! プリセットのエクスポート・インポートも同じ形式を使う。
+//! 画面で切り替えた表示言語も合わせて保存する。
+//!
+//! 透かし（ウォーターマーク）の設定は保存しない。ライブラリにもWebアプリにも透かしの機能がなく、
+//! `RasterizeOptions`に対応する項目がないため。透かしを追加する場合は`RasterizeOptions`に項目を加えれば、
+//! 設定・プリセットの保存とエクスポートにもそのまま含まれる。
+
+use gloo_storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Lang;
+use crate::{ColorSpace, ImageEncoding, RasterizeOptions};
+
+/// 最後に使用した設定を保存するキー
+const SETTINGS_KEY: &str = "pdf_rasterizer.settings";
+/// ユーザーが保存したプリセットを保存するキー
+const PRESETS_KEY: &str = "pdf_rasterizer.presets";
+/// 表示言語を保存するキー
+const LANG_KEY: &str = "pdf_rasterizer.lang";
+
+/// 名前付きの変換設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub options: RasterizeOptions,
+    /// 組み込みのプリセットかどうか（保存・削除の対象外）
+    #[serde(skip)]
+    pub builtin: bool,
+}
+
+/// 組み込みのプリセット
+pub fn builtin_presets() -> Vec<Preset> {
+    let preset = |name: &str, options: RasterizeOptions| Preset {
+        name: name.to_string(),
+        options,
+        builtin: true,
+    };
+
+    vec![
+        // FAXの標準的な解像度に合わせたグレースケール
+        preset(
+            "Fax",
+            RasterizeOptions {
+                dpi: 200,
+                quality: 60,
+                color_space: ColorSpace::Gray,
+                ..Default::default()
+            },
+        ),
+        // 画質を落とさずに保存する
+        preset(
+            "Archive",
+            RasterizeOptions {
+                dpi: 300,
+                encoding: ImageEncoding::Flate,
+                ..Default::default()
+            },
+        ),
+        // メールに添付できるサイズに抑える
+        preset(
+            "Email-small",
+            RasterizeOptions {
+                dpi: 100,
+                quality: 50,
+                ..Default::default()
+            },
+        ),
+    ]
+}
+
+/// 最後に使用した設定を読み込む
+pub fn load_settings() -> Option<RasterizeOptions> {
+    LocalStorage::get(SETTINGS_KEY).ok()
+}
+
+/// 現在の設定を保存する
+pub fn save_settings(options: &RasterizeOptions) {
+    if let Err(e) = LocalStorage::set(SETTINGS_KEY, options) {
+        gloo_console::error!(format!("failed to save settings: {}", e));
+    }
+}
+
+/// 切り替えた表示言語を読み込む（切り替えていなければ`None`）
+pub fn load_lang() -> Option<Lang> {
+    LocalStorage::get(LANG_KEY).ok()
+}
+
+/// 表示言語を保存する
+pub fn save_lang(lang: Lang) {
+    if let Err(e) = LocalStorage::set(LANG_KEY, lang) {
+        gloo_console::error!(format!("failed to save language: {}", e));
+    }
+}
+
+/// ユーザーが保存したプリセットを読み込む
+pub fn load_presets() -> Vec<Preset> {
+    LocalStorage::get(PRESETS_KEY).unwrap_or_default()
+}
+
+/// ユーザーが保存したプリセットを保存する（組み込みのプリセットは除く）
+pub fn save_presets(presets: &[Preset]) {
+    let presets: Vec<&Preset> = presets.iter().filter(|preset| !preset.builtin).collect();
+    if let Err(e) = LocalStorage::set(PRESETS_KEY, presets) {
+        gloo_console::error!(format!("failed to save presets: {}", e));
+    }
+}
+
+/// プリセットの設定を`RasterizeOptions`のJSONとして書き出す
+pub fn export_preset(preset: &Preset) -> String {
+    serde_json::to_string_pretty(&preset.options).expect("設定のシリアライズに失敗しました")
+}
+
+/// `RasterizeOptions`のJSONからプリセットを作成する
+pub fn import_preset(name: &str, json: &str) -> Result<Preset, serde_json::Error> {
+    Ok(Preset {
+        name: name.to_string(),
+        options: serde_json::from_str(json)?,
+        builtin: false,
+    })
+}
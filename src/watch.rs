@@ -0,0 +1,448 @@
+//! `watch`サブコマンド: フォルダーに置かれたPDFを書き込みが終わるのを待って変換する
+//!
+//! 入力フォルダーを一定間隔で調べ、大きさと更新日時が一定時間変わらなくなったPDFを
+//! 書き込みが終わったものとして変換する。出力が入力より新しいPDFは変換済みとして扱う。
+//! 失敗したPDFはエラーフォルダーへ移し、同じ名前に`.log`を付けたファイルにエラーを書き出す。
+
+use anyhow::{Context, Result};
+use pdf_rasterizer::i18n::{tr, trf, Lang};
+use pdf_rasterizer::{Error, RasterizeOptions};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::batch::{self, Job};
+use crate::cli_report::FileStatus;
+use crate::PartialOutputs;
+
+/// 中断されたかを確認する間隔
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// フォルダーを調べる間隔の下限（短すぎる間隔でフォルダーを調べ続けてCPUを使い切らないようにする）
+pub const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 監視の設定
+pub struct WatchSettings {
+    /// 監視するフォルダー（サブフォルダーは含めない）
+    pub in_dir: PathBuf,
+    pub out_dir: PathBuf,
+    /// 変換に失敗したPDFとログを移すフォルダー
+    pub error_dir: PathBuf,
+    /// フォルダーを調べる間隔
+    pub interval: Duration,
+    /// 大きさと更新日時がこの時間変わらなければ書き込みが終わったとみなす
+    pub settle: Duration,
+}
+
+/// 書き込み中かどうかを判定するためのファイルの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Snapshot {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+/// 書き込みが終わるのを待っているファイル
+struct Pending {
+    snapshot: Snapshot,
+    since: Instant,
+}
+
+/// ファイルの状態が一定時間変わらなくなるのを待つ
+#[derive(Default)]
+struct Settling {
+    pending: HashMap<PathBuf, Pending>,
+}
+
+impl Settling {
+    /// `files`にないファイル（削除・移動されたもの）を待つのをやめる
+    fn retain(&mut self, files: &[(PathBuf, Snapshot)]) {
+        self.pending
+            .retain(|path, _| files.iter().any(|(file, _)| file == path));
+    }
+
+    /// `now`に見た`path`の状態を記録し、`settle`の間変わっていなければ`true`を返す
+    ///
+    /// 状態が変わった場合はその時点から待ち直す。`true`を返したファイルは待つのをやめる。
+    fn observe(&mut self, path: &Path, snapshot: Snapshot, now: Instant, settle: Duration) -> bool {
+        match self.pending.get(path) {
+            Some(waiting) if waiting.snapshot == snapshot => {
+                let settled = now.duration_since(waiting.since) >= settle;
+                if settled {
+                    self.pending.remove(path);
+                }
+                settled
+            }
+            _ => {
+                self.pending.insert(
+                    path.to_path_buf(),
+                    Pending {
+                        snapshot,
+                        since: now,
+                    },
+                );
+                false
+            }
+        }
+    }
+
+    /// 変換しないファイルを待つのをやめる
+    fn forget(&mut self, path: &Path) {
+        self.pending.remove(path);
+    }
+}
+
+/// 中断されるまでフォルダーを監視し、置かれたPDFを変換する
+pub fn run(
+    lang: Lang,
+    settings: &WatchSettings,
+    options: &RasterizeOptions,
+    partial_outputs: &PartialOutputs,
+) -> Result<()> {
+    for dir in [&settings.out_dir, &settings.error_dir] {
+        std::fs::create_dir_all(dir)
+            .with_context(|| trf(lang, "cli.batch.create_dir", &[&dir.display()]))?;
+    }
+
+    let mut settling = Settling::default();
+    // エラーフォルダーへ移せなかったファイル（変更されるまで変換し直さない）
+    let mut stuck: HashMap<PathBuf, Snapshot> = HashMap::new();
+
+    while !options.cancel.is_cancelled() {
+        let files = match scan(&settings.in_dir) {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    trf(
+                        lang,
+                        "cli.watch.read_dir",
+                        &[&settings.in_dir.display(), &e]
+                    )
+                );
+                Vec::new()
+            }
+        };
+
+        let mut ready = Vec::new();
+        let now = Instant::now();
+        settling.retain(&files);
+        stuck.retain(|path, snapshot| files.contains(&(path.clone(), *snapshot)));
+        for (input, snapshot) in files {
+            let output = output_path(settings, &input);
+            if snapshot.len == 0 || stuck.contains_key(&input) || is_converted(&output, &snapshot) {
+                settling.forget(&input);
+                continue;
+            }
+            if settling.observe(&input, snapshot, now, settings.settle) {
+                ready.push((input, output, snapshot));
+            }
+        }
+
+        for (input, output, snapshot) in ready {
+            if options.cancel.is_cancelled() {
+                break;
+            }
+            let job = Job { input, output };
+            match batch::convert(lang, &job, options, partial_outputs) {
+                FileStatus::Converted { .. } => status!(
+                    "{}",
+                    trf(
+                        lang,
+                        "cli.watch.converted",
+                        &[&job.input.display(), &job.output.display()]
+                    )
+                ),
+                FileStatus::Failed { error, message, .. } => {
+                    match move_to_error_dir(lang, &job.input, &settings.error_dir, &error, &message)
+                    {
+                        Ok(moved) => eprintln!(
+                            "{}",
+                            trf(
+                                lang,
+                                "cli.watch.failed",
+                                &[&job.input.display(), &message, &moved.display()]
+                            )
+                        ),
+                        Err(e) => {
+                            eprintln!(
+                                "{}",
+                                trf(
+                                    lang,
+                                    "cli.watch.move_failed",
+                                    &[&job.input.display(), &message, &format!("{:#}", e)]
+                                )
+                            );
+                            stuck.insert(job.input, snapshot);
+                        }
+                    }
+                }
+                FileStatus::Skipped | FileStatus::Cancelled => {}
+            }
+        }
+
+        sleep(settings.interval, options);
+    }
+
+    Ok(())
+}
+
+/// フォルダー直下のPDF（大文字小文字は区別しない、隠しファイルは除く）と状態を名前順に返す
+fn scan(dir: &Path) -> std::io::Result<Vec<(PathBuf, Snapshot)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let is_pdf = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"));
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !is_pdf || hidden {
+            continue;
+        }
+        // 書き込み中に消えたファイルなどは次の確認で改めて調べる
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_file() {
+            files.push((
+                path,
+                Snapshot {
+                    len: metadata.len(),
+                    modified: metadata.modified().ok(),
+                },
+            ));
+        }
+    }
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(files)
+}
+
+fn output_path(settings: &WatchSettings, input: &Path) -> PathBuf {
+    settings.out_dir.join(input.file_name().unwrap_or_default())
+}
+
+/// 入力より新しい出力があれば変換済みとみなす
+fn is_converted(output: &Path, input: &Snapshot) -> bool {
+    let output_modified = std::fs::metadata(output).and_then(|metadata| metadata.modified());
+    match (output_modified, input.modified) {
+        (Ok(output), Some(input)) => output >= input,
+        (Ok(_), None) => true,
+        (Err(_), _) => false,
+    }
+}
+
+/// 変換に失敗したPDFをエラーフォルダーへ移し、エラーをログに書き出す（移した先を返す）
+///
+/// 同じ名前のファイルがあれば`name-1.pdf`のように番号を付ける。
+fn move_to_error_dir(
+    lang: Lang,
+    input: &Path,
+    error_dir: &Path,
+    error: &Error,
+    message: &str,
+) -> Result<PathBuf> {
+    let destination = unique_path(error_dir, input);
+    // 別のファイルシステムへは名前の変更で移せないため、コピーしてから削除する
+    if std::fs::rename(input, &destination).is_err() {
+        std::fs::copy(input, &destination)
+            .and_then(|_| std::fs::remove_file(input))
+            .with_context(|| trf(lang, "cli.watch.move", &[&destination.display()]))?;
+    }
+
+    let mut log_path = destination.clone().into_os_string();
+    log_path.push(".log");
+    let log = format!(
+        "{}\n{}\n{}\n",
+        trf(lang, "cli.watch.log_input", &[&input.display()]),
+        trf(lang, "cli.watch.log_error", &[&message]),
+        serde_json::to_string(error)?,
+    );
+    std::fs::write(&log_path, log).with_context(|| {
+        trf(
+            lang,
+            "cli.watch.write_log",
+            &[&Path::new(&log_path).display()],
+        )
+    })?;
+    Ok(destination)
+}
+
+/// `dir`の中で`file`と同じ名前の、まだ存在しないパス
+fn unique_path(dir: &Path, file: &Path) -> PathBuf {
+    let path = dir.join(file.file_name().unwrap_or_default());
+    if !path.exists() {
+        return path;
+    }
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = file.extension().unwrap_or_default().to_string_lossy();
+    (1..)
+        .map(|n| dir.join(format!("{}-{}.{}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .unwrap_or(path)
+}
+
+/// 次の確認まで待つ（中断されたらすぐに戻る）
+fn sleep(interval: Duration, options: &RasterizeOptions) {
+    let deadline = Instant::now() + interval;
+    while !options.cancel.is_cancelled() {
+        let now = Instant::now();
+        if now >= deadline {
+            break;
+        }
+        std::thread::sleep(CANCEL_CHECK_INTERVAL.min(deadline - now));
+    }
+}
+
+/// 監視を始めるときの表示
+pub fn print_start(lang: Lang, settings: &WatchSettings) {
+    status!(
+        "{}",
+        trf(
+            lang,
+            "cli.watch.watching",
+            &[
+                &settings.in_dir.display(),
+                &settings.interval.as_secs_f64(),
+                &settings.settle.as_secs_f64()
+            ]
+        )
+    );
+    status!(
+        "{}",
+        trf(lang, "cli.output", &[&settings.out_dir.display()])
+    );
+    status!(
+        "{}",
+        trf(
+            lang,
+            "cli.watch.error_dir",
+            &[&settings.error_dir.display()]
+        )
+    );
+    status!("{}", tr(lang, "cli.watch.stop_hint"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::batch::tests::TempDir;
+
+    const SETTLE: Duration = Duration::from_secs(3);
+
+    fn snapshot(len: u64, modified: u64) -> Snapshot {
+        Snapshot {
+            len,
+            modified: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(modified)),
+        }
+    }
+
+    #[test]
+    fn unchanged_file_settles_after_the_settle_time() {
+        let path = Path::new("in/a.pdf");
+        let start = Instant::now();
+        let mut settling = Settling::default();
+
+        assert!(!settling.observe(path, snapshot(10, 1), start, SETTLE));
+        assert!(!settling.observe(path, snapshot(10, 1), start + SETTLE / 2, SETTLE));
+        assert!(settling.observe(path, snapshot(10, 1), start + SETTLE, SETTLE));
+        // 一度変換に回したファイルは、改めて待ち始める
+        assert!(!settling.observe(path, snapshot(10, 1), start + SETTLE * 2, SETTLE));
+    }
+
+    #[test]
+    fn changes_restart_the_wait() {
+        let path = Path::new("in/a.pdf");
+        let start = Instant::now();
+        let mut settling = Settling::default();
+
+        settling.observe(path, snapshot(10, 1), start, SETTLE);
+        // 大きさが変わった（書き込み中）
+        assert!(!settling.observe(path, snapshot(20, 1), start + SETTLE, SETTLE));
+        // 更新日時だけが変わった
+        assert!(!settling.observe(path, snapshot(20, 2), start + SETTLE * 2, SETTLE));
+        assert!(!settling.observe(
+            path,
+            snapshot(20, 2),
+            start + SETTLE * 2 + SETTLE / 2,
+            SETTLE
+        ));
+        assert!(settling.observe(path, snapshot(20, 2), start + SETTLE * 3, SETTLE));
+    }
+
+    #[test]
+    fn removed_and_forgotten_files_start_over() {
+        let a = PathBuf::from("in/a.pdf");
+        let b = PathBuf::from("in/b.pdf");
+        let start = Instant::now();
+        let mut settling = Settling::default();
+        settling.observe(&a, snapshot(10, 1), start, SETTLE);
+        settling.observe(&b, snapshot(10, 1), start, SETTLE);
+
+        // aが消え、bは変換しないことになった
+        settling.retain(&[(b.clone(), snapshot(10, 1))]);
+        settling.forget(&b);
+
+        assert!(!settling.observe(&a, snapshot(10, 1), start + SETTLE, SETTLE));
+        assert!(!settling.observe(&b, snapshot(10, 1), start + SETTLE, SETTLE));
+    }
+
+    #[test]
+    fn zero_settle_time_converts_on_the_next_check() {
+        let path = Path::new("in/a.pdf");
+        let start = Instant::now();
+        let mut settling = Settling::default();
+        assert!(!settling.observe(path, snapshot(10, 1), start, Duration::ZERO));
+        assert!(settling.observe(path, snapshot(10, 1), start, Duration::ZERO));
+    }
+
+    #[test]
+    fn scan_lists_visible_pdf_files_by_name() {
+        let dir = TempDir::new("watch-scan");
+        for name in ["b.pdf", "A.PDF", ".hidden.pdf", "notes.txt"] {
+            std::fs::write(dir.0.join(name), b"%PDF").unwrap();
+        }
+        std::fs::create_dir(dir.0.join("folder.pdf")).unwrap();
+
+        let files = scan(&dir.0).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|(path, _)| path.file_name().unwrap())
+            .collect();
+        assert_eq!(names, ["A.PDF", "b.pdf"]);
+        assert!(files.iter().all(|(_, snapshot)| snapshot.len == 4));
+    }
+
+    #[test]
+    fn output_newer_than_the_input_counts_as_converted() {
+        let dir = TempDir::new("watch-converted");
+        let output = dir.0.join("a.pdf");
+        assert!(!is_converted(&output, &snapshot(10, 1)));
+
+        std::fs::write(&output, b"%PDF").unwrap();
+        let written = std::fs::metadata(&output).unwrap().modified().unwrap();
+        let at = |modified: SystemTime| Snapshot {
+            len: 10,
+            modified: Some(modified),
+        };
+        assert!(is_converted(&output, &at(written)));
+        assert!(is_converted(
+            &output,
+            &at(written - Duration::from_secs(60))
+        ));
+        assert!(!is_converted(
+            &output,
+            &at(written + Duration::from_secs(60))
+        ));
+    }
+
+    #[test]
+    fn unique_path_numbers_existing_names() {
+        let dir = TempDir::new("watch-unique");
+        let input = Path::new("in/scan.pdf");
+        assert_eq!(unique_path(&dir.0, input), dir.0.join("scan.pdf"));
+
+        std::fs::write(dir.0.join("scan.pdf"), b"").unwrap();
+        std::fs::write(dir.0.join("scan-1.pdf"), b"").unwrap();
+        assert_eq!(unique_path(&dir.0, input), dir.0.join("scan-2.pdf"));
+    }
+}
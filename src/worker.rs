@@ -0,0 +1,988 @@
+//! Web Workerでの変換処理
+//!
+//! メインスレッドと専用Workerの間では、設定や進捗をJSON文字列で、
+//! PDFのバイト列をTransferableな`ArrayBuffer`でやり取りする。
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::rc::{Rc, Weak};
+
+use hayro::{InterpreterSettings, Pdf};
+use hayro_syntax::page::Page;
+use js_sys::{Array, ArrayBuffer, Object, Reflect, Uint8Array};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{DedicatedWorkerGlobalScope, ErrorEvent, MessageEvent, Worker};
+
+use crate::layout::{PageLayout, PagePlan};
+use crate::options::PageDpis;
+use crate::writer::{PdfWriter, RenderedPage};
+use crate::{
+    CancellationToken, Cancelled, Document, Error, ImageEncoding, PagePreview, ProgressEvent,
+    RasterizeOptions, SizeEstimate,
+};
+
+/// Trunkが生成するWorkerの読み込み用スクリプト
+pub const WORKER_URL: &str = "./worker_loader.js";
+
+/// メインスレッドからWorkerへ送る要求
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum Request {
+    /// `data`に添付したPDFを変換する
+    Rasterize { options: RasterizeOptions },
+    /// 実行中の変換を中断する
+    Cancel,
+    /// `data`に添付したPDFをパースして保持する（ワーカープール用）
+    Load { job: u32, options: RasterizeOptions },
+    /// 保持しているPDFの1ページを画像化する（ワーカープール用）
+    RenderPage { job: u32, index: usize },
+    /// 出力PDFのサイズを見積もる（`data`にPDFを添付した場合は読み込み直す）
+    Estimate {
+        key: usize,
+        options: RasterizeOptions,
+    },
+    /// 1ページの変換前後の画像を作成する（`data`の扱いは`Estimate`と同じ）
+    Preview {
+        key: usize,
+        index: usize,
+        options: RasterizeOptions,
+    },
+    /// 1ページを`dpi`でPNGにレンダリングする（`data`の扱いは`Estimate`と同じ）
+    Thumbnail { key: usize, index: usize, dpi: u32 },
+}
+
+/// Workerからメインスレッドへ送る応答
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum Response {
+    Progress {
+        event: ProgressEvent,
+    },
+    /// `data`に変換後のPDFを添付する
+    Done,
+    /// `job`はワーカープールのジョブの識別子（単独の変換と不正なメッセージでは`None`）
+    Failed {
+        job: Option<u32>,
+        error: Error,
+    },
+    Cancelled,
+    Loaded {
+        job: u32,
+        pages: usize,
+    },
+    /// `data`にエンコード済みのページ画像を添付する（`layout`は出力PDFでの配置）
+    PageRendered {
+        job: u32,
+        index: usize,
+        width: u32,
+        height: u32,
+        layout: PageLayout,
+    },
+    Estimated {
+        key: usize,
+        estimate: SizeEstimate,
+    },
+    EstimateFailed {
+        key: usize,
+        error: Error,
+    },
+    /// `data`に変換前のPNG（`original_bytes`バイト）と変換後の画像を続けて添付する
+    Previewed {
+        key: usize,
+        index: usize,
+        original_bytes: usize,
+        encoded_bytes: usize,
+        encoding: ImageEncoding,
+        width: u32,
+        height: u32,
+    },
+    PreviewFailed {
+        key: usize,
+        index: usize,
+        error: Error,
+    },
+    /// `data`にサムネイルのPNGを添付する
+    ThumbnailRendered {
+        key: usize,
+        index: usize,
+    },
+    ThumbnailFailed {
+        key: usize,
+        index: usize,
+        error: Error,
+    },
+}
+
+/// メインスレッドが受け取るWorkerからの通知
+pub enum WorkerEvent {
+    Progress(ProgressEvent),
+    Done(Vec<u8>),
+    Failed(Error),
+    Cancelled,
+    /// Workerでエラーが発生し、変換の応答が返らなくなった（引数はエラーメッセージ）
+    ///
+    /// 読み込みの失敗やパニックなど、Worker内で捕捉できなかったエラーで発生する。
+    /// 同じWorkerでは以降の変換も失敗する可能性が高いため、別の方法で変換し直す。
+    Crashed(String),
+}
+
+/// JSONと添付データからメッセージを作成し、添付データの所有権を移す転送リストを返す
+fn envelope<T: Serialize>(body: &T, data: Option<&[u8]>) -> (JsValue, Array) {
+    let message = Object::new();
+    let transfer = Array::new();
+    let json = serde_json::to_string(body).expect("メッセージのシリアライズに失敗しました");
+    let _ = Reflect::set(&message, &"body".into(), &json.into());
+
+    if let Some(data) = data {
+        let buffer = Uint8Array::from(data).buffer();
+        let _ = Reflect::set(&message, &"data".into(), &buffer);
+        transfer.push(&buffer);
+    }
+
+    (message.into(), transfer)
+}
+
+/// メッセージからJSONと添付データを取り出す
+fn open_envelope<T: for<'de> Deserialize<'de>>(message: &JsValue) -> Option<(T, Option<Vec<u8>>)> {
+    let json = Reflect::get(message, &"body".into()).ok()?.as_string()?;
+    let body = serde_json::from_str(&json).ok()?;
+    let data = Reflect::get(message, &"data".into())
+        .ok()
+        .and_then(|data| data.dyn_into::<ArrayBuffer>().ok())
+        .map(|buffer| Uint8Array::new(&buffer).to_vec());
+
+    Some((body, data))
+}
+
+/// ワーカープール用にWorker内で保持するPDF
+struct LoadedPdf {
+    job: u32,
+    pdf: Pdf,
+    /// ページごとのDPI
+    dpis: PageDpis,
+    options: RasterizeOptions,
+}
+
+/// Worker側のメッセージ処理を登録する（Workerのエントリーポイントから呼び出す）
+pub fn run_worker() {
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let current: Rc<RefCell<Option<CancellationToken>>> = Rc::default();
+    let loaded: Rc<RefCell<Option<LoadedPdf>>> = Rc::default();
+    let estimating: Rc<RefCell<Option<(usize, Document)>>> = Rc::default();
+
+    let onmessage = Closure::<dyn FnMut(MessageEvent)>::new({
+        let scope = scope.clone();
+        move |event: MessageEvent| match open_envelope::<Request>(&event.data()) {
+            Some((Request::Rasterize { options }, Some(data))) => {
+                *current.borrow_mut() = Some(options.cancel.clone());
+                let scope = scope.clone();
+
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = crate::rasterize_pdf_with_progress(data, &options, |event| {
+                        post(&scope, &Response::Progress { event }, None);
+                    })
+                    .await;
+
+                    match result {
+                        Ok(output) => post(&scope, &Response::Done, Some(&output)),
+                        Err(e) if e.is::<Cancelled>() => post(&scope, &Response::Cancelled, None),
+                        Err(e) => post(
+                            &scope,
+                            &Response::Failed {
+                                job: None,
+                                error: Error::from_anyhow(&e),
+                            },
+                            None,
+                        ),
+                    }
+                });
+            }
+            Some((Request::Cancel, _)) => {
+                if let Some(cancel) = current.borrow().as_ref() {
+                    cancel.cancel();
+                }
+            }
+            Some((Request::Load { job, options }, Some(data))) => {
+                match load_pdf(job, data, options) {
+                    Ok(pdf) => {
+                        let pages = pdf.pdf.pages().len();
+                        *loaded.borrow_mut() = Some(pdf);
+                        post(&scope, &Response::Loaded { job, pages }, None);
+                    }
+                    Err(e) => post(
+                        &scope,
+                        &Response::Failed {
+                            job: Some(job),
+                            error: Error::from_anyhow(&e),
+                        },
+                        None,
+                    ),
+                }
+            }
+            Some((Request::RenderPage { job, index }, _)) => {
+                let loaded = loaded.borrow();
+                let result = match loaded.as_ref() {
+                    Some(loaded) if loaded.job == job => render_loaded_page(loaded, index),
+                    // 中断済みのジョブの要求は無視する
+                    _ => return,
+                };
+
+                match result {
+                    Ok((page, layout)) => post(
+                        &scope,
+                        &Response::PageRendered {
+                            job,
+                            index,
+                            width: page.width,
+                            height: page.height,
+                            layout,
+                        },
+                        Some(&page.data),
+                    ),
+                    Err(e) => post(
+                        &scope,
+                        &Response::Failed {
+                            job: Some(job),
+                            error: Error::from_anyhow(&e),
+                        },
+                        None,
+                    ),
+                }
+            }
+            Some((Request::Estimate { key, options }, data)) => {
+                let result = with_document(&estimating, key, data, |document| {
+                    document.estimate_output_size(&options)
+                });
+
+                match result {
+                    Ok(estimate) => post(&scope, &Response::Estimated { key, estimate }, None),
+                    Err(e) => post(
+                        &scope,
+                        &Response::EstimateFailed {
+                            key,
+                            error: Error::from_anyhow(&e),
+                        },
+                        None,
+                    ),
+                }
+            }
+            Some((
+                Request::Preview {
+                    key,
+                    index,
+                    options,
+                },
+                data,
+            )) => {
+                let result = with_document(&estimating, key, data, |document| {
+                    document.preview_page(index, &options)
+                });
+
+                match result {
+                    Ok(preview) => {
+                        let original_bytes = preview.original_png.len();
+                        let mut images = preview.original_png;
+                        images.extend_from_slice(&preview.encoded_image);
+                        post(
+                            &scope,
+                            &Response::Previewed {
+                                key,
+                                index,
+                                original_bytes,
+                                encoded_bytes: preview.encoded_bytes,
+                                encoding: options.encoding,
+                                width: preview.width,
+                                height: preview.height,
+                            },
+                            Some(&images),
+                        );
+                    }
+                    Err(e) => post(
+                        &scope,
+                        &Response::PreviewFailed {
+                            key,
+                            index,
+                            error: Error::from_anyhow(&e),
+                        },
+                        None,
+                    ),
+                }
+            }
+            Some((Request::Thumbnail { key, index, dpi }, data)) => {
+                let result = with_document(&estimating, key, data, |document| {
+                    document.render_page_png(index, dpi)
+                });
+
+                match result {
+                    Ok(png) => post(
+                        &scope,
+                        &Response::ThumbnailRendered { key, index },
+                        Some(&png),
+                    ),
+                    Err(e) => post(
+                        &scope,
+                        &Response::ThumbnailFailed {
+                            key,
+                            index,
+                            error: Error::from_anyhow(&e),
+                        },
+                        None,
+                    ),
+                }
+            }
+            _ => post(
+                &scope,
+                &Response::Failed {
+                    job: None,
+                    error: Error::InvalidMessage,
+                },
+                None,
+            ),
+        }
+    });
+
+    scope.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    onmessage.forget();
+}
+
+/// `key`の文書で`f`を実行する（`data`があれば先にパースして保持する）
+fn with_document<T>(
+    loaded: &RefCell<Option<(usize, Document)>>,
+    key: usize,
+    data: Option<Vec<u8>>,
+    f: impl FnOnce(&Document) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let mut loaded = loaded.borrow_mut();
+    if let Some(data) = data {
+        *loaded = Some((key, Document::load(data)?));
+    }
+    match loaded.as_ref() {
+        Some((loaded_key, document)) if *loaded_key == key => f(document),
+        _ => Err(Error::EstimateNotLoaded.into()),
+    }
+}
+
+/// 入力PDFをパースし、単独のWorkerでの変換と同じく上限と照らし合わせる
+///
+/// ページごとのDPIを決め、変換するページの大きさと画素数の合計もここで確認する。
+fn load_pdf(job: u32, data: Vec<u8>, options: RasterizeOptions) -> anyhow::Result<LoadedPdf> {
+    options.limits.check_input(data.len() as u64)?;
+    let pdf = crate::parse_pdf(data)?;
+
+    options.limits.check_page_count(pdf.pages().len())?;
+    let dpis = PageDpis::new(&options, pdf.data().as_ref().as_ref());
+    let selected = options.selected_pages(pdf.pages().len())?;
+    let pages: Vec<&Page> = selected.iter().map(|&index| &pdf.pages()[index]).collect();
+    let plans: Vec<PagePlan> = pages
+        .iter()
+        .zip(&selected)
+        .map(|(page, &index)| PagePlan::new(page, dpis.get(index), &options))
+        .collect();
+    crate::check_page_sizes(&pages, &plans, &selected, &options.limits)?;
+
+    Ok(LoadedPdf {
+        job,
+        pdf,
+        dpis,
+        options,
+    })
+}
+
+/// 保持しているPDFの1ページを画像化し、出力PDFでの配置とともに返す
+fn render_loaded_page(
+    loaded: &LoadedPdf,
+    index: usize,
+) -> anyhow::Result<(RenderedPage, PageLayout)> {
+    let page = loaded
+        .pdf
+        .pages()
+        .get(index)
+        .ok_or(Error::PageNotFound { page: index + 1 })?;
+    let plan = PagePlan::new(page, loaded.dpis.get(index), &loaded.options);
+
+    let (image, _) = crate::render_page_timed(
+        page,
+        &InterpreterSettings::default(),
+        &plan.render_settings(),
+        &loaded.options,
+        index,
+    )?;
+    let rendered = crate::encode_page_image(&image, &loaded.options)?;
+    let layout = plan.layout(rendered.width, rendered.height);
+    Ok((rendered, layout))
+}
+
+fn post(scope: &DedicatedWorkerGlobalScope, response: &Response, data: Option<&[u8]>) {
+    let (message, transfer) = envelope(response, data);
+    let _ = scope.post_message_with_transfer(&message, &transfer);
+}
+
+/// メインスレッドから変換用Workerを操作するハンドル
+///
+/// ドロップするとWorkerを終了する。
+pub struct RasterizeWorker {
+    worker: Worker,
+    _onmessage: Closure<dyn FnMut(MessageEvent)>,
+    _onerror: Closure<dyn FnMut(ErrorEvent)>,
+    _onmessageerror: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl RasterizeWorker {
+    /// Workerを起動し、Workerからの通知を`on_event`で受け取る
+    pub fn spawn<F>(on_event: F) -> Result<Self, JsValue>
+    where
+        F: Fn(WorkerEvent) + 'static,
+    {
+        let on_event = Rc::new(on_event);
+        Self::spawn_raw(
+            {
+                let on_event = on_event.clone();
+                move |response, data| {
+                    let event = match (response, data) {
+                        (Response::Progress { event }, _) => WorkerEvent::Progress(event),
+                        (Response::Done, Some(data)) => WorkerEvent::Done(data),
+                        (Response::Cancelled, _) => WorkerEvent::Cancelled,
+                        (Response::Failed { error, .. }, _) => WorkerEvent::Failed(error),
+                        _ => WorkerEvent::Failed(Error::InvalidMessage),
+                    };
+                    on_event(event);
+                }
+            },
+            move |message| on_event(WorkerEvent::Crashed(message)),
+        )
+    }
+
+    /// Workerを起動し、応答を`on_response`で、Worker内で捕捉されなかったエラーを`on_error`で受け取る
+    fn spawn_raw<F, E>(on_response: F, on_error: E) -> Result<Self, JsValue>
+    where
+        F: Fn(Response, Option<Vec<u8>>) + 'static,
+        E: Fn(String) + 'static,
+    {
+        let worker = Worker::new(WORKER_URL)?;
+
+        let onmessage =
+            Closure::<dyn FnMut(MessageEvent)>::new(
+                move |event: MessageEvent| match open_envelope::<Response>(&event.data()) {
+                    Some((response, data)) => on_response(response, data),
+                    None => on_response(
+                        Response::Failed {
+                            job: None,
+                            error: Error::InvalidMessage,
+                        },
+                        None,
+                    ),
+                },
+            );
+        worker.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+
+        let on_error = Rc::new(on_error);
+        let onerror = Closure::<dyn FnMut(ErrorEvent)>::new({
+            let on_error = on_error.clone();
+            move |event: ErrorEvent| on_error(event.message())
+        });
+        worker.set_onerror(Some(onerror.as_ref().unchecked_ref()));
+
+        // 添付データを復元できないメッセージは応答が失われたものとして扱う
+        let onmessageerror = Closure::<dyn FnMut(MessageEvent)>::new(move |_: MessageEvent| {
+            on_error(String::from("messageerror"));
+        });
+        worker.set_onmessageerror(Some(onmessageerror.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            worker,
+            _onmessage: onmessage,
+            _onerror: onerror,
+            _onmessageerror: onmessageerror,
+        })
+    }
+
+    /// PDFの変換を開始する（`data`はWorkerへ転送される）
+    pub fn rasterize(&self, data: &[u8], options: &RasterizeOptions) {
+        self.send(
+            &Request::Rasterize {
+                options: options.clone(),
+            },
+            Some(data),
+        );
+    }
+
+    /// 実行中の変換を中断する
+    pub fn cancel(&self) {
+        self.send(&Request::Cancel, None);
+    }
+
+    fn send(&self, request: &Request, data: Option<&[u8]>) {
+        let (message, transfer) = envelope(request, data);
+        if let Err(e) = self.worker.post_message_with_transfer(&message, &transfer) {
+            gloo_console::error!("failed to post a message to the worker", e);
+        }
+    }
+}
+
+impl Drop for RasterizeWorker {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
+
+/// 出力PDFのサイズの見積もり・変換前後の比較・サムネイルを専用のWorkerで実行するハンドル
+///
+/// 変換処理とは別のWorkerで実行するため、変換中でも設定の変更に追従でき、メインスレッドも止まらない。
+/// いずれもプレビュー中のPDFに対して行うため、Worker内のパース済みの文書を共有する。
+pub struct SizeEstimator {
+    worker: RasterizeWorker,
+    /// Workerに転送済みのPDFの識別子
+    loaded: Rc<Cell<Option<usize>>>,
+    /// 結果を待っている比較（PDFの識別子とページ番号）
+    previewing: Rc<Cell<Option<(usize, usize)>>>,
+    /// 結果を待っているサムネイル（PDFの識別子とページ番号）
+    thumbnailing: Rc<Cell<Option<(usize, usize)>>>,
+}
+
+impl SizeEstimator {
+    /// Workerを起動し、見積もり結果を`on_result`で、比較用の画像を`on_preview`で、
+    /// サムネイルのPNGを`on_thumbnail`で受け取る
+    pub fn spawn<F, P, T>(on_result: F, on_preview: P, on_thumbnail: T) -> Result<Self, JsValue>
+    where
+        F: Fn(usize, Result<SizeEstimate, Error>) + 'static,
+        P: Fn(usize, usize, Result<PagePreview, Error>) + 'static,
+        T: Fn(usize, usize, Result<Vec<u8>, Error>) + 'static,
+    {
+        let on_result = Rc::new(on_result);
+        let on_preview = Rc::new(on_preview);
+        let on_thumbnail = Rc::new(on_thumbnail);
+        let loaded: Rc<Cell<Option<usize>>> = Rc::default();
+        let previewing: Rc<Cell<Option<(usize, usize)>>> = Rc::default();
+        let thumbnailing: Rc<Cell<Option<(usize, usize)>>> = Rc::default();
+        let worker = RasterizeWorker::spawn_raw(
+            {
+                let on_result = on_result.clone();
+                let on_preview = on_preview.clone();
+                let on_thumbnail = on_thumbnail.clone();
+                let previewing = previewing.clone();
+                let thumbnailing = thumbnailing.clone();
+                move |response, data| match response {
+                    Response::Estimated { key, estimate } => on_result(key, Ok(estimate)),
+                    Response::EstimateFailed { key, error } => on_result(key, Err(error)),
+                    Response::Previewed {
+                        key,
+                        index,
+                        original_bytes,
+                        encoded_bytes,
+                        encoding,
+                        width,
+                        height,
+                    } => {
+                        previewing.set(None);
+                        let result = match data {
+                            Some(mut original_png) if original_bytes <= original_png.len() => {
+                                let encoded_image = original_png.split_off(original_bytes);
+                                Ok(PagePreview {
+                                    original_png,
+                                    encoded_image,
+                                    encoded_mime_type: crate::document::preview_mime_type(encoding),
+                                    encoded_bytes,
+                                    width,
+                                    height,
+                                })
+                            }
+                            _ => Err(Error::InvalidMessage),
+                        };
+                        on_preview(key, index, result);
+                    }
+                    Response::PreviewFailed { key, index, error } => {
+                        previewing.set(None);
+                        on_preview(key, index, Err(error));
+                    }
+                    Response::ThumbnailRendered { key, index } => {
+                        thumbnailing.set(None);
+                        on_thumbnail(key, index, data.ok_or(Error::InvalidMessage));
+                    }
+                    Response::ThumbnailFailed { key, index, error } => {
+                        thumbnailing.set(None);
+                        on_thumbnail(key, index, Err(error));
+                    }
+                    _ => {}
+                }
+            },
+            {
+                let loaded = loaded.clone();
+                let previewing = previewing.clone();
+                let thumbnailing = thumbnailing.clone();
+                // 実行中の見積もり・比較・サムネイルを失敗させ、次の要求ではPDFを転送し直す
+                move |message: String| {
+                    if let Some((key, index)) = thumbnailing.take() {
+                        on_thumbnail(
+                            key,
+                            index,
+                            Err(Error::WorkerCrashed {
+                                message: message.clone(),
+                            }),
+                        );
+                    }
+                    if let Some((key, index)) = previewing.take() {
+                        on_preview(
+                            key,
+                            index,
+                            Err(Error::WorkerCrashed {
+                                message: message.clone(),
+                            }),
+                        );
+                    }
+                    if let Some(key) = loaded.take() {
+                        on_result(key, Err(Error::WorkerCrashed { message }));
+                    }
+                }
+            },
+        )?;
+
+        Ok(Self {
+            worker,
+            loaded,
+            previewing,
+            thumbnailing,
+        })
+    }
+
+    /// `key`で識別するPDFの出力サイズを見積もる
+    ///
+    /// 直前と同じ`key`の場合はPDFを転送し直さず、Worker内のパース済みの文書を使う。
+    pub fn estimate(&self, key: usize, data: &[u8], options: &RasterizeOptions) {
+        let data = self.data_to_send(key, data);
+        self.worker.send(
+            &Request::Estimate {
+                key,
+                options: options.clone(),
+            },
+            data,
+        );
+    }
+
+    /// `key`で識別するPDFの`index`ページ目（0始まり）を変換前と変換後の両方の画像にする
+    ///
+    /// PDFの転送は`estimate`と同じく、直前と同じ`key`の場合は省略する。
+    pub fn preview(&self, key: usize, index: usize, data: &[u8], options: &RasterizeOptions) {
+        let data = self.data_to_send(key, data);
+        self.previewing.set(Some((key, index)));
+        self.worker.send(
+            &Request::Preview {
+                key,
+                index,
+                options: options.clone(),
+            },
+            data,
+        );
+    }
+
+    /// `key`で識別するPDFの`index`ページ目（0始まり）を`dpi`でPNGにレンダリングする
+    ///
+    /// PDFの転送は`estimate`と同じく、直前と同じ`key`の場合は省略する。
+    pub fn thumbnail(&self, key: usize, index: usize, dpi: u32, data: &[u8]) {
+        let data = self.data_to_send(key, data);
+        self.thumbnailing.set(Some((key, index)));
+        self.worker
+            .send(&Request::Thumbnail { key, index, dpi }, data);
+    }
+
+    /// Workerに転送する必要があれば`data`を返す
+    fn data_to_send<'a>(&self, key: usize, data: &'a [u8]) -> Option<&'a [u8]> {
+        let data = (self.loaded.get() != Some(key)).then_some(data);
+        self.loaded.set(Some(key));
+        data
+    }
+}
+
+/// ページの画像化を複数のWorkerに分散するワーカープール
+///
+/// 各WorkerがPDFをパースして保持し、メインスレッドが未処理のページを
+/// 空いているWorkerへ1ページずつ割り当てる。画像化されたページは
+/// ページ順に並べ替えてから出力PDFへ書き出す。
+pub struct RasterizePool {
+    inner: Rc<PoolInner>,
+}
+
+struct PoolInner {
+    workers: RefCell<Vec<RasterizeWorker>>,
+    job: RefCell<Option<PoolJob>>,
+    next_job_id: RefCell<u32>,
+    on_event: Box<dyn Fn(WorkerEvent)>,
+}
+
+/// 実行中の変換の状態
+struct PoolJob {
+    id: u32,
+    options: RasterizeOptions,
+    total: Option<usize>,
+    /// 変換するページの文書内での番号（出力順）
+    selected: Vec<usize>,
+    next_to_dispatch: usize,
+    idle: Vec<bool>,
+    /// 書き出し待ちのページと出力PDFでの配置（ページ番号順）
+    pending: BTreeMap<usize, (RenderedPage, PageLayout)>,
+    writer: Option<PdfWriter<Vec<u8>>>,
+    written: usize,
+}
+
+impl RasterizePool {
+    /// `size`個のWorkerを起動し、変換の通知を`on_event`で受け取る
+    pub fn spawn<F>(size: usize, on_event: F) -> Result<Self, JsValue>
+    where
+        F: Fn(WorkerEvent) + 'static,
+    {
+        let inner = Rc::new(PoolInner {
+            workers: RefCell::new(Vec::new()),
+            job: RefCell::new(None),
+            next_job_id: RefCell::new(0),
+            on_event: Box::new(on_event),
+        });
+
+        for worker_index in 0..size.max(1) {
+            let weak: Weak<PoolInner> = Rc::downgrade(&inner);
+            let worker = RasterizeWorker::spawn_raw(
+                {
+                    let weak = weak.clone();
+                    move |response, data| {
+                        if let Some(inner) = weak.upgrade() {
+                            inner.handle(worker_index, response, data);
+                        }
+                    }
+                },
+                move |message| {
+                    if let Some(inner) = weak.upgrade() {
+                        inner.crashed(message);
+                    }
+                },
+            )?;
+            inner.workers.borrow_mut().push(worker);
+        }
+
+        Ok(Self { inner })
+    }
+
+    /// Worker数
+    pub fn size(&self) -> usize {
+        self.inner.workers.borrow().len()
+    }
+
+    /// PDFの変換を開始する（各Workerにコピーを転送する）
+    pub fn rasterize(&self, data: &[u8], options: &RasterizeOptions) {
+        let id = {
+            let mut next_job_id = self.inner.next_job_id.borrow_mut();
+            *next_job_id = next_job_id.wrapping_add(1);
+            *next_job_id
+        };
+
+        let writer = match PdfWriter::new(Vec::new()) {
+            Ok(writer) => writer,
+            Err(e) => {
+                (self.inner.on_event)(WorkerEvent::Failed(Error::from_anyhow(&e)));
+                return;
+            }
+        };
+
+        *self.inner.job.borrow_mut() = Some(PoolJob {
+            id,
+            options: options.clone(),
+            total: None,
+            selected: Vec::new(),
+            next_to_dispatch: 0,
+            idle: vec![false; self.size()],
+            pending: BTreeMap::new(),
+            writer: Some(writer),
+            written: 0,
+        });
+
+        let request = Request::Load {
+            job: id,
+            options: options.clone(),
+        };
+        for worker in self.inner.workers.borrow().iter() {
+            worker.send(&request, Some(data));
+        }
+    }
+
+    /// 実行中の変換を中断する（処理中のページの結果は破棄する）
+    pub fn cancel(&self) {
+        if self.inner.job.borrow_mut().take().is_some() {
+            (self.inner.on_event)(WorkerEvent::Cancelled);
+        }
+    }
+}
+
+impl PoolInner {
+    fn handle(&self, worker_index: usize, response: Response, data: Option<Vec<u8>>) {
+        // 借用を解放してから通知する（通知先から`cancel`が呼ばれても安全にするため）
+        let events = self.advance(worker_index, response, data);
+        for event in events {
+            (self.on_event)(event);
+        }
+    }
+
+    /// Workerでエラーが発生した場合は実行中のジョブを中止する
+    ///
+    /// 1つのWorkerが止まるとそのWorkerに割り当てたページが返らないため、ジョブ全体を諦めて
+    /// 呼び出し側に別の方法で変換し直してもらう。
+    fn crashed(&self, message: String) {
+        if self.job.borrow_mut().take().is_some() {
+            (self.on_event)(WorkerEvent::Crashed(message));
+        }
+    }
+
+    /// Workerの応答でジョブを進め、通知すべきイベントを返す
+    fn advance(
+        &self,
+        worker_index: usize,
+        response: Response,
+        data: Option<Vec<u8>>,
+    ) -> Vec<WorkerEvent> {
+        let mut slot = self.job.borrow_mut();
+        let Some(job) = slot.as_mut() else {
+            return Vec::new();
+        };
+        let mut events = Vec::new();
+
+        match (response, data) {
+            (Response::Loaded { job: id, pages }, _) if id == job.id => {
+                if job.total.is_none() {
+                    match job.options.selected_pages(pages) {
+                        Ok(selected) => job.selected = selected,
+                        Err(e) => {
+                            *slot = None;
+                            events.push(WorkerEvent::Failed(Error::from_anyhow(&e)));
+                            return events;
+                        }
+                    }
+                    let pages = job.selected.len();
+                    job.total = Some(pages);
+                    events.push(WorkerEvent::Progress(ProgressEvent::Parsed { pages }));
+                }
+                job.idle[worker_index] = true;
+            }
+            (
+                Response::PageRendered {
+                    job: id,
+                    index,
+                    width,
+                    height,
+                    layout,
+                },
+                Some(data),
+            ) if id == job.id => {
+                // 文書内の番号を出力PDF内での番号に変換する
+                let Ok(index) = job.selected.binary_search(&index) else {
+                    return events;
+                };
+                events.push(WorkerEvent::Progress(ProgressEvent::PageRendered {
+                    index,
+                    bytes: data.len(),
+                }));
+                job.pending.insert(
+                    index,
+                    (
+                        RenderedPage {
+                            data,
+                            width,
+                            height,
+                            color_space: job.options.color_space,
+                            encoding: job.options.encoding,
+                        },
+                        layout,
+                    ),
+                );
+                job.idle[worker_index] = true;
+
+                if let Err(e) = job.write_pending(&mut events) {
+                    *slot = None;
+                    events.push(WorkerEvent::Failed(Error::from_anyhow(&e)));
+                    return events;
+                }
+            }
+            // 以前のジョブの失敗は無視する
+            (Response::Failed { job: id, error }, _) if id.is_none_or(|id| id == job.id) => {
+                *slot = None;
+                events.push(WorkerEvent::Failed(error));
+                return events;
+            }
+            // 以前のジョブの応答は無視する
+            _ => return events,
+        }
+
+        if job.total == Some(job.written) {
+            let finished = job.writer.take().map(|writer| writer.finish());
+            *slot = None;
+            match finished {
+                Some(Ok((output, bytes))) => {
+                    events.push(WorkerEvent::Progress(ProgressEvent::Saved { bytes }));
+                    events.push(WorkerEvent::Done(output));
+                }
+                Some(Err(e)) => events.push(WorkerEvent::Failed(Error::from_anyhow(&e))),
+                None => {}
+            }
+            return events;
+        }
+
+        job.dispatch(&self.workers.borrow());
+        events
+    }
+}
+
+impl PoolJob {
+    /// ページ順に書き出せるページをすべて出力PDFへ書き出す
+    fn write_pending(&mut self, events: &mut Vec<WorkerEvent>) -> anyhow::Result<()> {
+        let total = self.total.unwrap_or(0);
+        let Some(writer) = self.writer.as_mut() else {
+            return Ok(());
+        };
+
+        while let Some((page, layout)) = self.pending.remove(&self.written) {
+            writer.add_page(&page, &layout)?;
+            self.written += 1;
+            events.push(WorkerEvent::Progress(ProgressEvent::Assembling {
+                done: self.written,
+                total,
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// 空いているWorkerに未処理のページを割り当てる
+    ///
+    /// 書き出し待ちのページが増えすぎないよう、先行して割り当てるページ数を
+    /// Worker数の2倍までに制限する。
+    fn dispatch(&mut self, workers: &[RasterizeWorker]) {
+        let Some(total) = self.total else {
+            return;
+        };
+        let limit = (self.written + workers.len() * 2).min(total);
+
+        for (worker_index, worker) in workers.iter().enumerate() {
+            if self.next_to_dispatch >= limit {
+                break;
+            }
+            if !self.idle[worker_index] {
+                continue;
+            }
+
+            self.idle[worker_index] = false;
+            worker.send(
+                &Request::RenderPage {
+                    job: self.id,
+                    index: self.selected[self.next_to_dispatch],
+                },
+                None,
+            );
+            self.next_to_dispatch += 1;
+        }
+    }
+}
+
+/// ブラウザが利用できる論理プロセッサ数
+pub fn hardware_concurrency() -> usize {
+    web_sys::window()
+        .map(|window| window.navigator().hardware_concurrency() as usize)
+        .unwrap_or(1)
+        .max(1)
+}
@@ -0,0 +1,360 @@
+use crate::{layout::PageLayout, ColorSpace, Error, ImageEncoding};
+use anyhow::{Context, Result};
+use std::io::Write;
+
+/// Catalogオブジェクトの番号
+const CATALOG_ID: usize = 1;
+/// Pagesオブジェクトの番号
+const PAGES_ID: usize = 2;
+/// 各ページで使用するオブジェクト数（Page・画像XObject・コンテンツストリーム）
+const OBJECTS_PER_PAGE: usize = 3;
+
+/// レンダリング済みのページ画像
+pub(crate) struct RenderedPage {
+    /// エンコード済みの画像データ
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub color_space: ColorSpace,
+    pub encoding: ImageEncoding,
+}
+
+/// 書き込んだバイト数を数えるWriter
+struct CountingWriter<W: Write> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// ページ画像を受け取るたびにPDFオブジェクトを書き出すWriter
+///
+/// lopdfの`Document`のように全ページをメモリ上に保持せず、
+/// 各ページを書き出した時点で画像データを解放できる。
+pub(crate) struct PdfWriter<W: Write> {
+    out: CountingWriter<W>,
+    /// オブジェクト番号ごとのバイトオフセット（番号 - 1 がインデックス）
+    offsets: Vec<Option<u64>>,
+    page_ids: Vec<usize>,
+}
+
+impl<W: Write> PdfWriter<W> {
+    /// ヘッダーを書き込んでWriterを作成する
+    pub fn new(inner: W) -> Result<Self> {
+        let mut out = CountingWriter { inner, written: 0 };
+        // バイナリを含むことを示すコメントをヘッダーに続けて書き込む
+        out.write_all(b"%PDF-1.5\n%\xE2\xE3\xCF\xD3\n")
+            .context(Error::WriteHeader)?;
+
+        Ok(Self {
+            out,
+            offsets: vec![None; PAGES_ID],
+            page_ids: Vec::new(),
+        })
+    }
+
+    /// 画像1枚を1ページとして、指定した大きさのページに配置して書き込む
+    pub fn add_page(&mut self, page: &RenderedPage, layout: &PageLayout) -> Result<()> {
+        let page_num = self.page_ids.len();
+        let page_id = PAGES_ID + 1 + page_num * OBJECTS_PER_PAGE;
+        let image_id = page_id + 1;
+        let content_id = page_id + 2;
+
+        // ページオブジェクトを作成
+        self.begin_object(page_id)?;
+        write!(
+            self.out,
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Contents {} 0 R \
+             /Resources << /XObject << /Im{} {} 0 R >> >> >>",
+            PAGES_ID,
+            real(layout.page_width),
+            real(layout.page_height),
+            content_id,
+            page_num,
+            image_id
+        )?;
+        self.end_object()?;
+
+        // 画像XObjectを作成
+        self.begin_object(image_id)?;
+        write!(
+            self.out,
+            "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} \
+             /BitsPerComponent 8 /Filter /{} /Length {} >>",
+            page.width,
+            page.height,
+            page.color_space.pdf_name(),
+            page.encoding.pdf_filter(),
+            page.data.len()
+        )?;
+        self.write_stream(&page.data)?;
+        self.end_object()?;
+
+        // コンテンツストリームを作成（画像を配置）
+        let content = format!(
+            "q\n{} 0 0 {} {} {} cm\n/Im{} Do\nQ",
+            real(layout.image_width),
+            real(layout.image_height),
+            real(layout.image_x),
+            real(layout.image_y),
+            page_num
+        );
+        self.begin_object(content_id)?;
+        write!(self.out, "<< /Length {} >>", content.len())?;
+        self.write_stream(content.as_bytes())?;
+        self.end_object()?;
+
+        self.page_ids.push(page_id);
+        Ok(())
+    }
+
+    /// Pages・Catalog・相互参照表を書き込み、内部のWriterと書き込んだバイト数を返す
+    pub fn finish(mut self) -> Result<(W, u64)> {
+        // Pagesオブジェクトを作成
+        let kids = self
+            .page_ids
+            .iter()
+            .map(|id| format!("{} 0 R", id))
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.begin_object(PAGES_ID)?;
+        write!(
+            self.out,
+            "<< /Type /Pages /Count {} /Kids [{}] >>",
+            self.page_ids.len(),
+            kids
+        )?;
+        self.end_object()?;
+
+        // Catalogオブジェクトを作成
+        self.begin_object(CATALOG_ID)?;
+        write!(self.out, "<< /Type /Catalog /Pages {} 0 R >>", PAGES_ID)?;
+        self.end_object()?;
+
+        // 相互参照表とTrailerを作成
+        let xref_offset = self.out.written;
+        write!(
+            self.out,
+            "xref\n0 {}\n0000000000 65535 f \n",
+            self.offsets.len() + 1
+        )?;
+        for offset in &self.offsets {
+            let offset = offset.context(Error::UnwrittenObject)?;
+            writeln!(self.out, "{:010} 00000 n ", offset)?;
+        }
+        write!(
+            self.out,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            CATALOG_ID,
+            xref_offset
+        )?;
+
+        self.out.flush().context(Error::SaveOutput)?;
+        Ok((self.out.inner, self.out.written))
+    }
+
+    fn begin_object(&mut self, id: usize) -> Result<()> {
+        if self.offsets.len() < id {
+            self.offsets.resize(id, None);
+        }
+        self.offsets[id - 1] = Some(self.out.written);
+        writeln!(self.out, "{} 0 obj", id)?;
+        Ok(())
+    }
+
+    fn end_object(&mut self) -> Result<()> {
+        self.out.write_all(b"\nendobj\n")?;
+        Ok(())
+    }
+
+    fn write_stream(&mut self, data: &[u8]) -> Result<()> {
+        self.out.write_all(b"\nstream\n")?;
+        self.out.write_all(data)?;
+        self.out.write_all(b"\nendstream")?;
+        Ok(())
+    }
+}
+
+/// PDFの実数表現に変換する（指数表記を使わず、末尾の0を除去）
+fn real(value: f32) -> String {
+    let s = format!("{:.4}", value);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s.is_empty() || s == "-" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::{Document, Object};
+
+    fn page(width: u32, height: u32, encoding: ImageEncoding) -> RenderedPage {
+        RenderedPage {
+            data: vec![0xAB; 16],
+            width,
+            height,
+            color_space: ColorSpace::Rgb,
+            encoding,
+        }
+    }
+
+    fn write_pdf(pages: &[(RenderedPage, PageLayout)]) -> Vec<u8> {
+        let mut writer = PdfWriter::new(Vec::new()).unwrap();
+        for (page, layout) in pages {
+            writer.add_page(page, layout).unwrap();
+        }
+        let (data, bytes) = writer.finish().unwrap();
+        assert_eq!(bytes, data.len() as u64);
+        data
+    }
+
+    /// `startxref`と相互参照表が指すオフセットを読み取る
+    ///
+    /// ヘッダーのコメントはUTF-8ではないため、相互参照表以降だけを文字列にする。
+    fn xref_offsets(data: &[u8]) -> Vec<usize> {
+        let marker = data
+            .windows(10)
+            .rposition(|window| window == b"startxref\n")
+            .expect("startxref");
+        let startxref: usize = std::str::from_utf8(&data[marker + 10..])
+            .unwrap()
+            .lines()
+            .next()
+            .and_then(|line| line.parse().ok())
+            .expect("startxref");
+
+        let xref = std::str::from_utf8(&data[startxref..]).unwrap();
+        assert!(xref.starts_with("xref\n"));
+        xref.lines()
+            .skip(3) // `xref`、範囲、オブジェクト0
+            .take_while(|line| line.ends_with(" n "))
+            .map(|line| line[..10].parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn writes_a_pdf_that_lopdf_can_read() {
+        let data = write_pdf(&[
+            (
+                page(200, 100, ImageEncoding::Jpeg),
+                PageLayout::from_dpi(200, 100, 72.0),
+            ),
+            (
+                page(150, 300, ImageEncoding::Flate),
+                PageLayout::from_dpi(150, 300, 144.0),
+            ),
+        ]);
+        assert!(data.starts_with(b"%PDF-1.5\n"));
+        assert!(data.ends_with(b"%%EOF\n"));
+
+        let document = Document::load_mem(&data).unwrap();
+        let pages = document.get_pages();
+        assert_eq!(pages.len(), 2);
+
+        let media_box = |number: u32| {
+            let page = document.get_dictionary(pages[&number]).unwrap();
+            page.get(b"MediaBox")
+                .and_then(Object::as_array)
+                .unwrap()
+                .iter()
+                .map(|value| value.as_float().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(media_box(1), vec![0.0, 0.0, 200.0, 100.0]);
+        assert_eq!(media_box(2), vec![0.0, 0.0, 75.0, 150.0]);
+
+        let images: Vec<_> = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_stream().ok())
+            .filter(|stream| stream.dict.has(b"Width"))
+            .map(|stream| {
+                let dict = &stream.dict;
+                (
+                    dict.get(b"Width").and_then(Object::as_i64).unwrap(),
+                    dict.get(b"Filter").and_then(Object::as_name).unwrap(),
+                    stream.content.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(images.len(), 2);
+        assert!(images.contains(&(200, &b"DCTDecode"[..], vec![0xAB; 16])));
+        assert!(images.contains(&(150, &b"FlateDecode"[..], vec![0xAB; 16])));
+    }
+
+    #[test]
+    fn xref_offsets_point_at_their_objects() {
+        let data = write_pdf(&[
+            (
+                page(10, 10, ImageEncoding::Jpeg),
+                PageLayout::from_dpi(10, 10, 72.0),
+            ),
+            (
+                page(20, 20, ImageEncoding::Jpeg),
+                PageLayout::from_dpi(20, 20, 72.0),
+            ),
+        ]);
+        let offsets = xref_offsets(&data);
+        // Catalog・Pagesと、1ページごとに3つのオブジェクト
+        assert_eq!(offsets.len(), 2 + 2 * OBJECTS_PER_PAGE);
+        for (index, offset) in offsets.into_iter().enumerate() {
+            let header = format!("{} 0 obj\n", index + 1);
+            assert!(
+                data[offset..].starts_with(header.as_bytes()),
+                "object {} is not at offset {}",
+                index + 1,
+                offset
+            );
+        }
+    }
+
+    #[test]
+    fn places_the_image_with_the_layout() {
+        let layout = PageLayout {
+            page_width: 595.276,
+            page_height: 841.89,
+            image_x: 0.0,
+            image_y: 101.445,
+            image_width: 595.276,
+            image_height: 639.0,
+        };
+        let data = write_pdf(&[(page(10, 10, ImageEncoding::Jpeg), layout)]);
+        let text = String::from_utf8_lossy(&data);
+        assert!(text.contains("/MediaBox [0 0 595.276 841.89]"));
+        assert!(text.contains("q\n595.276 0 0 639 0 101.445 cm\n/Im0 Do\nQ"));
+    }
+
+    #[test]
+    fn empty_document_has_no_pages() {
+        let data = write_pdf(&[]);
+        let document = Document::load_mem(&data).unwrap();
+        assert!(document.get_pages().is_empty());
+        assert_eq!(xref_offsets(&data).len(), 2);
+    }
+
+    #[test]
+    fn real_numbers_avoid_exponents_and_trailing_zeros() {
+        assert_eq!(real(612.0), "612");
+        assert_eq!(real(0.5), "0.5");
+        assert_eq!(real(101.445), "101.445");
+        assert_eq!(real(1.0 / 3.0), "0.3333");
+        assert_eq!(real(-12.25), "-12.25");
+        assert_eq!(real(0.0), "0");
+        assert_eq!(real(1e-7), "0");
+        assert_eq!(real(1e9), "1000000000");
+    }
+}